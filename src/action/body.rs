@@ -1,5 +1,5 @@
 use crate::{cli::BodyCmd as Cmd, validator, Ctx, QuartzResult};
-use std::io::Write;
+use std::io::{Read, Write};
 
 const POSSIBLE_EXT: [&str; 3] = ["json", "html", "xml"];
 
@@ -27,7 +27,7 @@ pub fn print(ctx: &Ctx) {
     let (_, mut endpoint) = ctx.require_endpoint();
 
     if let Some(body) = endpoint.body() {
-        print!("{body}");
+        let _ = std::io::stdout().write_all(body);
     }
 }
 
@@ -74,11 +74,9 @@ pub fn edit(ctx: &Ctx, format: Option<String>) -> QuartzResult {
 pub fn stdin(ctx: &Ctx) {
     let handle = ctx.require_handle();
 
-    let mut input = String::new();
-    while let Ok(bytes) = std::io::stdin().read_line(&mut input) {
-        if bytes == 0 {
-            break;
-        }
+    let mut input = Vec::new();
+    if std::io::stdin().read_to_end(&mut input).is_err() {
+        panic!("could not read standard input");
     }
 
     if let Ok(mut file) = std::fs::OpenOptions::new()
@@ -87,6 +85,6 @@ pub fn stdin(ctx: &Ctx) {
         .truncate(true)
         .open(handle.dir(ctx).join("body"))
     {
-        let _ = file.write_all(input.as_bytes());
+        let _ = file.write_all(&input);
     }
 }
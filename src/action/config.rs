@@ -27,6 +27,7 @@ pub fn get(ctx: &Ctx, args: GetArgs) {
         "preferences.editor" => ctx.config.preferences.editor(),
         "preferences.pager" => ctx.config.preferences.pager(),
         "ui.colors" => ctx.config.ui.colors().to_string(),
+        "history.max_body_size" => ctx.config.history.max_body_size().to_string(),
         _ => panic!("invalid key"),
     };
 
@@ -47,6 +48,11 @@ pub fn set(ctx: &mut Ctx, args: SetArgs) {
             .config
             .ui
             .set_colors(matches!(args.value.as_str(), "true")),
+        "history.max_body_size" => ctx.config.history.set_max_body_size(
+            args.value
+                .parse()
+                .unwrap_or_else(|_| panic!("invalid number of bytes: {}", args.value)),
+        ),
         _ => panic!("invalid key"),
     };
 
@@ -1,4 +1,13 @@
-use crate::{cookie::Cookie, Ctx};
+use std::io::Read;
+use std::path::PathBuf;
+
+use crate::{
+    cli::CookieCmd as Cmd,
+    cookie::{Cookie, CookieJar, Domain},
+    env::Env,
+    Ctx, QuartzResult,
+};
+use colored::Colorize;
 
 #[derive(clap::Args, Debug)]
 pub struct PrintArgs {
@@ -9,12 +18,121 @@ pub struct PrintArgs {
     domain: Option<String>,
 }
 
+#[derive(clap::Args, Debug)]
+pub struct SetArgs {
+    #[arg(name = "NAME=VALUE")]
+    cookie: String,
+
+    /// Domain the cookie is sent to
+    #[arg(long, short = 'd')]
+    domain: String,
+
+    /// Only send the cookie to requests under this path
+    #[arg(long, default_value = "/")]
+    path: String,
+
+    /// Expiration date, such as "Wed, 21 Oct 2015 07:28:00 GMT"
+    #[arg(long, value_name = "DATE", conflicts_with = "max_age")]
+    expires: Option<String>,
+
+    /// Number of seconds until the cookie expires
+    #[arg(long, value_name = "SECONDS")]
+    max_age: Option<i64>,
+
+    /// Only send the cookie over HTTPS
+    #[arg(long)]
+    secure: bool,
+
+    /// Mark the cookie as HttpOnly
+    #[arg(long)]
+    http_only: bool,
+
+    /// Also send the cookie to subdomains
+    #[arg(long)]
+    subdomains: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RmArgs {
+    #[arg(name = "NAME", required = true)]
+    names: Vec<String>,
+
+    /// Only remove cookies that match this domain
+    #[arg(long, short = 'd')]
+    domain: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Format {
+    /// Netscape HTTP Cookie file, as used by cURL
+    Netscape,
+    /// JSON array of cookies, as used by browser extensions
+    Json,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ImportArgs {
+    /// Cookie file to import. Reads from standard input when "-" or omitted
+    file: Option<PathBuf>,
+
+    /// Environment to import cookies into, instead of the current one
+    #[arg(long, short = 'e')]
+    env: Option<String>,
+
+    /// File format. Guessed from the file content when omitted
+    #[arg(long, short = 'f')]
+    format: Option<Format>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ExportArgs {
+    /// File to write cookies to. Writes to standard output when omitted
+    file: Option<PathBuf>,
+
+    /// Environment to export cookies from, instead of the current one
+    #[arg(long, short = 'e')]
+    env: Option<String>,
+
+    /// File format. Guessed from the file extension when omitted
+    #[arg(long, short = 'f')]
+    format: Option<Format>,
+}
+
+pub fn cmd(ctx: &Ctx, command: Cmd) -> QuartzResult {
+    match command {
+        Cmd::Set(args) => set(ctx, args)?,
+        Cmd::Rm(args) => rm(ctx, args)?,
+        Cmd::Clear => clear(ctx)?,
+        Cmd::Import(args) => import(ctx, args)?,
+        Cmd::Export(args) => export(ctx, args)?,
+        Cmd::Ls(args) => print(ctx, args),
+    };
+
+    Ok(())
+}
+
+/// Environment named `name`, or the current one.
+fn require_env(ctx: &Ctx, name: Option<&str>) -> Env {
+    match name {
+        Some(name) => Env::parse(ctx, name)
+            .ok()
+            .filter(|env| env.exists(ctx))
+            .unwrap_or_else(|| panic!("could not resolve {} environment", name.red())),
+        None => ctx.require_env(),
+    }
+}
+
+/// Whether `cookie` was set for exactly `domain`, not one of its parents or subdomains.
+fn is_domain(cookie: &Cookie, domain: &str) -> bool {
+    cookie.domain().trim_start_matches('.') == Domain::canonicalize(domain).trim_start_matches('.')
+}
+
 pub fn print(ctx: &Ctx, args: PrintArgs) {
     let jar = ctx.require_env().cookie_jar(ctx);
 
     let iter = jar.iter().filter(|c| {
         if let Some(domain) = &args.domain {
-            c.domain().matches(domain.as_str())
+            is_domain(c, domain)
         } else {
             true
         }
@@ -38,3 +156,138 @@ pub fn print(ctx: &Ctx, args: PrintArgs) {
         }
     }
 }
+
+pub fn set(ctx: &Ctx, args: SetArgs) -> QuartzResult {
+    let mut jar = ctx.require_env().cookie_jar(ctx);
+
+    let (name, value) = args
+        .cookie
+        .split_once('=')
+        .unwrap_or_else(|| panic!("malformed cookie. Expected NAME=VALUE"));
+
+    let mut cookie = Cookie::builder();
+    cookie
+        .domain(args.domain.trim_start_matches('.'))
+        .subdomains(args.subdomains || args.domain.starts_with('.'))
+        .path(args.path)
+        .secure(args.secure)
+        .http_only(args.http_only)
+        .name(name.trim())
+        .value(value.trim());
+
+    if let Some(seconds) = args.max_age {
+        let now = chrono::Utc::now().timestamp_micros();
+        cookie.expires_at(now.saturating_add(seconds.saturating_mul(1_000_000)).max(1));
+    } else if let Some(date) = args.expires {
+        let date = CookieJar::parse_date(&date)
+            .unwrap_or_else(|| panic!("malformed expiration date: {date}"));
+        cookie.expires_at(date.timestamp_micros().max(1));
+    }
+
+    let cookie = cookie
+        .build()
+        .unwrap_or_else(|_| panic!("malformed cookie: {}", args.cookie));
+
+    if cookie.expired() {
+        jar.remove(&cookie);
+    } else {
+        jar.replace(cookie);
+    }
+
+    jar.write()?;
+    Ok(())
+}
+
+pub fn rm(ctx: &Ctx, args: RmArgs) -> QuartzResult {
+    let mut jar = ctx.require_env().cookie_jar(ctx);
+
+    for name in &args.names {
+        let before = jar.len();
+
+        jar.retain(|c| {
+            let domain = match &args.domain {
+                Some(domain) => is_domain(c, domain),
+                None => true,
+            };
+
+            !(domain && c.name() == name)
+        });
+
+        if jar.len() == before {
+            panic!("{name}: No such cookie");
+        }
+    }
+
+    jar.write()?;
+    Ok(())
+}
+
+pub fn clear(ctx: &Ctx) -> QuartzResult {
+    let mut jar = ctx.require_env().cookie_jar(ctx);
+
+    jar.clear();
+    jar.write()?;
+
+    Ok(())
+}
+
+pub fn import(ctx: &Ctx, args: ImportArgs) -> QuartzResult {
+    let env = require_env(ctx, args.env.as_deref());
+    let mut jar = env.cookie_jar(ctx);
+
+    let content = match &args.file {
+        Some(path) if path.as_os_str() != "-" => std::fs::read_to_string(path)
+            .unwrap_or_else(|_| panic!("no such file: {}", path.display())),
+        _ => {
+            let mut content = String::new();
+            std::io::stdin().read_to_string(&mut content)?;
+            content
+        }
+    };
+
+    let format = args.format.unwrap_or_else(|| {
+        if content.trim_start().starts_with('[') {
+            Format::Json
+        } else {
+            Format::Netscape
+        }
+    });
+
+    let imported = match format {
+        Format::Netscape => CookieJar::parse(&content),
+        Format::Json => CookieJar::from_json(&content)
+            .unwrap_or_else(|err| panic!("malformed JSON cookie file: {err}")),
+    };
+
+    for cookie in imported.iter() {
+        jar.replace(cookie.clone());
+    }
+
+    jar.write()?;
+    Ok(())
+}
+
+pub fn export(ctx: &Ctx, args: ExportArgs) -> QuartzResult {
+    let env = require_env(ctx, args.env.as_deref());
+    let jar = env.cookie_jar(ctx);
+
+    let format =
+        args.format.unwrap_or_else(
+            || match args.file.as_ref().and_then(|path| path.extension()) {
+                Some(extension) if extension == "json" => Format::Json,
+                _ => Format::Netscape,
+            },
+        );
+
+    let content = match format {
+        Format::Netscape => jar.to_string(),
+        Format::Json => jar.to_json()? + "\n",
+    };
+
+    match args.file {
+        Some(path) => std::fs::write(path, content)?,
+        None => print!("{content}"),
+    };
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use crate::{
+    action::run,
+    cli::ExportCmd as Cmd,
+    endpoint::{Endpoint, EndpointHandle},
+    history::{Entry, History},
+    http_file, openapi, Ctx, QuartzResult,
+};
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Format {
+    Json,
+    Yaml,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct OpenapiArgs {
+    /// File to write the specification to. Writes to standard output when omitted
+    file: Option<PathBuf>,
+
+    /// Only export endpoints under this handle
+    #[arg(long)]
+    handle: Option<String>,
+
+    /// Guessed from the file extension when omitted, defaulting to JSON
+    #[arg(long, short = 'f', value_enum)]
+    format: Option<Format>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct HttpArgs {
+    /// Only export endpoints under this handle
+    handle: Option<String>,
+
+    /// File to write the requests to. Writes to standard output when omitted
+    #[arg(long, short = 'o', value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+pub fn cmd(ctx: &Ctx, command: Cmd) -> QuartzResult {
+    match command {
+        Cmd::Openapi(args) => openapi(ctx, args)?,
+        Cmd::Http(args) => http(ctx, args)?,
+    };
+
+    Ok(())
+}
+
+pub fn openapi(ctx: &Ctx, args: OpenapiArgs) -> QuartzResult {
+    let (root, title) = match &args.handle {
+        Some(handle) => (ctx.require_input_handle(handle), handle.to_owned()),
+        None => (EndpointHandle::QUARTZ, project_name(ctx)),
+    };
+
+    let endpoints = resolved_endpoints(ctx, &root);
+
+    let env = ctx.require_env();
+    let history = latest_entries(ctx, &endpoints);
+    let spec = openapi::Spec::export(&title, endpoints, &history, &env.variables);
+
+    let yaml = args
+        .format
+        .map(|f| matches!(f, Format::Yaml))
+        .unwrap_or_else(|| {
+            args.file
+                .as_ref()
+                .and_then(|path| path.extension())
+                .is_some_and(|ext| ext == "yaml" || ext == "yml")
+        });
+
+    let content = if yaml { spec.to_yaml() } else { spec.to_json() };
+
+    match &args.file {
+        Some(path) => std::fs::write(path, content)?,
+        None => println!("{content}"),
+    };
+
+    Ok(())
+}
+
+pub fn http(ctx: &Ctx, args: HttpArgs) -> QuartzResult {
+    let root = match &args.handle {
+        Some(handle) => ctx.require_input_handle(handle),
+        None => EndpointHandle::QUARTZ,
+    };
+
+    let content = http_file::write(resolved_endpoints(ctx, &root));
+
+    match &args.output {
+        Some(path) => std::fs::write(path, content)?,
+        None => print!("{content}"),
+    };
+
+    Ok(())
+}
+
+/// Endpoints under `root`, with URLs inherited from their parents already resolved.
+fn resolved_endpoints(ctx: &Ctx, root: &EndpointHandle) -> Vec<(EndpointHandle, Endpoint)> {
+    run::collect(ctx, root)
+        .into_iter()
+        .map(|(handle, mut endpoint)| {
+            endpoint.resolve_url();
+            (handle, endpoint)
+        })
+        .collect()
+}
+
+/// Most recent history entry of each of `endpoints`.
+fn latest_entries(ctx: &Ctx, endpoints: &[(EndpointHandle, Endpoint)]) -> HashMap<String, Entry> {
+    let handles: HashSet<String> = endpoints
+        .iter()
+        .map(|(handle, _)| handle.handle())
+        .collect();
+    let mut map = HashMap::new();
+
+    if let Ok(history) = History::new(ctx) {
+        // Older entries are only read while some handle has none yet
+        for entry in history.iter(ctx) {
+            if map.len() == handles.len() {
+                break;
+            }
+
+            if handles.contains(entry.handle()) {
+                map.entry(entry.handle().to_string()).or_insert(entry);
+            }
+        }
+    }
+
+    map
+}
+
+/// Name of the directory containing the quartz project.
+fn project_name(ctx: &Ctx) -> String {
+    ctx.path()
+        .parent()
+        .and_then(|dir| dir.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or(String::from("quartz"))
+}
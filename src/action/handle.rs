@@ -1,7 +1,9 @@
 use std::collections::VecDeque;
+use std::io::Read;
 use std::process::ExitCode;
 
 use crate::{
+    curl,
     endpoint::{Endpoint, EndpointHandle, EndpointPatch},
     validator, Ctx, QuartzResult, StateField,
 };
@@ -17,6 +19,10 @@ pub struct CreateArgs {
     /// Immediatly switches to this handle after creating it
     #[arg(name = "use", long)]
     switch: bool,
+
+    /// Build the endpoint from a cURL command. Reads from standard input when "-"
+    #[arg(long, value_name = "COMMAND")]
+    from_curl: Option<String>,
 }
 
 #[derive(clap::Args, Debug)]
@@ -68,7 +74,14 @@ pub fn create(ctx: &Ctx, mut args: CreateArgs) {
         panic!("endpoint already exists");
     }
 
-    let mut endpoint = Endpoint::from(&mut args.patch);
+    let mut endpoint = match &args.from_curl {
+        Some(command) => {
+            let mut endpoint = from_curl(command);
+            endpoint.update(&mut args.patch);
+            endpoint
+        }
+        None => Endpoint::from(&mut args.patch),
+    };
     endpoint.set_handle(ctx, &handle);
 
     if args.switch {
@@ -81,6 +94,31 @@ pub fn create(ctx: &Ctx, mut args: CreateArgs) {
 
     handle.write(ctx);
     endpoint.write();
+
+    if let Some(body) = &endpoint.body {
+        std::fs::write(handle.dir(ctx).join("body"), body)
+            .unwrap_or_else(|_| panic!("failed to write body"));
+    }
+}
+
+/// Parses `command`, or standard input when it is "-", printing options it could not convert.
+fn from_curl(command: &str) -> Endpoint {
+    let command = if command == "-" {
+        let mut input = String::new();
+        std::io::stdin()
+            .read_to_string(&mut input)
+            .unwrap_or_else(|err| panic!("could not read standard input: {err}"));
+        input
+    } else {
+        command.to_owned()
+    };
+
+    let command = curl::parse(&command).unwrap_or_else(|err| panic!("{err}"));
+    for warning in &command.warnings {
+        eprintln!("{}: {warning}", "warning".yellow().bold());
+    }
+
+    command.endpoint
 }
 
 pub fn switch(ctx: &mut Ctx, mut args: SwitchArgs) {
@@ -103,6 +141,7 @@ pub fn switch(ctx: &mut Ctx, mut args: SwitchArgs) {
                         handle: handle.handle(),
                         patch: args.patch,
                         switch: true,
+                        from_curl: None,
                     },
                 );
             } else {
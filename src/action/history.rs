@@ -1,13 +1,41 @@
-use crate::{history::History, Ctx, QuartzResult};
+use std::path::PathBuf;
+
+use crate::{cli::HistoryCmd as Cmd, har, history::History, Ctx, QuartzResult};
 
 #[derive(clap::Args, Debug)]
 pub struct Args {
+    #[command(subcommand)]
+    command: Option<Cmd>,
+
     /// Maximum number of requests to be listed
     #[arg(short = 'n', long, value_name = "N")]
     max_count: Option<usize>,
 }
 
+#[derive(clap::Args, Debug)]
+pub struct ExportArgs {
+    /// Write entries as an HTTP Archive (HAR) 1.2
+    #[arg(long, required = true)]
+    har: bool,
+
+    /// File to write the archive to. Writes to standard output when omitted
+    #[arg(long, short = 'o', value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Maximum number of history entries to export, starting from the most recent
+    #[arg(short = 'n', long, value_name = "N")]
+    max_count: Option<usize>,
+
+    /// Only export entries of this handle
+    #[arg(long)]
+    handle: Option<String>,
+}
+
 pub fn cmd(ctx: &Ctx, args: Args) -> QuartzResult {
+    if let Some(Cmd::Export(args)) = args.command {
+        return export(ctx, args);
+    }
+
     let history = History::new(ctx)?;
     let mut count = 0;
     let max_count = args.max_count.unwrap_or(usize::MAX);
@@ -31,3 +59,25 @@ pub fn cmd(ctx: &Ctx, args: Args) -> QuartzResult {
 
     Ok(())
 }
+
+pub fn export(ctx: &Ctx, args: ExportArgs) -> QuartzResult {
+    let history = History::new(ctx)?;
+
+    let mut entries: Vec<_> = history
+        .entries(ctx)
+        .into_iter()
+        .filter(|entry| args.handle.as_ref().is_none_or(|h| h == entry.handle()))
+        .take(args.max_count.unwrap_or(usize::MAX))
+        .collect();
+
+    // Archives list entries in the order they were sent
+    entries.reverse();
+
+    let content = serde_json::to_string_pretty(&har::export(&entries))?;
+    match &args.output {
+        Some(path) => std::fs::write(path, content)?,
+        None => println!("{content}"),
+    };
+
+    Ok(())
+}
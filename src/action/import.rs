@@ -0,0 +1,332 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    cli::ImportCmd as Cmd,
+    collection::{slug, Collection},
+    endpoint::{self, EndpointHandle},
+    env::Env,
+    har, http_file, insomnia,
+    openapi::{self, GroupBy},
+    postman, Ctx, QuartzResult,
+};
+use colored::Colorize;
+use regex::Regex;
+
+/// Where imported endpoints are written to.
+#[derive(clap::Args, Debug)]
+pub struct TargetArgs {
+    /// Handle to create the endpoints under
+    #[arg(long, value_name = "HANDLE")]
+    into: Option<String>,
+
+    /// List the endpoints that would be created, without writing them
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Overwrite endpoints of handles that already exist
+    #[arg(long)]
+    force: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct OpenapiArgs {
+    /// OpenAPI 3 specification, in JSON or YAML. Reads from standard input when "-"
+    file: PathBuf,
+
+    /// How operations are organized into handles
+    #[arg(long, value_enum, default_value = "tags")]
+    group_by: GroupBy,
+
+    #[command(flatten)]
+    target: TargetArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct PostmanArgs {
+    /// Collection file. Reads from standard input when "-"
+    file: PathBuf,
+
+    /// Postman environment file to create a quartz environment from
+    #[arg(long, value_name = "FILE")]
+    env: Option<PathBuf>,
+
+    #[command(flatten)]
+    target: TargetArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct InsomniaArgs {
+    /// Export file, in JSON or YAML. Reads from standard input when "-"
+    file: PathBuf,
+
+    #[command(flatten)]
+    target: TargetArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct HttpArgs {
+    /// File in the REST Client or JetBrains HTTP Client format. Reads from standard input when "-"
+    file: PathBuf,
+
+    #[command(flatten)]
+    target: TargetArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct HarArgs {
+    /// HTTP Archive file. Reads from standard input when "-"
+    file: PathBuf,
+
+    /// Only import requests whose URL matches this regular expression
+    #[arg(long, value_name = "REGEX")]
+    url: Option<Regex>,
+
+    /// Only import requests with this method
+    #[arg(long, short = 'X')]
+    method: Option<String>,
+
+    /// Import a single request per method and URL, where IDs in the path become variables
+    #[arg(long)]
+    dedupe: bool,
+
+    #[command(flatten)]
+    target: TargetArgs,
+}
+
+pub fn cmd(ctx: &Ctx, command: Cmd) -> QuartzResult {
+    match command {
+        Cmd::Openapi(args) => openapi(ctx, args)?,
+        Cmd::Postman(args) => postman(ctx, args)?,
+        Cmd::Insomnia(args) => insomnia(ctx, args)?,
+        Cmd::Http(args) => http(ctx, args)?,
+        Cmd::Har(args) => har(ctx, args)?,
+    };
+
+    Ok(())
+}
+
+pub fn openapi(ctx: &Ctx, args: OpenapiArgs) -> QuartzResult {
+    let spec = openapi::Spec::parse(&read(&args.file)).unwrap_or_else(|err| panic!("{err}"));
+
+    let name = spec.title().map(slug).unwrap_or_default();
+    let root = args
+        .target
+        .root(if name.is_empty() { "openapi" } else { &name });
+
+    write(ctx, spec.collection(&root, args.group_by), &args.target)
+}
+
+pub fn postman(ctx: &Ctx, args: PostmanArgs) -> QuartzResult {
+    let document =
+        postman::Document::parse(&read(&args.file)).unwrap_or_else(|err| panic!("{err}"));
+
+    if let Some(path) = &args.env {
+        let env = postman::environment(&read(path)).unwrap_or_else(|err| panic!("{err}"));
+        write_env(ctx, env, &args.target)?;
+    }
+
+    let name = document.name().map(slug).unwrap_or_default();
+    let root = args
+        .target
+        .root(if name.is_empty() { "postman" } else { &name });
+
+    write(ctx, document.collection(&root), &args.target)
+}
+
+pub fn insomnia(ctx: &Ctx, args: InsomniaArgs) -> QuartzResult {
+    let document =
+        insomnia::Document::parse(&read(&args.file)).unwrap_or_else(|err| panic!("{err}"));
+
+    for env in document.environments() {
+        write_env(ctx, env, &args.target)?;
+    }
+
+    let name = document.name().map(slug).unwrap_or_default();
+    let root = args
+        .target
+        .root(if name.is_empty() { "insomnia" } else { &name });
+
+    write(ctx, document.collection(&root), &args.target)
+}
+
+pub fn http(ctx: &Ctx, args: HttpArgs) -> QuartzResult {
+    // Requests are named by their full handle, so they are created at the top level by default
+    let root = args
+        .target
+        .into
+        .as_deref()
+        .map(EndpointHandle::from)
+        .unwrap_or(EndpointHandle::QUARTZ);
+
+    write(
+        ctx,
+        http_file::parse(&read(&args.file), &root),
+        &args.target,
+    )
+}
+
+pub fn har(ctx: &Ctx, args: HarArgs) -> QuartzResult {
+    let document = har::Document::parse(&read(&args.file)).unwrap_or_else(|err| panic!("{err}"));
+
+    let filter = har::Filter {
+        url: args.url,
+        method: args.method,
+        dedupe: args.dedupe,
+    };
+
+    write(
+        ctx,
+        document.collection(&args.target.root("har"), &filter),
+        &args.target,
+    )
+}
+
+impl TargetArgs {
+    /// Handle given by the user, or `default` otherwise.
+    fn root(&self, default: &str) -> EndpointHandle {
+        EndpointHandle::from(self.into.as_deref().unwrap_or(default))
+    }
+}
+
+/// Reads the file at `path`, or standard input when it is "-".
+fn read(path: &Path) -> String {
+    if path.as_os_str() == "-" {
+        let mut content = String::new();
+        std::io::stdin()
+            .read_to_string(&mut content)
+            .unwrap_or_else(|err| panic!("could not read standard input: {err}"));
+
+        return content;
+    }
+
+    std::fs::read_to_string(path).unwrap_or_else(|_| panic!("no such file: {}", path.display()))
+}
+
+/// Creates a handle for each endpoint of `collection` and sets its variables on the current
+/// environment, keeping values that are already defined.
+fn write(ctx: &Ctx, mut collection: Collection, target: &TargetArgs) -> QuartzResult {
+    for warning in &collection.warnings {
+        eprintln!("{}: {warning}", "warning".yellow().bold());
+    }
+
+    // Names taken from the source could otherwise write files outside of the project
+    collection.endpoints.retain(|(handle, _)| {
+        if !handle.is_valid() {
+            eprintln!(
+                "{}: skipping {}, which is not a valid handle",
+                "warning".yellow().bold(),
+                handle.handle()
+            );
+        }
+
+        handle.is_valid()
+    });
+
+    if target.dry_run {
+        let padding = collection
+            .endpoints
+            .iter()
+            .map(|(_, endpoint)| endpoint.method.len())
+            .max()
+            .unwrap_or_default();
+
+        for (handle, endpoint) in &collection.endpoints {
+            println!(
+                "{:<padding$} {} {}",
+                endpoint::colored_method(&endpoint.method).bold(),
+                handle.handle(),
+                endpoint.url.dimmed(),
+            );
+        }
+
+        for (key, value) in collection.variables.iter() {
+            println!("{} {key}={value}", "var".dimmed());
+        }
+
+        return Ok(());
+    }
+
+    let mut count = 0;
+    for (handle, mut endpoint) in collection.endpoints {
+        if handle.endpoint(ctx).is_some() && !target.force {
+            eprintln!(
+                "{}: skipping {}, which already exists",
+                "warning".yellow().bold(),
+                handle.handle()
+            );
+            continue;
+        }
+
+        endpoint.set_handle(ctx, &handle);
+        handle.write(ctx);
+        endpoint.write();
+
+        let body = handle.dir(ctx).join("body");
+        match &endpoint.body {
+            Some(content) => std::fs::write(body, content)?,
+            None => {
+                let _ = std::fs::remove_file(body);
+            }
+        }
+
+        count += 1;
+    }
+
+    if !collection.variables.is_empty() {
+        let mut env = ctx.require_env();
+
+        for (key, value) in collection.variables.0 {
+            env.variables.entry(key).or_insert(value);
+        }
+
+        env.update(ctx)?;
+    }
+
+    println!("Imported {count} endpoints");
+
+    Ok(())
+}
+
+/// Creates `env`, or adds its variables to the existing environment of the same name. Variables
+/// that are already defined are only replaced with `--force`.
+fn write_env(ctx: &Ctx, env: Env, target: &TargetArgs) -> QuartzResult {
+    if matches!(env.name.trim(), "" | "." | "..") || env.name.contains(['/', '\\']) {
+        eprintln!(
+            "{}: skipping {} environment, which is not a valid name",
+            "warning".yellow().bold(),
+            env.name
+        );
+
+        return Ok(());
+    }
+
+    if target.dry_run {
+        println!("{} {}", "env".dimmed(), env.name);
+
+        for (key, value) in env.variables.iter() {
+            println!("{} {key}={value}", "var".dimmed());
+        }
+
+        return Ok(());
+    }
+
+    if !env.exists(ctx) {
+        env.write(ctx)?;
+        println!("Created {} environment", env.name.green());
+
+        return Ok(());
+    }
+
+    let mut existing = Env::parse(ctx, &env.name)?;
+    for (key, value) in env.variables.0 {
+        if target.force || !existing.variables.contains_key(&key) {
+            existing.variables.insert(key, value);
+        }
+    }
+
+    existing.update(ctx)?;
+    println!("Updated {} environment", env.name.green());
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use std::convert::Infallible;
+use std::io::Write;
 
 use crate::{
     cli::LastCmd as Cmd,
@@ -34,7 +35,9 @@ pub fn req_head(entry: &history::Entry) {
     let iter = entry
         .messages()
         .iter()
-        .filter_map(|p| match p.starts_with('>') {
+        .enumerate()
+        .filter(|(i, _)| !entry.is_body(*i))
+        .filter_map(|(_, p)| match p.starts_with('>') {
             true => Some(
                 p.split('\n')
                     .map(|s| s.trim_start_matches('>').trim())
@@ -65,7 +68,9 @@ pub fn res_head(entry: &history::Entry) {
     let iter = entry
         .messages()
         .iter()
-        .filter_map(|p| match p.starts_with('<') {
+        .enumerate()
+        .filter(|(i, _)| !entry.is_body(*i))
+        .filter_map(|(_, p)| match p.starts_with('<') {
             true => Some(
                 p.split('\n')
                     .map(|s| s.trim_start_matches('<').trim())
@@ -81,7 +86,17 @@ pub fn res_head(entry: &history::Entry) {
 }
 
 pub fn res_body(entry: &history::Entry) {
-    if let Some(body) = entry.messages().last() {
-        println!("{}", body);
+    // Without a response, the last message holds why the request failed
+    let index = entry
+        .response_body_index()
+        .unwrap_or(entry.messages().len().saturating_sub(1));
+
+    match entry.message_bytes(index) {
+        // Binary bodies are written exactly as received
+        Some(body) if entry.is_binary(index) => {
+            let _ = std::io::stdout().write_all(&body);
+        }
+        Some(body) => println!("{}", String::from_utf8_lossy(&body)),
+        None => (),
     }
 }
@@ -169,8 +169,8 @@ pub fn cmd(ctx: &Ctx, args: Args) {
             output_list.push(output);
         }
 
-        for child in node.children.iter() {
-            queue.push(&child);
+        for child in node.children.iter().rev() {
+            queue.push(child);
         }
     }
 
@@ -6,16 +6,20 @@ pub mod body;
 pub mod config;
 pub mod cookie;
 pub mod env;
+pub mod export;
 pub mod handle;
 pub mod header;
 pub mod history;
+pub mod import;
 pub mod init;
 pub mod last;
 pub mod ls;
 pub mod query;
+pub mod run;
 pub mod send;
 pub mod show;
 pub mod snippet;
+pub mod test;
 pub mod var;
 
 pub async fn cmd(ctx: &mut Ctx, command: Cmd) -> QuartzResult {
@@ -23,6 +27,8 @@ pub async fn cmd(ctx: &mut Ctx, command: Cmd) -> QuartzResult {
         Cmd::Init(_) => (), // Init is only run on main, before ctx is resolved
 
         Cmd::Send(args) => action::send::cmd(ctx, args).await?,
+        Cmd::Test(args) => action::test::cmd(ctx, args).await?,
+        Cmd::Run(args) => action::run::cmd(ctx, args).await?,
         Cmd::Create(args) => action::handle::create(ctx, args),
         Cmd::Use(args) => action::handle::switch(ctx, args),
         Cmd::Ls(args) => action::ls::cmd(ctx, args),
@@ -38,6 +44,9 @@ pub async fn cmd(ctx: &mut Ctx, command: Cmd) -> QuartzResult {
         Cmd::Last { command } => action::last::cmd(ctx, command)?,
         Cmd::Var { command } => action::var::cmd(ctx, command)?,
         Cmd::Env { command } => action::env::cmd(ctx, command)?,
+        Cmd::Cookie { command } => action::cookie::cmd(ctx, command)?,
+        Cmd::Import { command } => action::import::cmd(ctx, command)?,
+        Cmd::Export { command } => action::export::cmd(ctx, command)?,
         Cmd::Config { command } => action::config::cmd(ctx, command)?,
     };
 
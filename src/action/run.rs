@@ -0,0 +1,206 @@
+use std::process::ExitCode;
+use std::time::Duration;
+
+use crate::{
+    action::{send, test::ReportArgs},
+    endpoint::{self, Endpoint, EndpointHandle},
+    report::Case,
+    Ctx, QuartzResult,
+};
+use colored::Colorize;
+
+#[derive(clap::Args, Debug)]
+#[group(id = "RunArgs")]
+pub struct Args {
+    /// Handle whose endpoints are sent, including its sub-handles
+    handle: String,
+
+    /// Stop at the first request that fails or has failing assertions
+    #[arg(long)]
+    bail: bool,
+
+    /// Milliseconds to wait between requests
+    #[arg(long, value_name = "MS")]
+    delay: Option<u64>,
+
+    #[command(flatten)]
+    report: ReportArgs,
+
+    #[command(flatten)]
+    send: send::RequestArgs,
+}
+
+pub async fn cmd(ctx: &mut Ctx, args: Args) -> QuartzResult {
+    let handles = collect(ctx, &ctx.require_input_handle(&args.handle));
+
+    if handles.is_empty() {
+        panic!("no endpoints found under {}", args.handle.red());
+    }
+
+    let verbose = args.report.verbose();
+    let iterations = send::Args::from(args.send)
+        .iterations()
+        .unwrap_or_else(|err| panic!("{err}"));
+    let mut cases: Vec<Case> = Vec::new();
+
+    'run: for (n, send_args) in iterations.iter().enumerate() {
+        let iteration = (iterations.len() > 1).then_some(n + 1);
+
+        if let (Some(n), true) = (iteration, verbose) {
+            if n > 1 {
+                println!();
+            }
+
+            println!("{}", format!("Iteration {n}").bold());
+        }
+
+        for (handle, endpoint) in handles.iter() {
+            if !cases.is_empty() {
+                if let Some(delay) = args.delay {
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                }
+            }
+
+            if verbose {
+                println!(
+                    "{} {}",
+                    endpoint::colored_method(&endpoint.method).bold(),
+                    handle.handle()
+                );
+            }
+
+            // Every request reads the environment again, so captures and cookies from previous
+            // steps are available to the next ones
+            let case = Case::new(handle.handle(), iteration);
+            let case = if endpoint.is_websocket() {
+                case.skip(send::WEBSOCKET_SKIPPED)
+            } else {
+                match send::send(ctx, handle, endpoint.clone(), send_args, None).await {
+                    Ok(res) => case.response(&res, &endpoint.assertions),
+                    Err(err) => case.error(err),
+                }
+            };
+
+            if verbose {
+                case.print("  ");
+            }
+
+            let failed = case.failed();
+            cases.push(case);
+
+            if failed && args.bail {
+                break 'run;
+            }
+        }
+    }
+
+    if verbose {
+        println!();
+        print_summary(&cases, iterations.len() > 1);
+    }
+
+    args.report.write(&args.handle, &cases)?;
+
+    if cases.iter().any(|case| case.failed()) {
+        ctx.code(ExitCode::FAILURE);
+    }
+
+    Ok(())
+}
+
+/// Handles with an endpoint under `root`, including itself, in depth-first order with
+/// siblings sorted by name.
+pub fn collect(ctx: &Ctx, root: &EndpointHandle) -> Vec<(EndpointHandle, Endpoint)> {
+    let tree = root.clone().tree(ctx);
+    let mut list = Vec::new();
+    let mut stack = vec![&tree.root];
+
+    while let Some(node) = stack.pop() {
+        if let Some(endpoint) = node.value.endpoint(ctx) {
+            list.push((node.value.clone(), endpoint));
+        }
+
+        stack.extend(node.children.iter().rev());
+    }
+
+    list
+}
+
+fn print_summary(cases: &[Case], iterations: bool) {
+    let rows: Vec<[String; 5]> = cases
+        .iter()
+        .map(|case| {
+            let assertions = match (&case.error, case.checks.len()) {
+                (None, 0) | (Some(_), _) => String::from("-"),
+                (None, len) => format!("{}/{len}", len - case.failures()),
+            };
+
+            [
+                case.iteration.unwrap_or(1).to_string(),
+                case.name.clone(),
+                case.status
+                    .map(|status| status.as_u16().to_string())
+                    .unwrap_or(String::from("---")),
+                match (&case.error, &case.skipped) {
+                    (None, None) => format!("{}ms", case.elapsed.as_millis()),
+                    _ => String::from("-"),
+                },
+                assertions,
+            ]
+        })
+        .collect();
+
+    let titles = ["ITERATION", "HANDLE", "STATUS", "TIME", "ASSERTIONS"];
+    let mut widths = titles.map(str::len);
+    for row in &rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.len());
+        }
+    }
+
+    // Iteration column is only relevant when there is iteration data
+    let skip = if iterations { 0 } else { 1 };
+    let line = |values: [&str; 5]| {
+        values
+            .iter()
+            .zip(widths)
+            .skip(skip)
+            .map(|(value, width)| format!("{value:<width$}"))
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    println!("{}", line(titles).bold());
+
+    for (row, case) in rows.iter().zip(cases) {
+        let text = line(row.each_ref().map(String::as_str));
+
+        if case.failed() {
+            println!("{}", text.red());
+        } else if case.skipped.is_some() {
+            println!("{}", text.yellow());
+        } else {
+            println!("{text}");
+        }
+    }
+
+    let failed = cases.iter().filter(|case| case.failed()).count();
+    let skipped = cases.iter().filter(|case| case.skipped.is_some()).count();
+
+    let mut summary = format!(
+        "{} passed, {} failed",
+        cases.len() - failed - skipped,
+        failed
+    );
+    if skipped > 0 {
+        summary.push_str(&format!(", {skipped} skipped"));
+    }
+
+    if failed > 0 {
+        println!("\n{}", summary.red());
+    } else {
+        println!("\n{}", summary.green());
+    }
+}
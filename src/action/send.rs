@@ -1,21 +1,36 @@
 use crate::{
-    cookie::CookieJar,
-    endpoint::EndpointPatch,
+    cookie::{Cookie, CookieJar},
+    download::{self, Download, Offset},
+    endpoint::{Endpoint, EndpointHandle, EndpointPatch, Retry},
+    env::Variables,
     history::{self, History},
-    Ctx, PairMap, QuartzResult,
+    iteration,
+    request::{self, Request},
+    sse, websocket, Ctx, PairMap, QuartzResult,
 };
 use chrono::Utc;
+use colored::Colorize;
 use hyper::{
     body::{Bytes, HttpBody},
-    header::{HeaderName, HeaderValue},
-    Body, Client, Uri,
+    client::HttpConnector,
+    Body, Client, HeaderMap, StatusCode, Uri,
 };
+use hyper_tls::HttpsConnector;
+use std::fmt::Display;
+use std::future::Future;
 use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::str::FromStr;
-use tokio::io::{stdout, AsyncWriteExt as _};
+use std::time::Duration;
+use tokio::io::{stdout, AsyncWrite, AsyncWriteExt as _};
+use tokio::time::Instant;
 
-#[derive(clap::Args, Debug)]
-pub struct Args {
+/// Why `test` and `run` skip WebSocket endpoints, which need standard input to exchange messages.
+pub const WEBSOCKET_SKIPPED: &str = "websocket endpoints can only be opened by send";
+
+/// Options of every command that sends requests: `send`, `test` and `run`.
+#[derive(clap::Args, Debug, Clone)]
+pub struct RequestArgs {
     /// Change a variable when sending the request.
     #[arg(long = "var", short = 'v', value_name = "KEY=VALUE")]
     variables: Vec<String>,
@@ -34,99 +49,536 @@ pub struct Args {
     /// Which file to write all cookies after a completed request
     #[arg(long, short = 'c', value_name = "FILE")]
     cookie_jar: Option<PathBuf>,
+
+    /// Maximum time in milliseconds to establish a connection
+    #[arg(long, value_name = "MS")]
+    connect_timeout: Option<u64>,
+
+    /// Maximum time in milliseconds to wait for the response or between body chunks
+    #[arg(long, value_name = "MS")]
+    read_timeout: Option<u64>,
+
+    /// Maximum time in milliseconds for the whole exchange
+    #[arg(long, value_name = "MS")]
+    timeout: Option<u64>,
+
+    /// Maximum number of attempts when a request fails
+    #[arg(long, value_name = "N")]
+    retry: Option<u32>,
+
+    /// Milliseconds to wait before retrying. It doubles after each retry
+    #[arg(long, value_name = "MS")]
+    retry_backoff: Option<u64>,
+
+    /// Response status that should be retried. This argument can be passed multiple times
+    #[arg(long, value_name = "STATUS")]
+    retry_on: Vec<u16>,
+
+    /// Send once for each row of a CSV or JSON file, using its values as variables
+    #[arg(long, value_name = "FILE")]
+    iteration_data: Option<PathBuf>,
+
+    /// Variables from the current iteration data row.
+    #[arg(skip)]
+    iteration: Variables,
+}
+
+/// Options of `send`, which can also write the response body as it is received.
+#[derive(clap::Args, Debug, Clone)]
+pub struct Args {
+    #[command(flatten)]
+    pub request: RequestArgs,
+
+    /// Write the response body to a file instead of standard output
+    #[arg(long, short = 'o', value_name = "FILE", group = "download")]
+    output: Option<PathBuf>,
+
+    /// Write the response body to a file named after the last segment of the URL path
+    #[arg(long, short = 'O', group = "download")]
+    remote_name: bool,
+
+    /// Resume a download at OFFSET bytes, or at the size of the output file when "-"
+    #[arg(long, short = 'C', value_name = "OFFSET", requires = "download")]
+    continue_at: Option<Offset>,
+
+    /// How to print events of event stream responses
+    #[arg(long, value_name = "FORMAT", value_enum, default_value_t)]
+    event_format: sse::Format,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TimeoutError {
+    Read,
+    Total,
+}
+
+impl Display for TimeoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeoutError::Read => write!(f, "read timed out"),
+            TimeoutError::Total => write!(f, "request timed out"),
+        }
+    }
+}
+
+impl std::error::Error for TimeoutError {}
+
+/// Final response of a sent endpoint, after retries and redirects.
+#[derive(Debug)]
+pub struct Response {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+
+    /// Whole body, which is left empty when it was written to an output as it was received,
+    /// unless captures needed it.
+    pub body: Bytes,
+
+    /// Request URL, after following redirects.
+    pub url: Uri,
+
+    /// Time taken by the whole exchange, including retries and redirects.
+    pub elapsed: Duration,
+}
+
+impl RequestArgs {
+    /// Splits these options into one for each iteration data row. Without iteration data, there
+    /// is a single iteration.
+    pub fn iterations(&self) -> QuartzResult<Vec<RequestArgs>> {
+        let Some(path) = &self.iteration_data else {
+            return Ok(vec![self.clone()]);
+        };
+
+        let rows = iteration::read(path)?;
+        if rows.is_empty() {
+            return Err(format!("no rows found in {}", path.display()).into());
+        }
+
+        let list = rows
+            .into_iter()
+            .map(|row| RequestArgs {
+                iteration_data: None,
+                iteration: row,
+                ..self.clone()
+            })
+            .collect();
+
+        Ok(list)
+    }
+
+    /// Retry policy from endpoint, overwritten by command-line options.
+    fn retry_policy(&self, retry: Option<Retry>) -> Retry {
+        let mut retry = retry.unwrap_or_default();
+
+        if let Some(attempts) = self.retry {
+            retry.attempts = attempts;
+        }
+
+        if let Some(backoff) = self.retry_backoff {
+            retry.backoff = backoff;
+        }
+
+        if !self.retry_on.is_empty() {
+            retry.on = self.retry_on.clone();
+        }
+
+        retry.attempts = retry.attempts.max(1);
+        retry
+    }
+}
+
+impl Args {
+    /// Splits these options into one for each iteration data row, keeping the output ones.
+    pub fn iterations(&self) -> QuartzResult<Vec<Args>> {
+        let list = self
+            .request
+            .iterations()?
+            .into_iter()
+            .map(|request| Args {
+                request,
+                ..self.clone()
+            })
+            .collect();
+
+        Ok(list)
+    }
+}
+
+impl From<RequestArgs> for Args {
+    /// Options to send a request without writing its body anywhere, as tests do.
+    fn from(request: RequestArgs) -> Self {
+        Self {
+            request,
+            output: None,
+            remote_name: false,
+            continue_at: None,
+            event_format: Default::default(),
+        }
+    }
+}
+
+/// Awaits `future` until `read` elapses or `deadline` is reached, whichever comes first.
+async fn limit<F>(
+    future: F,
+    read: Option<Duration>,
+    deadline: Option<Instant>,
+) -> Result<F::Output, TimeoutError>
+where
+    F: Future,
+{
+    let read = read.map(|duration| Instant::now() + duration);
+
+    let limit = match (read, deadline) {
+        (Some(read), Some(total)) if total <= read => Some((total, TimeoutError::Total)),
+        (Some(read), _) => Some((read, TimeoutError::Read)),
+        (None, Some(total)) => Some((total, TimeoutError::Total)),
+        (None, None) => None,
+    };
+
+    match limit {
+        Some((at, err)) => tokio::time::timeout_at(at, future).await.map_err(|_| err),
+        None => Ok(future.await),
+    }
+}
+
+/// Stores cookies set by a response from `url` in `cookie_jar`, warning about malformed ones.
+fn store_cookies(cookie_jar: &mut CookieJar, url: &Uri, headers: &HeaderMap) {
+    for cookie_header in headers.get_all("Set-Cookie") {
+        let header = String::from_utf8_lossy(cookie_header.as_bytes());
+
+        if cookie_jar.set(&url.to_string(), &header).is_err() {
+            eprintln!(
+                "{}: skipping malformed cookie: {header}",
+                "warning".yellow().bold()
+            );
+        }
+    }
+}
+
+pub async fn cmd(ctx: &mut Ctx, args: Args) -> QuartzResult {
+    let (handle, endpoint) = ctx.require_endpoint();
+    let iterations = args.iterations().unwrap_or_else(|err| panic!("{err}"));
+
+    if iterations.len() == 1 {
+        send(ctx, &handle, endpoint, &iterations[0], Some(&mut stdout()))
+            .await
+            .unwrap_or_else(|err| panic!("{err}"));
+
+        return Ok(());
+    }
+
+    for (i, args) in iterations.iter().enumerate() {
+        let title = format!("iteration {}", i + 1);
+
+        // Bodies are printed after their iteration title, so they are not streamed
+        match send(ctx, &handle, endpoint.clone(), args, None).await {
+            Ok(res) => {
+                eprintln!("{} {}", title.bold(), res.status);
+
+                let mut out = stdout();
+                let _ = out.write_all(&res.body).await;
+                if !res.body.ends_with(b"\n") {
+                    let _ = out.write_all(b"\n").await;
+                }
+                let _ = out.flush().await;
+            }
+            Err(err) => {
+                eprintln!("{} {}: {err}", title.bold(), "error".red().bold());
+                ctx.code(ExitCode::FAILURE);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Upgrades `request` to a WebSocket connection and exchanges messages through it, sending the
+/// request body first and then each line of standard input.
+///
+/// # Errors
+///
+/// This function will return an error if the server refused the upgrade or the connection was
+/// lost while sending a message.
+async fn open_websocket(
+    ctx: &Ctx,
+    mut entry: history::EntryBuilder,
+    client: &Client<HttpsConnector<HttpConnector>>,
+    mut request: Request,
+    read: Option<Duration>,
+    deadline: Option<Instant>,
+    out: &mut (dyn AsyncWrite + Unpin),
+) -> QuartzResult<Response> {
+    let start = Instant::now();
+    let first = request.body.take();
+    let key = websocket::key();
+
+    request.method = String::from("GET");
+    request.form = None;
+    request.headers.extend([
+        (String::from("Connection"), String::from("Upgrade")),
+        (String::from("Sec-WebSocket-Key"), key.clone()),
+        (String::from("Sec-WebSocket-Version"), String::from("13")),
+        (String::from("Upgrade"), String::from("websocket")),
+    ]);
+
+    let req = request.to_hyper()?;
+    entry.timing(&request.url).message(&req);
+
+    let sent = Instant::now();
+    let res = match limit(client.request(req), read, deadline).await {
+        Ok(Ok(res)) => res,
+        Ok(Err(err)) => {
+            entry.message_raw(format!("* {err}"));
+            History::write(ctx, entry.build()?)?;
+
+            return Err(err.into());
+        }
+        Err(err) => {
+            entry.message_raw(format!("* {err}"));
+            History::write(ctx, entry.build()?)?;
+
+            return Err(err.into());
+        }
+    };
+    entry.waited(sent.elapsed()).message(&res);
+
+    let accepted = res
+        .headers()
+        .get("Sec-WebSocket-Accept")
+        .is_some_and(|value| value.as_bytes() == websocket::accept(&key).as_bytes());
+
+    if res.status() != StatusCode::SWITCHING_PROTOCOLS || !accepted {
+        History::write(ctx, entry.build()?)?;
+
+        return Err(format!("websocket handshake failed: {}", res.status()).into());
+    }
+
+    let status = res.status();
+    let headers = res.headers().clone();
+
+    let receiving = Instant::now();
+    let result = match hyper::upgrade::on(res).await {
+        Ok(upgraded) => {
+            let max = ctx.config.history.max_body_size();
+            websocket::session(upgraded, first, websocket::stdin(), out, &mut entry, max).await
+        }
+        Err(err) => Err(err.into()),
+    };
+    entry.received(receiving.elapsed());
+
+    if let Err(err) = &result {
+        entry.message_raw(format!("* {err}"));
+    }
+    History::write(ctx, entry.build()?)?;
+    result?;
+
+    Ok(Response {
+        status,
+        headers,
+        body: Bytes::new(),
+        url: Uri::from_str(&request.url)?,
+        elapsed: start.elapsed(),
+    })
 }
 
-pub async fn cmd(ctx: &Ctx, mut args: Args) -> QuartzResult {
-    let (handle, mut endpoint) = ctx.require_endpoint();
+/// Sends `endpoint` with the current environment, following `args` options.
+///
+/// When there is an `out`put, the response body is written to it as it is received instead of
+/// being kept in memory.
+///
+/// Every attempt is recorded in history, along with up to `history.max_body_size` bytes of the
+/// response body, and cookies received are stored in the environment cookie jar.
+///
+/// # Errors
+///
+/// This function will return an error if the request could not be made or no response was
+/// received in time, after exhausting every retry.
+pub async fn send(
+    ctx: &Ctx,
+    handle: &EndpointHandle,
+    mut endpoint: Endpoint,
+    args: &Args,
+    mut out: Option<&mut (dyn AsyncWrite + Unpin)>,
+) -> QuartzResult<Response> {
+    let start = Instant::now();
     let mut env = ctx.require_env();
-    for var in args.variables {
-        env.variables.set(&var);
+    for (key, value) in args.request.iteration.iter() {
+        env.variables.insert(key.clone(), value.clone());
     }
+    for var in &args.request.variables {
+        env.variables.set(var);
+    }
+
+    let mut entry = history::Entry::builder();
+    entry
+        .handle(handle.handle())
+        .timestemp(Utc::now().timestamp_micros());
 
-    if !endpoint.headers.contains_key("user-agent") {
+    request::prepare(&mut endpoint, &args.request.patch, &env);
+
+    let output = match (&args.output, args.remote_name) {
+        (Some(path), _) => Some(path.clone()),
+        (None, true) => Some(PathBuf::from(
+            download::remote_name(&endpoint.full_url()?)
+                .ok_or("no file name in URL, use --output instead")?,
+        )),
+        (None, false) => None,
+    };
+
+    let offset = match (&output, args.continue_at) {
+        (Some(path), Some(offset)) => offset.resolve(path),
+        _ => 0,
+    };
+    if offset > 0 {
         endpoint
             .headers
-            .insert("user-agent".to_string(), Ctx::user_agent());
+            .insert(String::from("Range"), format!("bytes={offset}-"));
     }
 
     let mut cookie_jar = env.cookie_jar(ctx);
 
-    let extras = args.cookies.iter().flat_map(|c| {
-        if c.contains('=') {
-            return vec![c.to_owned()];
+    // Cookies passed as arguments are only sent along with the env ones when they match
+    let mut extras = CookieJar::default();
+    for c in &args.request.cookies {
+        if let Some((name, value)) = c.split_once('=') {
+            let mut cookie = Cookie::builder();
+            cookie
+                .domain(endpoint.full_url()?.host().unwrap_or_default())
+                .name(name.trim())
+                .value(value.trim());
+
+            extras.replace(
+                cookie
+                    .build()
+                    .map_err(|_| format!("malformed cookie: {c}"))?,
+            );
+            continue;
         }
 
         let path = Path::new(c);
         if !path.exists() {
-            panic!("no such file: {c}");
+            return Err(format!("no such file: {c}").into());
         }
 
-        CookieJar::read(path)
-            .unwrap()
-            .iter()
-            .map(|c| format!("{}={}", c.name(), c.value()))
-            .collect()
-    });
-
-    let cookie_value = cookie_jar
-        .iter()
-        .map(|c| format!("{}={}", c.name(), c.value()))
-        .chain(extras)
-        .collect::<Vec<String>>()
-        .join("; ");
+        extras.extend(CookieJar::read(path)?.drain());
+    }
 
-    if !cookie_value.is_empty() {
-        endpoint
-            .headers
-            .insert(String::from("Cookie"), cookie_value);
+    if let Some(ms) = args.request.connect_timeout {
+        endpoint.timeout.connect = Some(ms);
+    }
+    if let Some(ms) = args.request.read_timeout {
+        endpoint.timeout.read = Some(ms);
+    }
+    if let Some(ms) = args.request.timeout {
+        endpoint.timeout.total = Some(ms);
     }
 
-    let mut entry = history::Entry::builder();
-    entry
-        .handle(handle.handle())
-        .timestemp(Utc::now().timestamp_micros());
+    let retry = args.request.retry_policy(endpoint.retry.clone());
+    let read = endpoint.timeout.read.map(Duration::from_millis);
+    let deadline = endpoint
+        .timeout
+        .total
+        .map(|ms| Instant::now() + Duration::from_millis(ms));
 
-    endpoint.update(&mut args.patch);
-    endpoint.apply_env(&env);
+    let client = {
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        http.set_connect_timeout(endpoint.timeout.connect.map(Duration::from_millis));
 
-    let body = endpoint.body().cloned();
+        Client::builder().build::<_, Body>(HttpsConnector::new_with_connector(http))
+    };
+
+    if endpoint.is_websocket() {
+        let Some(out) = out else {
+            return Err(WEBSOCKET_SKIPPED.into());
+        };
+
+        // Cookies are selected for the URL the handshake is sent to
+        endpoint.url = websocket::http_url(&endpoint.url);
+        let request = Request::new(&mut endpoint, &env, &[&cookie_jar, &extras])?;
+
+        return open_websocket(ctx, entry, &client, request, read, deadline, out).await;
+    }
+
+    // Form files are read relative to the project, wherever it is sent from
+    let project = ctx.path().parent().unwrap_or(ctx.path());
 
     let mut res: hyper::Response<Body>;
 
     loop {
-        let mut req = endpoint
-            // TODO: Find a way around this clone
-            .clone()
-            .into_request()
-            .unwrap_or_else(|_| panic!("malformed request"));
-        for (key, val) in env.headers.iter() {
-            if !endpoint.headers.contains_key(key) {
-                req.headers_mut()
-                    .insert(HeaderName::from_str(key)?, HeaderValue::from_str(val)?);
+        // Cookies are selected again on every redirect, as the URL may have changed
+        let mut request = Request::new(&mut endpoint, &env, &[&cookie_jar, &extras])?;
+        request.dir = project.to_path_buf();
+        let url = endpoint.full_url()?;
+
+        let mut attempt = 1;
+        let mut backoff = Duration::from_millis(retry.backoff);
+
+        res = loop {
+            let req = request.to_hyper()?;
+
+            entry.timing(url.to_string()).message(&req);
+            if let Some(body) = request.body_message() {
+                entry.message_bytes(body);
             }
-        }
 
-        entry.message(&req);
-        if let Some(ref body) = body {
-            entry.message_raw(body.to_owned());
-        }
+            let sent = Instant::now();
+            let result: QuartzResult<hyper::Response<Body>> =
+                match limit(client.request(req), read, deadline).await {
+                    Ok(Ok(res)) => Ok(res),
+                    Ok(Err(err)) => Err(err.into()),
+                    Err(err) => Err(err.into()),
+                };
+            entry.waited(sent.elapsed());
+
+            let failure = match &result {
+                Ok(res) if retry.on.contains(&res.status().as_u16()) => {
+                    Some(res.status().to_string())
+                }
+                Ok(_) => None,
+                Err(err) => Some(err.to_string()),
+            };
+
+            let Some(failure) = failure else {
+                break result?;
+            };
+
+            let out_of_time = matches!(
+                result
+                    .as_ref()
+                    .err()
+                    .and_then(|err| err.downcast_ref::<TimeoutError>()),
+                Some(TimeoutError::Total)
+            ) || deadline
+                .is_some_and(|deadline| Instant::now() + backoff >= deadline);
+
+            match result {
+                // Last response is handled as a regular one
+                Ok(res) if attempt >= retry.attempts || out_of_time => break res,
+                Ok(res) => {
+                    entry.message(&res);
+                    entry.message_raw(format!("* attempt {attempt} failed: {failure}"));
+                }
+                Err(err) => {
+                    entry.message_raw(format!("* attempt {attempt} failed: {failure}"));
+
+                    if attempt >= retry.attempts || out_of_time {
+                        // Keeps track of failed attempts, even though there is no response
+                        History::write(ctx, entry.build()?)?;
+                        return Err(err);
+                    }
+                }
+            }
 
-        let client = {
-            let https = hyper_tls::HttpsConnector::new();
-            Client::builder().build(https)
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+            attempt += 1;
         };
 
-        res = client.request(req).await?;
-
         entry.message(&res);
 
-        if let Some(cookie_header) = res.headers().get("Set-Cookie") {
-            let url = endpoint.full_url()?;
+        store_cookies(&mut cookie_jar, &url, res.headers());
 
-            cookie_jar.set(url.host().unwrap(), cookie_header.to_str()?);
-        }
-
-        if args.no_follow || !res.status().is_redirection() {
+        if args.request.no_follow || !res.status().is_redirection() {
             break;
         }
 
@@ -148,23 +600,253 @@ pub async fn cmd(ctx: &Ctx, mut args: Args) -> QuartzResult {
         };
     }
 
-    match args.cookie_jar {
-        Some(path) => cookie_jar.write_at(&path)?,
+    match &args.request.cookie_jar {
+        Some(path) => cookie_jar.write_at(path)?,
         None => cookie_jar.write()?,
     };
 
-    let mut bytes = Bytes::new();
+    // Error responses are not written to the output file, so it can still be resumed
+    let mut download = match &output {
+        Some(path) if res.status().is_success() => {
+            // Servers that ignore the range send the whole body again
+            let resume = (res.status() == StatusCode::PARTIAL_CONTENT).then_some(offset);
+            let total = res
+                .body()
+                .size_hint()
+                .exact()
+                .map(|length| length + resume.unwrap_or(0));
+            let progress = console::Term::stdout().is_term() && console::Term::stderr().is_term();
+
+            Some(Download::open(path, resume, total, progress).await?)
+        }
+        _ => None,
+    };
+
+    let max_history = ctx.config.history.max_body_size() as usize;
+    let keep = (out.is_none() && download.is_none()) || !endpoint.captures.is_empty();
 
-    while let Some(chunk) = res.data().await {
-        if let Ok(chunk) = chunk {
-            bytes = [bytes, chunk].concat().into();
+    // Event streams are printed event by event, and resumed when they are disconnected
+    let mut events = match (&out, &download) {
+        (Some(_), None) if sse::is_event_stream(res.headers()) => Some(sse::Parser::default()),
+        _ => None,
+    };
+    let interrupted = tokio::signal::ctrl_c();
+    tokio::pin!(interrupted);
+
+    let mut bytes: Vec<u8> = Vec::new();
+    let mut recorded: Vec<u8> = Vec::new();
+    let mut size: u64 = 0;
+
+    loop {
+        let receiving = Instant::now();
+        let mut received = 0;
+        let mut closed = false;
+
+        loop {
+            // Polling the interrupt first registers its handler before any event is printed
+            let chunk = tokio::select! {
+                biased;
+                _ = &mut interrupted, if events.is_some() => {
+                    closed = true;
+                    break;
+                }
+                chunk = limit(res.data(), read, deadline) => chunk,
+            };
+
+            let chunk: QuartzResult<Option<Bytes>> = match chunk {
+                Ok(Some(Ok(chunk))) => Ok(Some(chunk)),
+                // Disconnected event streams are resumed
+                Ok(Some(Err(_))) if events.is_some() => break,
+                Ok(Some(Err(err))) => Err(err.into()),
+                Ok(None) => Ok(None),
+                Err(err) => Err(err.into()),
+            };
+
+            let chunk = match chunk {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(err) => {
+                    // What was already written is kept, so the download can be resumed
+                    if let Some(download) = download.take() {
+                        let _ = download.finish().await;
+                    }
+
+                    entry.message_raw(format!("* {err}"));
+                    History::write(ctx, entry.build()?)?;
+
+                    return Err(err);
+                }
+            };
+
+            if keep {
+                bytes.extend_from_slice(&chunk);
+            }
+
+            if let Some(download) = download.as_mut() {
+                if let Err(err) = download.write(&chunk).await {
+                    entry.message_raw(format!("* {err}"));
+                    History::write(ctx, entry.build()?)?;
+
+                    return Err(err);
+                }
+
+                continue;
+            }
+
+            let output = match events.as_mut() {
+                Some(parser) => {
+                    let mut text = String::new();
+                    for event in parser.feed(&chunk) {
+                        match args.event_format {
+                            sse::Format::Text => text.push_str(&format!("{event}\n")),
+                            sse::Format::Json => text.push_str(&format!("{}\n", event.to_json())),
+                        }
+
+                        entry.event(event);
+                        received += 1;
+                    }
+
+                    Bytes::from(text)
+                }
+                None => {
+                    size += chunk.len() as u64;
+                    if recorded.len() < max_history {
+                        let end = chunk.len().min(max_history - recorded.len());
+                        recorded.extend_from_slice(&chunk[..end]);
+                    }
+
+                    chunk
+                }
+            };
+
+            if let Some(writer) = out.as_mut() {
+                let written = match writer.write_all(&output).await {
+                    Ok(()) => writer.flush().await,
+                    Err(err) => Err(err),
+                };
+
+                // Nothing else would be read from a closed pipe, such as "quartz send | head"
+                if written.is_err() {
+                    closed = true;
+                    break;
+                }
+            }
+        }
+
+        entry.received(receiving.elapsed());
+
+        let Some(parser) = events.as_mut() else {
+            break;
+        };
+
+        entry.message_raw(match received {
+            1 => String::from("* 1 event received"),
+            n => format!("* {n} events received"),
+        });
+        if closed {
+            break;
+        }
+
+        // Waits as long as the server asked before reconnecting
+        tokio::select! {
+            _ = tokio::time::sleep(parser.retry()) => (),
+            _ = &mut interrupted => break,
+        }
+
+        // Fields of an event cut off by the disconnection are dropped
+        parser.reset();
+
+        if let Some(id) = parser.last_id() {
+            endpoint
+                .headers
+                .insert(String::from("Last-Event-ID"), id.to_string());
+        }
+
+        let url = endpoint.full_url()?;
+        let mut request = Request::new(&mut endpoint, &env, &[&cookie_jar, &extras])?;
+        request.dir = project.to_path_buf();
+        let req = request.to_hyper()?;
+        entry.timing(url.to_string()).message(&req);
+
+        let sent = Instant::now();
+        let result = limit(client.request(req), read, deadline).await;
+        entry.waited(sent.elapsed());
+
+        match result {
+            Ok(Ok(next)) => {
+                entry.message(&next);
+
+                store_cookies(&mut cookie_jar, &url, next.headers());
+                match &args.request.cookie_jar {
+                    Some(path) => cookie_jar.write_at(path)?,
+                    None => cookie_jar.write()?,
+                };
+
+                // Any other response, such as 204 No Content, ends the stream for good
+                if next.status() != StatusCode::OK || !sse::is_event_stream(next.headers()) {
+                    break;
+                }
+
+                res = next;
+            }
+            Ok(Err(err)) => {
+                entry.message_raw(format!("* reconnection failed: {err}"));
+                break;
+            }
+            Err(err) => {
+                entry.message_raw(format!("* reconnection failed: {err}"));
+                break;
+            }
         }
     }
 
-    entry.message_raw(String::from_utf8(bytes.to_vec())?);
+    if let Some(download) = download {
+        // Only the file size and hash are recorded
+        entry.message_raw(download.finish().await?);
+    } else if (recorded.len() as u64) < size {
+        // Text cut in the middle of a character is still recorded as text
+        if let Err(err) = std::str::from_utf8(&recorded) {
+            if err.error_len().is_none() {
+                recorded.truncate(err.valid_up_to());
+            }
+        }
 
-    let _ = stdout().write_all(&bytes).await;
+        let recorded_size = recorded.len();
+        entry.message_bytes(recorded).message_raw(format!(
+            "* body truncated to {recorded_size} of {size} bytes"
+        ));
+    } else if events.is_none() {
+        entry.message_bytes(recorded);
+    }
     History::write(ctx, entry.build()?)?;
 
-    Ok(())
+    let res = Response {
+        status: res.status(),
+        headers: res.headers().clone(),
+        body: bytes.into(),
+        url: endpoint.full_url()?,
+        elapsed: start.elapsed(),
+    };
+
+    if res.status.is_success() && !endpoint.captures.is_empty() {
+        // Variables passed as arguments are not meant to be saved
+        let mut env = ctx.require_env();
+
+        for capture in &endpoint.captures {
+            match capture.extract(&res, &cookie_jar) {
+                Ok(value) => {
+                    env.variables.insert(capture.var.clone(), value);
+                }
+                Err(reason) => eprintln!(
+                    "{}: could not capture {}: {reason}",
+                    "warning".yellow().bold(),
+                    capture.var
+                ),
+            }
+        }
+
+        env.update(ctx)?;
+    }
+
+    Ok(res)
 }
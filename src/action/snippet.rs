@@ -1,4 +1,10 @@
-use crate::{cli::SnippetCmd as Cmd, endpoint::EndpointPatch, snippet, Ctx, PairMap, QuartzResult};
+use crate::{
+    cli::SnippetCmd as Cmd,
+    endpoint::EndpointPatch,
+    request::{self, Request},
+    snippet::{self, Snippet},
+    Ctx, PairMap, QuartzResult,
+};
 
 #[derive(clap::Args, Debug)]
 pub struct Args {
@@ -13,7 +19,7 @@ pub struct Args {
     command: crate::cli::SnippetCmd,
 }
 
-pub fn cmd(ctx: &Ctx, mut args: Args) -> QuartzResult {
+pub fn cmd(ctx: &Ctx, args: Args) -> QuartzResult {
     let (_, mut endpoint) = ctx.require_endpoint();
     let mut env = ctx.require_env();
 
@@ -21,13 +27,33 @@ pub fn cmd(ctx: &Ctx, mut args: Args) -> QuartzResult {
         env.variables.set(&var);
     }
 
-    endpoint.update(&mut args.patch);
-    endpoint.apply_env(&env);
-
-    match args.command {
-        Cmd::Curl(curl) => curl.print(&mut endpoint)?,
-        Cmd::Http => snippet::Http::print(&mut endpoint)?,
+    request::prepare(&mut endpoint, &args.patch, &env);
+    let request = Request::new(&mut endpoint, &env, &[&env.cookie_jar(ctx)])?;
+    let generator: Box<dyn Snippet> = match args.command {
+        Cmd::Curl(curl) => Box::new(curl),
+        Cmd::Http => Box::new(snippet::HttpMessage),
+        Cmd::Fetch => Box::new(snippet::Fetch),
+        Cmd::Python => Box::new(snippet::Python),
+        Cmd::Go => Box::new(snippet::Go),
+        Cmd::Reqwest => Box::new(snippet::Reqwest),
+        Cmd::Httpie => Box::new(snippet::Httpie),
+        Cmd::Wget => Box::new(snippet::Wget),
+        Cmd::Template(args) => {
+            if args.len() > 1 {
+                panic!(
+                    "unexpected arguments for {} snippet: {}",
+                    args[0],
+                    args[1..].join(" ")
+                );
+            }
+
+            let template =
+                snippet::Template::find(ctx, &args[0]).unwrap_or_else(|err| panic!("{err}"));
+            Box::new(template)
+        }
     };
 
+    print!("{}", generator.render(&request));
+
     Ok(())
 }
@@ -0,0 +1,140 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use crate::{
+    action::send,
+    report::{self, Case},
+    Ctx, QuartzResult,
+};
+use colored::Colorize;
+
+#[derive(clap::Args, Debug)]
+#[group(id = "TestArgs")]
+pub struct Args {
+    /// Handle to test. Defaults to the current one
+    handle: Option<String>,
+
+    #[command(flatten)]
+    report: ReportArgs,
+
+    #[command(flatten)]
+    send: send::RequestArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ReportArgs {
+    /// Write a test report. It replaces the regular output, unless written to a file
+    #[arg(long, value_name = "FORMAT")]
+    report: Option<report::Format>,
+
+    /// Which file to write the test report to
+    #[arg(long, value_name = "FILE", requires = "report")]
+    report_file: Option<PathBuf>,
+}
+
+impl ReportArgs {
+    /// Whether the regular output should be printed.
+    pub fn verbose(&self) -> bool {
+        self.report.is_none() || self.report_file.is_some()
+    }
+
+    /// Writes the report of `cases`, in case one was requested.
+    pub fn write(&self, name: &str, cases: &[Case]) -> QuartzResult {
+        let Some(format) = self.report else {
+            return Ok(());
+        };
+
+        let content = report::render(format, name, cases);
+
+        match &self.report_file {
+            Some(path) => std::fs::write(path, content)?,
+            None => print!("{content}"),
+        };
+
+        Ok(())
+    }
+}
+
+pub async fn cmd(ctx: &mut Ctx, args: Args) -> QuartzResult {
+    let (handle, endpoint) = match &args.handle {
+        Some(handle) => {
+            let handle = ctx.require_input_handle(handle);
+            let endpoint = ctx.require_endpoint_from_handle(&handle);
+
+            (handle, endpoint)
+        }
+        None => ctx.require_endpoint(),
+    };
+
+    let verbose = args.report.verbose();
+
+    let assertions = endpoint.assertions.clone();
+    if assertions.is_empty() {
+        eprintln!(
+            "{}: no assertions found for {}",
+            "warning".yellow().bold(),
+            handle.handle()
+        );
+    }
+
+    let iterations = send::Args::from(args.send)
+        .iterations()
+        .unwrap_or_else(|err| panic!("{err}"));
+    let mut cases: Vec<Case> = Vec::new();
+
+    for (i, send_args) in iterations.iter().enumerate() {
+        let iteration = (iterations.len() > 1).then_some(i + 1);
+        let case = Case::new(handle.handle(), iteration);
+
+        let case = if endpoint.is_websocket() {
+            case.skip(send::WEBSOCKET_SKIPPED)
+        } else {
+            match send::send(ctx, &handle, endpoint.clone(), send_args, None).await {
+                Ok(res) => case.response(&res, &assertions),
+                Err(err) => case.error(err),
+            }
+        };
+
+        if verbose {
+            match iteration {
+                Some(n) => {
+                    println!("{}", format!("Iteration {n}").bold());
+                    case.print("  ");
+                }
+                None => case.print(""),
+            }
+        }
+
+        cases.push(case);
+    }
+
+    if verbose {
+        // Assertions cannot be checked without a response, so they all fail
+        let (total, failed) =
+            cases
+                .iter()
+                .fold((0, 0), |(total, failed), case| match &case.error {
+                    Some(_) => {
+                        let len = assertions.len().max(1);
+                        (total + len, failed + len)
+                    }
+                    None => (total + case.checks.len(), failed + case.failures()),
+                });
+
+        let summary = format!("{} passed, {} failed", total - failed, failed);
+
+        if failed > 0 {
+            println!("\n{}", summary.red());
+        } else {
+            println!("\n{}", summary.green());
+        }
+    }
+
+    args.report.write(&handle.handle(), &cases)?;
+
+    if cases.iter().any(|case| case.failed()) {
+        ctx.code(ExitCode::FAILURE);
+    }
+
+    Ok(())
+}
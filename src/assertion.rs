@@ -0,0 +1,586 @@
+use std::fmt::Display;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::action::send::Response;
+
+/// Expectation about a response, stored in the `[[assert]]` sections of an endpoint file.
+///
+/// Each kind of assertion is told apart by its key, so the file reads like:
+///
+/// ```toml
+/// [[assert]]
+/// status = 200
+///
+/// [[assert]]
+/// header = "content-type"
+/// matches = "^application/json"
+///
+/// [[assert]]
+/// json = "$.data[0].id"
+/// equals = 1
+///
+/// [[assert]]
+/// contains = "Hello"
+///
+/// [[assert]]
+/// max_time = 500
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawAssertion", into = "RawAssertion")]
+pub enum Assertion {
+    /// Response has this status code.
+    Status { status: u16 },
+
+    /// Response has this header. When `equals` or `matches` is set, its value is compared.
+    Header {
+        header: String,
+        equals: Option<String>,
+        matches: Option<String>,
+    },
+
+    /// Response body is JSON with a value at `json` path. When `equals` or `matches` is set,
+    /// the value is compared.
+    Json {
+        json: String,
+        equals: Option<Value>,
+        matches: Option<String>,
+    },
+
+    /// Response body contains this text.
+    Body { contains: String },
+
+    /// Response took at most this many milliseconds.
+    Time { max_time: u64 },
+}
+
+/// Flat representation of [`Assertion`] in the endpoint file.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawAssertion {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    header: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    json: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    contains: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_time: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    equals: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    matches: Option<String>,
+}
+
+impl TryFrom<RawAssertion> for Assertion {
+    type Error = String;
+
+    fn try_from(raw: RawAssertion) -> Result<Self, Self::Error> {
+        let kinds = [
+            raw.status.is_some(),
+            raw.header.is_some(),
+            raw.json.is_some(),
+            raw.contains.is_some(),
+            raw.max_time.is_some(),
+        ];
+
+        if kinds.iter().filter(|v| **v).count() != 1 {
+            return Err(String::from(
+                "assertion expects exactly one of status, header, json, contains or max_time",
+            ));
+        }
+
+        let compares = raw.equals.is_some() || raw.matches.is_some();
+        if compares && raw.header.is_none() && raw.json.is_none() {
+            return Err(String::from(
+                "equals and matches are only available for header and json assertions",
+            ));
+        }
+
+        if let Some(status) = raw.status {
+            return Ok(Assertion::Status { status });
+        }
+
+        if let Some(header) = raw.header {
+            let equals = match raw.equals {
+                Some(Value::String(v)) => Some(v),
+                Some(_) => return Err(String::from("header value must be a string")),
+                None => None,
+            };
+
+            return Ok(Assertion::Header {
+                header,
+                equals,
+                matches: raw.matches,
+            });
+        }
+
+        if let Some(json) = raw.json {
+            return Ok(Assertion::Json {
+                json,
+                equals: raw.equals,
+                matches: raw.matches,
+            });
+        }
+
+        if let Some(contains) = raw.contains {
+            return Ok(Assertion::Body { contains });
+        }
+
+        Ok(Assertion::Time {
+            max_time: raw.max_time.unwrap_or_default(),
+        })
+    }
+}
+
+impl From<Assertion> for RawAssertion {
+    fn from(value: Assertion) -> Self {
+        match value {
+            Assertion::Status { status } => Self {
+                status: Some(status),
+                ..Default::default()
+            },
+            Assertion::Header {
+                header,
+                equals,
+                matches,
+            } => Self {
+                header: Some(header),
+                equals: equals.map(Value::String),
+                matches,
+                ..Default::default()
+            },
+            Assertion::Json {
+                json,
+                equals,
+                matches,
+            } => Self {
+                json: Some(json),
+                equals,
+                matches,
+                ..Default::default()
+            },
+            Assertion::Body { contains } => Self {
+                contains: Some(contains),
+                ..Default::default()
+            },
+            Assertion::Time { max_time } => Self {
+                max_time: Some(max_time),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl Display for Assertion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Assertion::Status { status } => write!(f, "status is {status}"),
+            Assertion::Header {
+                header,
+                equals,
+                matches,
+            } => {
+                write!(f, "header {header}")?;
+                fmt_comparison(f, equals.as_ref().map(|v| format!("{v:?}")), matches)
+            }
+            Assertion::Json {
+                json,
+                equals,
+                matches,
+            } => {
+                write!(f, "json {json}")?;
+                fmt_comparison(f, equals.as_ref().map(|v| v.to_string()), matches)
+            }
+            Assertion::Body { contains } => write!(f, "body contains {contains:?}"),
+            Assertion::Time { max_time } => write!(f, "response time is at most {max_time}ms"),
+        }
+    }
+}
+
+fn fmt_comparison(
+    f: &mut std::fmt::Formatter<'_>,
+    equals: Option<String>,
+    matches: &Option<String>,
+) -> std::fmt::Result {
+    match (equals, matches) {
+        (Some(equals), _) => write!(f, " equals {equals}"),
+        (None, Some(matches)) => write!(f, " matches /{matches}/"),
+        (None, None) => write!(f, " exists"),
+    }
+}
+
+impl Assertion {
+    /// Checks whether `res` meets this assertion.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error describing what was found instead, when the
+    /// assertion fails.
+    pub fn check(&self, res: &Response) -> Result<(), String> {
+        match self {
+            Assertion::Status { status } => {
+                if res.status.as_u16() == *status {
+                    Ok(())
+                } else {
+                    Err(format!("got {}", res.status.as_u16()))
+                }
+            }
+            Assertion::Header {
+                header,
+                equals,
+                matches,
+            } => {
+                let values: Vec<String> = res
+                    .headers
+                    .get_all(header.as_str())
+                    .iter()
+                    .map(|v| String::from_utf8_lossy(v.as_bytes()).to_string())
+                    .collect();
+
+                if values.is_empty() {
+                    return Err(String::from("header not found"));
+                }
+
+                if let Some(equals) = equals {
+                    if !values.iter().any(|v| v == equals) {
+                        return Err(format!("got {:?}", values.join(", ")));
+                    }
+                }
+
+                if let Some(matches) = matches {
+                    let regex = Self::regex(matches)?;
+
+                    if !values.iter().any(|v| regex.is_match(v)) {
+                        return Err(format!("got {:?}", values.join(", ")));
+                    }
+                }
+
+                Ok(())
+            }
+            Assertion::Json {
+                json,
+                equals,
+                matches,
+            } => {
+                let body: Value = serde_json::from_slice(&res.body)
+                    .map_err(|_| String::from("body is not valid JSON"))?;
+
+                let path = JsonPath::parse(json).ok_or(format!("invalid JSON path: {json}"))?;
+                let value = path
+                    .find(&body)
+                    .ok_or(String::from("no value found at path"))?;
+
+                if let Some(equals) = equals {
+                    if !json_eq(value, equals) {
+                        return Err(format!("got {value}"));
+                    }
+                }
+
+                if let Some(matches) = matches {
+                    let regex = Self::regex(matches)?;
+                    let text = match value {
+                        Value::String(s) => s.to_owned(),
+                        _ => value.to_string(),
+                    };
+
+                    if !regex.is_match(&text) {
+                        return Err(format!("got {value}"));
+                    }
+                }
+
+                Ok(())
+            }
+            Assertion::Body { contains } => {
+                if String::from_utf8_lossy(&res.body).contains(contains.as_str()) {
+                    Ok(())
+                } else {
+                    Err(String::from("text not found"))
+                }
+            }
+            Assertion::Time { max_time } => {
+                let elapsed = res.elapsed.as_millis();
+
+                if elapsed <= *max_time as u128 {
+                    Ok(())
+                } else {
+                    Err(format!("took {elapsed}ms"))
+                }
+            }
+        }
+    }
+
+    fn regex(pattern: &str) -> Result<Regex, String> {
+        Regex::new(pattern).map_err(|_| format!("invalid pattern: {pattern}"))
+    }
+}
+
+/// Compares JSON values, taking integers and floats with the same value as equal.
+fn json_eq(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a == b || a.as_f64() == b.as_f64(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| json_eq(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(key).is_some_and(|b| json_eq(a, b)))
+        }
+        _ => a == b,
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Simple JSON path, made of object keys and array indexes, like `$.data[0].name`.
+#[derive(Debug, PartialEq)]
+pub struct JsonPath(Vec<Segment>);
+
+impl JsonPath {
+    /// Parses a JSON path.
+    ///
+    /// The leading `$` is optional. Keys are separated by dots, or written in brackets with
+    /// quotes when they have special characters. Indexes are written in brackets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::assertion::JsonPath;
+    /// use serde_json::json;
+    ///
+    /// let value = json!({ "data": [{ "name": "quartz" }], "a.b": true });
+    ///
+    /// let path = JsonPath::parse("$.data[0].name").unwrap();
+    /// assert_eq!(path.find(&value), Some(&json!("quartz")));
+    ///
+    /// let path = JsonPath::parse("[\"a.b\"]").unwrap();
+    /// assert_eq!(path.find(&value), Some(&json!(true)));
+    ///
+    /// assert!(JsonPath::parse("$.data[").is_none());
+    /// ```
+    pub fn parse(input: &str) -> Option<Self> {
+        let mut segments = Vec::new();
+        let input = input.trim();
+        let mut rest = input.strip_prefix('$').unwrap_or(input);
+
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('[') {
+                let (inner, after) = after.split_once(']')?;
+                let inner = inner.trim();
+
+                let quoted = inner
+                    .strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .or_else(|| inner.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')));
+
+                match quoted {
+                    Some(key) => segments.push(Segment::Key(key.to_string())),
+                    None => segments.push(Segment::Index(inner.parse().ok()?)),
+                }
+
+                rest = after;
+                continue;
+            }
+
+            let after = rest.strip_prefix('.').unwrap_or(rest);
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            let key = &after[..end];
+
+            if key.is_empty() {
+                return None;
+            }
+
+            segments.push(Segment::Key(key.to_string()));
+            rest = &after[end..];
+        }
+
+        Some(Self(segments))
+    }
+
+    /// Returns the value at this path inside `value`.
+    pub fn find<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        let mut current = value;
+
+        for segment in &self.0 {
+            current = match segment {
+                Segment::Key(key) => current.as_object()?.get(key)?,
+                Segment::Index(index) => current.as_array()?.get(*index)?,
+            };
+        }
+
+        Some(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::{body::Bytes, HeaderMap, StatusCode, Uri};
+    use std::time::Duration;
+
+    fn response(status: u16, headers: &[(&'static str, &str)], body: &str) -> Response {
+        let mut map = HeaderMap::new();
+        for (key, value) in headers {
+            map.append(*key, value.parse().unwrap());
+        }
+
+        Response {
+            status: StatusCode::from_u16(status).unwrap(),
+            headers: map,
+            body: Bytes::from(body.to_string()),
+            url: Uri::from_static("http://localhost/"),
+            elapsed: Duration::from_millis(120),
+        }
+    }
+
+    fn parse(input: &str) -> Vec<Assertion> {
+        #[derive(Deserialize)]
+        struct File {
+            assert: Vec<Assertion>,
+        }
+
+        toml::from_str::<File>(input).unwrap().assert
+    }
+
+    #[test]
+    fn assertion_parse() {
+        let list = parse(
+            r#"
+            [[assert]]
+            status = 201
+
+            [[assert]]
+            header = "location"
+
+            [[assert]]
+            json = "$.id"
+            equals = 1
+
+            [[assert]]
+            contains = "ok"
+
+            [[assert]]
+            max_time = 500
+            "#,
+        );
+
+        assert_eq!(
+            list,
+            vec![
+                Assertion::Status { status: 201 },
+                Assertion::Header {
+                    header: String::from("location"),
+                    equals: None,
+                    matches: None,
+                },
+                Assertion::Json {
+                    json: String::from("$.id"),
+                    equals: Some(Value::from(1)),
+                    matches: None,
+                },
+                Assertion::Body {
+                    contains: String::from("ok"),
+                },
+                Assertion::Time { max_time: 500 },
+            ]
+        );
+
+        #[derive(Deserialize)]
+        struct File {
+            #[allow(dead_code)]
+            assert: Vec<Assertion>,
+        }
+
+        assert!(toml::from_str::<File>("[[assert]]\nstatus = 200\ntypo = 1").is_err());
+        assert!(toml::from_str::<File>("[[assert]]\nstatus = 200\ncontains = \"a\"").is_err());
+        assert!(toml::from_str::<File>("[[assert]]\ncontains = \"a\"\nequals = \"a\"").is_err());
+    }
+
+    #[test]
+    fn assertion_check() {
+        let res = response(
+            200,
+            &[("content-type", "application/json; charset=utf-8")],
+            r#"{ "id": 1, "tags": ["a", "b"], "name": "quartz" }"#,
+        );
+
+        let list = parse(
+            r#"
+            [[assert]]
+            status = 200
+
+            [[assert]]
+            header = "Content-Type"
+            matches = "^application/json"
+
+            [[assert]]
+            json = "id"
+            equals = 1.0
+
+            [[assert]]
+            json = "$.tags"
+            equals = ["a", "b"]
+
+            [[assert]]
+            json = "$.name"
+            matches = "^qu"
+
+            [[assert]]
+            contains = "quartz"
+
+            [[assert]]
+            max_time = 200
+            "#,
+        );
+
+        for assertion in list {
+            assert_eq!(assertion.check(&res), Ok(()), "{assertion}");
+        }
+    }
+
+    #[test]
+    fn assertion_check_failure() {
+        let res = response(404, &[], "not found");
+
+        let list = parse(
+            r#"
+            [[assert]]
+            status = 200
+
+            [[assert]]
+            header = "content-type"
+
+            [[assert]]
+            json = "$.id"
+
+            [[assert]]
+            contains = "ok"
+
+            [[assert]]
+            max_time = 100
+            "#,
+        );
+
+        let errors: Vec<String> = list.iter().filter_map(|a| a.check(&res).err()).collect();
+
+        assert_eq!(
+            errors,
+            vec![
+                "got 404",
+                "header not found",
+                "body is not valid JSON",
+                "text not found",
+                "took 120ms",
+            ]
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{action::send::Response, assertion::JsonPath, cookie::CookieJar};
+
+/// Response value to be stored in an environment variable after a successful request, declared
+/// in the `[[capture]]` sections of an endpoint file.
+///
+/// ```toml
+/// [[capture]]
+/// var = "token"
+/// json = "$.access_token"
+///
+/// [[capture]]
+/// var = "session"
+/// cookie = "session_id"
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawCapture", into = "RawCapture")]
+pub struct Capture {
+    /// Variable name to store the value in.
+    pub var: String,
+
+    pub source: Source,
+}
+
+/// Where in the response a [`Capture`] value comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    /// Value at a JSON path of the response body.
+    Json(String),
+
+    /// Response header value.
+    Header(String),
+
+    /// Regular expression over the response body. The first group is captured, or the whole
+    /// match when there is no group.
+    Regex(String),
+
+    /// Value of a cookie sent to the request URL.
+    Cookie(String),
+}
+
+/// Flat representation of [`Capture`] in the endpoint file.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawCapture {
+    var: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    json: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    header: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    regex: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cookie: Option<String>,
+}
+
+impl TryFrom<RawCapture> for Capture {
+    type Error = String;
+
+    fn try_from(raw: RawCapture) -> Result<Self, Self::Error> {
+        let sources = [
+            raw.json.map(Source::Json),
+            raw.header.map(Source::Header),
+            raw.regex.map(Source::Regex),
+            raw.cookie.map(Source::Cookie),
+        ];
+
+        let mut sources = sources.into_iter().flatten();
+
+        match (sources.next(), sources.next()) {
+            (Some(source), None) => Ok(Capture {
+                var: raw.var,
+                source,
+            }),
+            _ => Err(String::from(
+                "capture expects exactly one of json, header, regex or cookie",
+            )),
+        }
+    }
+}
+
+impl From<Capture> for RawCapture {
+    fn from(value: Capture) -> Self {
+        let mut raw = RawCapture {
+            var: value.var,
+            ..Default::default()
+        };
+
+        match value.source {
+            Source::Json(v) => raw.json = Some(v),
+            Source::Header(v) => raw.header = Some(v),
+            Source::Regex(v) => raw.regex = Some(v),
+            Source::Cookie(v) => raw.cookie = Some(v),
+        };
+
+        raw
+    }
+}
+
+impl Capture {
+    /// Finds the value to be captured from `res`, whose cookies were stored in `jar`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error describing why there is no value to capture.
+    pub fn extract(&self, res: &Response, jar: &CookieJar) -> Result<String, String> {
+        match &self.source {
+            Source::Json(path) => {
+                let body: Value = serde_json::from_slice(&res.body)
+                    .map_err(|_| String::from("body is not valid JSON"))?;
+
+                let value = JsonPath::parse(path)
+                    .ok_or(format!("invalid JSON path: {path}"))?
+                    .find(&body)
+                    .ok_or(format!("no value found at {path}"))?;
+
+                match value {
+                    Value::String(s) => Ok(s.to_owned()),
+                    _ => Ok(value.to_string()),
+                }
+            }
+            Source::Header(key) => res
+                .headers
+                .get(key.as_str())
+                .map(|v| String::from_utf8_lossy(v.as_bytes()).to_string())
+                .ok_or(format!("no {key} header found")),
+            Source::Regex(pattern) => {
+                let regex =
+                    Regex::new(pattern).map_err(|_| format!("invalid pattern: {pattern}"))?;
+                let body = String::from_utf8_lossy(&res.body);
+
+                let captures = regex
+                    .captures(&body)
+                    .ok_or(format!("no match for /{pattern}/"))?;
+
+                let found = captures.get(1).or(captures.get(0)).unwrap();
+                Ok(found.as_str().to_string())
+            }
+            Source::Cookie(name) => jar
+                .select(&res.url)
+                .into_iter()
+                .find(|c| c.name() == name)
+                .map(|c| c.value().to_string())
+                .ok_or(format!("no {name} cookie found")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::{body::Bytes, HeaderMap, StatusCode, Uri};
+    use std::time::Duration;
+
+    fn parse(input: &str) -> Result<Vec<Capture>, toml::de::Error> {
+        #[derive(Deserialize)]
+        struct File {
+            capture: Vec<Capture>,
+        }
+
+        toml::from_str::<File>(input).map(|f| f.capture)
+    }
+
+    #[test]
+    fn capture_extract() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", "abc".parse().unwrap());
+
+        let res = Response {
+            status: StatusCode::OK,
+            headers,
+            body: Bytes::from(r#"{ "token": "secret", "user": { "id": 7 } }"#),
+            url: Uri::from_static("https://example.com/login"),
+            elapsed: Duration::ZERO,
+        };
+
+        let mut jar = CookieJar::default();
+        jar.set("https://example.com/", "session=s1").unwrap();
+        jar.set("https://other.com/", "other=o1").unwrap();
+
+        let captures = parse(
+            r#"
+            [[capture]]
+            var = "token"
+            json = "$.token"
+
+            [[capture]]
+            var = "id"
+            json = "$.user.id"
+
+            [[capture]]
+            var = "request"
+            header = "X-Request-Id"
+
+            [[capture]]
+            var = "user"
+            regex = '"id": (\d+)'
+
+            [[capture]]
+            var = "session"
+            cookie = "session"
+            "#,
+        )
+        .unwrap();
+
+        let values: Vec<String> = captures
+            .iter()
+            .map(|c| c.extract(&res, &jar).unwrap())
+            .collect();
+
+        assert_eq!(values, vec!["secret", "7", "abc", "7", "s1"]);
+
+        let other = parse("[[capture]]\nvar = \"other\"\ncookie = \"other\"").unwrap();
+        assert!(other[0].extract(&res, &jar).is_err());
+    }
+
+    #[test]
+    fn capture_parse_malformed() {
+        assert!(parse("[[capture]]\nvar = \"a\"").is_err());
+        assert!(parse("[[capture]]\nvar = \"a\"\njson = \"$.a\"\nheader = \"b\"").is_err());
+        assert!(parse("[[capture]]\njson = \"$.a\"").is_err());
+    }
+}
@@ -25,6 +25,10 @@ pub enum Cmd {
     Init(action::init::Args),
     /// Send request using the current handle's endpoint and outputs the response
     Send(action::send::Args),
+    /// Send request using a handle's endpoint and check its assertions
+    Test(action::test::Args),
+    /// Send every endpoint under a handle, in order, and check their assertions
+    Run(action::run::Args),
     /// Create a new handle
     Create(action::handle::CreateArgs),
     /// Switch handle or edit its endpoint
@@ -87,6 +91,22 @@ pub enum Cmd {
         #[command(subcommand)]
         command: VarCmd,
     },
+    /// Manage current environment's cookies
+    #[command(alias = "cookies")]
+    Cookie {
+        #[command(subcommand)]
+        command: CookieCmd,
+    },
+    /// Create handles from other tools' collections and specifications
+    Import {
+        #[command(subcommand)]
+        command: ImportCmd,
+    },
+    /// Write handles as other tools' collections and specifications
+    Export {
+        #[command(subcommand)]
+        command: ExportCmd,
+    },
     /// Manage configuration for quartz
     Config {
         #[command(subcommand)]
@@ -180,8 +200,36 @@ pub enum ShowCmd {
 
 #[derive(Debug, Subcommand)]
 pub enum SnippetCmd {
+    /// cURL command
     Curl(crate::snippet::Curl),
+
+    /// Raw HTTP message
     Http,
+
+    /// JavaScript using the Fetch API
+    #[command(alias = "js")]
+    Fetch,
+
+    /// Python using the requests package
+    #[command(alias = "requests")]
+    Python,
+
+    /// Go using the net/http package
+    Go,
+
+    /// Rust using the reqwest crate
+    #[command(alias = "rust")]
+    Reqwest,
+
+    /// HTTPie command
+    Httpie,
+
+    /// GNU Wget command
+    Wget,
+
+    /// User-defined snippet, from a template in .quartz/snippets or ~/.config/quartz/snippets
+    #[command(external_subcommand)]
+    Template(Vec<String>),
 }
 
 #[derive(Debug, Subcommand)]
@@ -255,3 +303,61 @@ pub enum VarCmd {
     #[command(name = "ls", alias = "list")]
     Ls,
 }
+
+#[derive(Debug, Subcommand)]
+pub enum CookieCmd {
+    /// Add a new or existent cookie
+    Set(action::cookie::SetArgs),
+
+    /// Remove cookies by name
+    #[command(name = "rm", alias = "remove")]
+    Rm(action::cookie::RmArgs),
+
+    /// Remove every cookie
+    Clear,
+
+    /// Add cookies from a Netscape HTTP Cookie file or JSON file
+    Import(action::cookie::ImportArgs),
+
+    /// Write cookies to a Netscape HTTP Cookie file or JSON file
+    Export(action::cookie::ExportArgs),
+
+    /// Print cookies
+    #[command(name = "ls", alias = "list")]
+    Ls(action::cookie::PrintArgs),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum HistoryCmd {
+    /// Write history entries in formats understood by other tools
+    Export(action::history::ExportArgs),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ImportCmd {
+    /// Import an OpenAPI 3 specification
+    #[command(name = "openapi")]
+    Openapi(action::import::OpenapiArgs),
+
+    /// Import a Postman collection, version 2.1
+    Postman(action::import::PostmanArgs),
+
+    /// Import an Insomnia export, including its environments
+    Insomnia(action::import::InsomniaArgs),
+
+    /// Import requests from a .http file, as used by the VS Code REST Client and JetBrains
+    Http(action::import::HttpArgs),
+
+    /// Import requests from an HTTP Archive (HAR), as saved by browsers and proxies
+    Har(action::import::HarArgs),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ExportCmd {
+    /// Export endpoints as an OpenAPI 3 specification
+    #[command(name = "openapi")]
+    Openapi(action::export::OpenapiArgs),
+
+    /// Export endpoints as requests of a .http file
+    Http(action::export::HttpArgs),
+}
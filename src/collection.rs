@@ -0,0 +1,36 @@
+use crate::{
+    endpoint::{Endpoint, EndpointHandle},
+    env::Variables,
+};
+
+/// Endpoints read from another tool's format, ready to be written as handles.
+#[derive(Debug, Default)]
+pub struct Collection {
+    /// Endpoints in declaration order, with their request body already set.
+    pub endpoints: Vec<(EndpointHandle, Endpoint)>,
+
+    /// Variables the endpoints refer to, such as their base URL.
+    pub variables: Variables,
+
+    /// Features of the source that could not be converted.
+    pub warnings: Vec<String>,
+}
+
+/// Turns `input` into a handle-friendly name: lowercase words separated by dashes.
+///
+/// # Examples
+///
+/// ```
+/// use quartz_cli::collection::slug;
+///
+/// assert_eq!(slug("Swagger Petstore"), "swagger-petstore");
+/// assert_eq!(slug("GET /users/{id}"), "get-users-id");
+/// ```
+pub fn slug(input: &str) -> String {
+    input
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<String>>()
+        .join("-")
+}
@@ -5,6 +5,9 @@ use std::{fs::OpenOptions, io::Write, path::PathBuf};
 pub struct Config {
     pub preferences: Preferences,
     pub ui: UiConfig,
+
+    #[serde(default)]
+    pub history: HistoryConfig,
 }
 
 impl Config {
@@ -102,10 +105,8 @@ impl UiConfig {
             clicolor == "0"
         } else if let Ok(clicolor) = std::env::var("CLICOLOR") {
             clicolor == "0"
-        } else if let Some(colors) = self.colors {
-            colors
         } else {
-            true
+            self.colors.unwrap_or(true)
         }
     }
 
@@ -113,3 +114,22 @@ impl UiConfig {
         self.colors = Some(colors);
     }
 }
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct HistoryConfig {
+    max_body_size: Option<u64>,
+}
+
+impl HistoryConfig {
+    /// Default for [`HistoryConfig::max_body_size`], 10 MiB.
+    pub const MAX_BODY_SIZE: u64 = 10 * 1024 * 1024;
+
+    /// Maximum number of bytes of a response body recorded in history.
+    pub fn max_body_size(&self) -> u64 {
+        self.max_body_size.unwrap_or(Self::MAX_BODY_SIZE)
+    }
+
+    pub fn set_max_body_size(&mut self, size: u64) {
+        self.max_body_size = Some(size);
+    }
+}
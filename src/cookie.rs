@@ -1,10 +1,13 @@
 use crate::QuartzResult;
 use chrono::prelude::*;
-use hyper::http::uri::Scheme;
+use hyper::{http::uri::Scheme, Uri};
+use serde::{Deserialize, Serialize};
 use std::{
     collections::HashSet,
     convert::Infallible,
+    fmt::Display,
     hash::Hash,
+    net::IpAddr,
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
     str::FromStr,
@@ -18,11 +21,51 @@ pub enum Field {
     ExpiresAt,
     Name,
     Value,
+    Attributes,
 }
 
 #[derive(Debug, Clone)]
 pub struct CookieError;
 
+impl Display for CookieError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "malformed cookie")
+    }
+}
+
+impl std::error::Error for CookieError {}
+
+/// Cookie SameSite attribute.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl Display for SameSite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SameSite::Strict => write!(f, "Strict"),
+            SameSite::Lax => write!(f, "Lax"),
+            SameSite::None => write!(f, "None"),
+        }
+    }
+}
+
+impl FromStr for SameSite {
+    type Err = CookieError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "strict" => Ok(SameSite::Strict),
+            "lax" => Ok(SameSite::Lax),
+            "none" => Ok(SameSite::None),
+            _ => Err(CookieError),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Domain(String);
 
@@ -134,6 +177,40 @@ impl Domain {
         true
     }
 
+    /// Whether `host` is this [`Domain`] or one of its subdomains, as in the
+    /// domain-matching algorithm of [RFC 6265](https://datatracker.ietf.org/doc/html/rfc6265#section-5.1.3).
+    ///
+    /// Unlike [`Domain::matches`], a subdomain never includes its parent domain. IP addresses
+    /// only include themselves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::cookie::Domain;
+    ///
+    /// assert!(Domain::new("example.com").includes("example.com"));
+    /// assert!(Domain::new(".example.com").includes("www.example.com"));
+    /// assert!(Domain::new("example.com").includes("sub.www.example.com"));
+    /// assert!(Domain::new("127.0.0.1").includes("127.0.0.1"));
+    ///
+    /// assert_eq!(Domain::new("www.example.com").includes("example.com"), false);
+    /// assert_eq!(Domain::new("example.com").includes("anotherexample.com"), false);
+    /// assert_eq!(Domain::new("0.0.1").includes("127.0.0.1"), false);
+    /// ```
+    #[must_use]
+    pub fn includes<T>(&self, host: T) -> bool
+    where
+        T: Into<Domain>,
+    {
+        let host: Domain = host.into();
+
+        if host.parse::<IpAddr>().is_ok() {
+            return self.trim_start_matches('.') == *host;
+        }
+
+        self.as_segments().count() <= host.as_segments().count() && self.matches(host)
+    }
+
     /// Transforms a string into domain segments from top-level.
     ///
     /// # Examples
@@ -157,6 +234,9 @@ pub struct CookieBuilder {
     subdomains: bool,
     path: Option<String>,
     secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+    partitioned: bool,
     expires_at: i64,
     name: Option<String>,
     value: Option<String>,
@@ -189,6 +269,21 @@ impl CookieBuilder {
         self
     }
 
+    pub fn http_only(&mut self, v: bool) -> &mut Self {
+        self.http_only = v;
+        self
+    }
+
+    pub fn same_site(&mut self, v: Option<SameSite>) -> &mut Self {
+        self.same_site = v;
+        self
+    }
+
+    pub fn partitioned(&mut self, v: bool) -> &mut Self {
+        self.partitioned = v;
+        self
+    }
+
     pub fn expires_at(&mut self, v: i64) -> &mut Self {
         self.expires_at = v;
         self
@@ -226,6 +321,9 @@ impl CookieBuilder {
             subdomains: self.subdomains,
             path: PathAttr::from(self.path.unwrap_or_default().as_str()),
             secure: self.secure,
+            http_only: self.http_only,
+            same_site: self.same_site,
+            partitioned: self.partitioned,
             expires_at: self.expires_at,
             name,
             value,
@@ -239,6 +337,9 @@ pub struct Cookie {
     subdomains: bool,
     path: PathAttr,
     secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+    partitioned: bool,
     expires_at: i64,
     name: String,
     value: String,
@@ -259,9 +360,12 @@ impl Hash for Cookie {
     }
 }
 
-impl ToString for Cookie {
+impl Display for Cookie {
     /// Converts a given [`Cookie`] into a Netspace HTTP Cookie file line.
     ///
+    /// HttpOnly cookies are prefixed with `#HttpOnly_`, like cURL does. SameSite and Partitioned
+    /// attributes, which have no column in that format, are written to an eighth column when set.
+    ///
     ///# Examples
     ///
     /// ```
@@ -280,17 +384,29 @@ impl ToString for Cookie {
     /// assert_eq!(cookie.to_string(),
     /// "httpbin.org\tTRUE\t/somepath\tFALSE\t0\tmysecret\tsupersecretkey");
     /// ```
-    fn to_string(&self) -> String {
-        format!(
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.http_only {
+            write!(f, "{}", Cookie::HTTP_ONLY_PREFIX)?;
+        }
+
+        write!(
+            f,
             "{}\t{}\t{}\t{}\t{}\t{}\t{}",
             *self.domain,
             self.subdomains.to_string().to_uppercase(),
-            self.path.to_string(),
+            self.path,
             self.secure.to_string().to_uppercase(),
-            self.expires_at,
+            self.expires_at_seconds(),
             self.name,
             self.value,
-        )
+        )?;
+
+        let attributes = self.attributes();
+        if !attributes.is_empty() {
+            write!(f, "\t{attributes}")?;
+        }
+
+        Ok(())
     }
 }
 
@@ -318,15 +434,44 @@ impl FromStr for Cookie {
     /// assert_eq!(cookie.secure(), true);
     /// assert_eq!(cookie.name(), "mycookie");
     /// assert_eq!(cookie.value(), "secret");
+    ///
+    /// let s = "#HttpOnly_httpbin.org\tFALSE\t/\tTRUE\t0\tsession\tsecret\tSameSite=Lax";
+    /// let cookie = Cookie::from_str(s).unwrap();
+    ///
+    /// assert!(cookie.http_only());
+    /// assert_eq!(**cookie.domain(), "httpbin.org");
+    /// assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+    /// # use quartz_cli::cookie::SameSite;
     /// ```
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut cookie = Cookie::builder();
-        let line: Vec<&str> = s.splitn(7, '\t').collect();
 
-        if line.len() != 7 {
+        let s = match s.strip_prefix(Cookie::HTTP_ONLY_PREFIX) {
+            Some(s) => {
+                cookie.http_only(true);
+                s
+            }
+            None => s,
+        };
+
+        let line: Vec<&str> = s.splitn(8, '\t').collect();
+
+        if line.len() < 7 {
             return Err(CookieError);
         }
 
+        if let Some(attributes) = line.get(Field::Attributes as usize) {
+            for attribute in attributes.split(';') {
+                let (key, value) = CookieJar::pair(attribute).unwrap_or((attribute.trim(), ""));
+
+                match key.to_lowercase().as_str() {
+                    "samesite" => cookie.same_site(value.parse().ok()),
+                    "partitioned" => cookie.partitioned(true),
+                    _ => &mut cookie,
+                };
+            }
+        }
+
         cookie
             .domain(line[Field::Domain as usize])
             .subdomains(line[Field::Subdomains as usize] == "TRUE")
@@ -335,8 +480,14 @@ impl FromStr for Cookie {
             .name(line[Field::Name as usize])
             .value(line[Field::Value as usize]);
 
-        if let Ok(v) = line[4].parse() {
-            cookie.expires_at(v);
+        if let Ok(v) = line[Field::ExpiresAt as usize].parse::<i64>() {
+            // Older quartz versions wrote microseconds instead of seconds. Such values would
+            // only be reached in seconds millions of years from now.
+            if v >= 100_000_000_000_000 {
+                cookie.expires_at(v);
+            } else {
+                cookie.expires_at(v.saturating_mul(1_000_000));
+            }
         }
 
         cookie.build()
@@ -344,23 +495,66 @@ impl FromStr for Cookie {
 }
 
 impl Cookie {
+    /// Netscape HTTP Cookie file prefix to mark HttpOnly cookies.
+    pub const HTTP_ONLY_PREFIX: &'static str = "#HttpOnly_";
+
     pub fn builder() -> CookieBuilder {
         CookieBuilder::default()
     }
 
-    pub fn matches<T>(&self, req: hyper::Request<T>) -> bool {
-        if !self.domain().matches(req.uri().host().unwrap_or_default()) {
+    /// Whether this cookie should be sent in a request to `uri`, according to
+    /// [RFC 6265](https://datatracker.ietf.org/doc/html/rfc6265#section-5.4).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Uri;
+    /// use quartz_cli::cookie::Cookie;
+    ///
+    /// let mut cookie = Cookie::builder();
+    /// cookie
+    ///     .domain("example.com")
+    ///     .subdomains(true)
+    ///     .path("/api")
+    ///     .secure(true)
+    ///     .name("session")
+    ///     .value("secret");
+    ///
+    /// let cookie = cookie.build().unwrap();
+    ///
+    /// assert!(cookie.matches(&Uri::from_static("https://example.com/api")));
+    /// assert!(cookie.matches(&Uri::from_static("https://www.example.com/api/users")));
+    ///
+    /// assert_eq!(cookie.matches(&Uri::from_static("http://example.com/api")), false);
+    /// assert_eq!(cookie.matches(&Uri::from_static("https://example.com/")), false);
+    /// assert_eq!(cookie.matches(&Uri::from_static("https://example.org/api")), false);
+    /// ```
+    #[must_use]
+    pub fn matches(&self, uri: &Uri) -> bool {
+        if self.expired() {
+            return false;
+        }
+
+        let host = uri.host().unwrap_or_default();
+        let domain_matches = if self.subdomains() {
+            self.domain().includes(host)
+        } else {
+            // Host-only cookie
+            **self.domain() == Domain::canonicalize(host)
+        };
+
+        if !domain_matches {
             return false;
         }
 
         if self.secure() {
-            let scheme = req.uri().scheme().unwrap_or(&Scheme::HTTP);
+            let scheme = uri.scheme().unwrap_or(&Scheme::HTTP);
             if scheme == &Scheme::HTTP {
                 return false;
             }
         }
 
-        if !self.path().matches(req.uri().path()) {
+        if !self.path().matches(uri.path()) {
             return false;
         }
 
@@ -388,10 +582,48 @@ impl Cookie {
         self.secure
     }
 
+    pub fn http_only(&self) -> bool {
+        self.http_only
+    }
+
+    pub fn same_site(&self) -> Option<SameSite> {
+        self.same_site
+    }
+
+    pub fn partitioned(&self) -> bool {
+        self.partitioned
+    }
+
+    /// Attributes without a Netscape HTTP Cookie file column, in Set-Cookie syntax.
+    fn attributes(&self) -> String {
+        let mut attributes = Vec::new();
+
+        if let Some(same_site) = self.same_site {
+            attributes.push(format!("SameSite={same_site}"));
+        }
+
+        if self.partitioned {
+            attributes.push("Partitioned".to_string());
+        }
+
+        attributes.join("; ")
+    }
+
+    /// Expiration timestamp in microseconds. Zero means it is a session cookie.
     pub fn expires_at(&self) -> i64 {
         self.expires_at
     }
 
+    /// Expiration timestamp in seconds, rounded up so expired cookies are not mistaken
+    /// for session ones.
+    pub fn expires_at_seconds(&self) -> i64 {
+        if self.expires_at > 0 {
+            (self.expires_at - 1) / 1_000_000 + 1
+        } else {
+            0
+        }
+    }
+
     pub fn name(&self) -> &str {
         self.name.as_ref()
     }
@@ -421,56 +653,108 @@ impl DerefMut for CookieJar {
     }
 }
 
-impl ToString for CookieJar {
-    fn to_string(&self) -> String {
-        let mut jar = String::new();
+impl Display for CookieJar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", CookieJar::HEADER)?;
+
+        let mut cookies: Vec<&Cookie> = self.iter().collect();
+        cookies.sort_by(|a, b| (&**a.domain(), a.name()).cmp(&(&**b.domain(), b.name())));
 
-        for cookie in self.iter() {
-            jar.push_str(&cookie.to_string());
-            jar.push('\n');
+        for cookie in cookies {
+            writeln!(f, "{cookie}")?;
         }
 
-        jar
+        Ok(())
     }
 }
 
 impl CookieJar {
     fn pair(v: &str) -> Option<(&str, &str)> {
-        v.trim().split_once('=')
+        let (key, value) = v.split_once('=')?;
+
+        Some((key.trim(), value.trim()))
     }
 
-    /// Insert new [`Cookie`] from Set-Cookie `input` from `domain`.
-    pub fn set(&mut self, domain: &str, input: &'_ str) -> Cookie {
+    /// Insert new [`Cookie`] from Set-Cookie `input` received from `origin`, following
+    /// [RFC 6265](https://datatracker.ietf.org/doc/html/rfc6265#section-5.2).
+    ///
+    /// `origin` is the request URL, or just its host. Attributes with invalid values are ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` has no name-value pair, or its Domain
+    /// attribute does not include `origin` host. In such case, the cookie must be ignored.
+    pub fn set(&mut self, origin: &str, input: &'_ str) -> QuartzResult<Cookie, CookieError> {
+        let uri = Uri::from_str(origin).map_err(|_| CookieError)?;
+        let host = uri.host().ok_or(CookieError)?;
+
         let mut cookie = Cookie::builder();
-        cookie.domain(domain);
+        cookie.domain(host).path(Self::default_path(uri.path()));
 
         let (pair, settings) = input.split_once(';').unwrap_or((input, ""));
 
-        let (key, value) = Self::pair(pair).unwrap_or_else(|| panic!("malformed cookie: {}", pair));
+        let (key, value) = Self::pair(pair).ok_or(CookieError)?;
+        if key.is_empty() {
+            return Err(CookieError);
+        }
 
         cookie.name(key);
         cookie.value(value);
 
+        let mut max_age = None;
+        let mut expires = None;
+
         for v in settings.split(';') {
-            let (key, value) = Self::pair(v).unwrap_or((v, ""));
+            let (key, value) = Self::pair(v).unwrap_or((v.trim(), ""));
 
             match key.to_lowercase().as_str() {
-                "domain" => cookie.domain(value),
-                "path" => cookie.path(value),
+                "domain" if !value.is_empty() => {
+                    let domain = Domain::new(value.trim_start_matches('.'));
+
+                    if !domain.includes(host) {
+                        return Err(CookieError);
+                    }
+
+                    cookie.domain(domain.as_str()).subdomains(true)
+                }
+                "path" if value.starts_with('/') => cookie.path(value),
                 "secure" => cookie.secure(true),
+                "httponly" => cookie.http_only(true),
+                "samesite" => match value.parse() {
+                    Ok(same_site) => cookie.same_site(Some(same_site)),
+                    Err(_) => &mut cookie,
+                },
+                "partitioned" => cookie.partitioned(true),
                 "max-age" => {
-                    cookie.expires_at(value.parse::<i64>().unwrap() + Utc::now().timestamp_micros())
+                    if let Ok(seconds) = value.parse::<i64>() {
+                        max_age = Some(seconds);
+                    }
+                    &mut cookie
+                }
+                "expires" => {
+                    if let Some(date) = Self::parse_date(value) {
+                        expires = Some(date.timestamp_micros());
+                    }
+                    &mut cookie
                 }
-                "expires" => cookie.expires_at(
-                    DateTime::parse_from_rfc2822(value)
-                        .unwrap()
-                        .timestamp_micros(),
-                ),
                 _ => &mut cookie,
             };
         }
 
-        let cookie = cookie.build().unwrap();
+        // Max-Age has precedence over Expires
+        match (max_age, expires) {
+            // Earliest representable time, so the cookie is immediately expired
+            (Some(seconds), _) if seconds <= 0 => cookie.expires_at(1),
+            (Some(seconds), _) => cookie.expires_at(
+                Utc::now()
+                    .timestamp_micros()
+                    .saturating_add(seconds.saturating_mul(1_000_000)),
+            ),
+            (None, Some(expires)) => cookie.expires_at(expires.max(1)),
+            (None, None) => &mut cookie,
+        };
+
+        let cookie = cookie.build()?;
 
         // Removing existing cookie ensures it is possible to
         // overwrite its value.
@@ -484,7 +768,159 @@ impl CookieJar {
             self.insert(cookie.clone());
         }
 
-        cookie
+        Ok(cookie)
+    }
+
+    /// Cookie path used when Set-Cookie has no Path attribute: the directory of
+    /// the request `path`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::cookie::CookieJar;
+    ///
+    /// assert_eq!(CookieJar::default_path(""), "/");
+    /// assert_eq!(CookieJar::default_path("/"), "/");
+    /// assert_eq!(CookieJar::default_path("/login"), "/");
+    /// assert_eq!(CookieJar::default_path("/api/login"), "/api");
+    /// assert_eq!(CookieJar::default_path("/api/v1/"), "/api/v1");
+    /// ```
+    pub fn default_path(path: &str) -> &str {
+        if !path.starts_with('/') {
+            return "/";
+        }
+
+        match path.rfind('/') {
+            Some(0) | None => "/",
+            Some(idx) => &path[..idx],
+        }
+    }
+
+    /// Parses a cookie-date `input` through the algorithm described in
+    /// [RFC 6265](https://datatracker.ietf.org/doc/html/rfc6265#section-5.1.1), which
+    /// accepts all date formats found in the wild.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::cookie::CookieJar;
+    ///
+    /// let expected = 784111777;
+    ///
+    /// let date = CookieJar::parse_date("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+    /// assert_eq!(date.timestamp(), expected);
+    ///
+    /// let date = CookieJar::parse_date("Sunday, 06-Nov-94 08:49:37 GMT").unwrap();
+    /// assert_eq!(date.timestamp(), expected);
+    ///
+    /// let date = CookieJar::parse_date("Sun Nov  6 08:49:37 1994").unwrap();
+    /// assert_eq!(date.timestamp(), expected);
+    ///
+    /// assert!(CookieJar::parse_date("Sun, 06 Nov 1994").is_none());
+    /// assert!(CookieJar::parse_date("Wed, 31 Feb 2024 08:49:37 GMT").is_none());
+    /// assert!(CookieJar::parse_date("tomorrow").is_none());
+    /// ```
+    pub fn parse_date(input: &str) -> Option<DateTime<Utc>> {
+        let is_delimiter = |ch: char| {
+            matches!(
+                ch,
+                '\x09' | '\x20'..='\x2F' | '\x3B'..='\x40' | '\x5B'..='\x60' | '\x7B'..='\x7E'
+            )
+        };
+
+        // Reads `min` to `max` leading digits, as long as they are not followed by another digit.
+        let digits = |token: &str, min: usize, max: usize| -> Option<(u32, usize)> {
+            let len = token.chars().take_while(|ch| ch.is_ascii_digit()).count();
+
+            if len < min || len > max {
+                return None;
+            }
+
+            Some((token[..len].parse().ok()?, len))
+        };
+
+        let mut time = None;
+        let mut day = None;
+        let mut month = None;
+        let mut year = None;
+
+        for token in input.split(is_delimiter).filter(|t| !t.is_empty()) {
+            if time.is_none() {
+                let mut parts = token.splitn(3, ':');
+                let exact = |part: &str| {
+                    digits(part, 1, 2)
+                        .filter(|(_, len)| *len == part.len())
+                        .map(|(v, _)| v)
+                };
+
+                if let (Some(h), Some(m), Some(s)) = (parts.next(), parts.next(), parts.next()) {
+                    if let (Some(h), Some(m), Some((s, _))) = (exact(h), exact(m), digits(s, 1, 2))
+                    {
+                        time = Some((h, m, s));
+                        continue;
+                    }
+                }
+            }
+
+            if day.is_none() {
+                if let Some((v, _)) = digits(token, 1, 2) {
+                    day = Some(v);
+                    continue;
+                }
+            }
+
+            if month.is_none() {
+                let months = [
+                    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov",
+                    "dec",
+                ];
+
+                let prefix = token.get(..3).unwrap_or_default().to_ascii_lowercase();
+                if let Some(idx) = months.iter().position(|m| *m == prefix) {
+                    month = Some(idx as u32 + 1);
+                    continue;
+                }
+            }
+
+            if year.is_none() {
+                if let Some((v, _)) = digits(token, 2, 4) {
+                    year = Some(v);
+                    continue;
+                }
+            }
+        }
+
+        let (hour, minute, second) = time?;
+        let day = day?;
+        let month = month?;
+        let year = match year? {
+            y @ 70..=99 => y + 1900,
+            y @ 0..=69 => y + 2000,
+            y => y,
+        };
+
+        if !(1..=31).contains(&day) || year < 1601 || hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+
+        Utc.with_ymd_and_hms(year as i32, month, day, hour, minute, second)
+            .single()
+    }
+
+    /// Cookies to be sent in a request to `uri`, with longer paths listed first.
+    pub fn select(&self, uri: &Uri) -> Vec<&Cookie> {
+        let mut cookies: Vec<&Cookie> = self.iter().filter(|c| c.matches(uri)).collect();
+
+        // RFC 6265 orders cookies with the same path length by creation time, which is not
+        // stored, so their name is used to keep the header stable.
+        cookies.sort_by(|a, b| {
+            b.path()
+                .len()
+                .cmp(&a.path().len())
+                .then_with(|| a.name().cmp(b.name()))
+        });
+
+        cookies
     }
 
     pub fn find_by_name(&self, s: &str) -> Vec<&Cookie> {
@@ -495,6 +931,9 @@ impl CookieJar {
 impl CookieJar {
     pub const FILENAME: &'static str = "cookies";
 
+    /// First line of Netscape HTTP Cookie files, which tools like cURL use to recognize them.
+    pub const HEADER: &'static str = "# Netscape HTTP Cookie File";
+
     /// Read [`CookieJar`] struct from Netscape HTTP Cookie file.
     /// Empty, malformed, or commented (starting with "#") lines will be skipped.
     ///
@@ -504,12 +943,23 @@ impl CookieJar {
     ///
     /// This function will return an error if the file does not exist.
     pub fn read(path: &Path) -> QuartzResult<Self> {
-        let mut cookies = Self::default();
         let file = std::fs::read_to_string(path)?;
-        let lines = file.lines();
+        let mut cookies = Self::parse(&file);
+
+        cookies.path = path.to_path_buf();
+        Ok(cookies)
+    }
+
+    /// Parse [`CookieJar`] from Netscape HTTP Cookie file `content`.
+    ///
+    /// Empty, malformed, commented or expired lines are skipped, just as in [`CookieJar::read`].
+    pub fn parse(content: &str) -> Self {
+        let mut cookies = Self::default();
 
-        for line in lines {
-            if line.is_empty() || line.starts_with('#') {
+        for line in content.lines() {
+            let http_only = line.starts_with(Cookie::HTTP_ONLY_PREFIX);
+
+            if line.is_empty() || (line.starts_with('#') && !http_only) {
                 continue;
             }
 
@@ -520,10 +970,90 @@ impl CookieJar {
             }
         }
 
-        cookies.path = path.to_path_buf();
+        cookies
+    }
+
+    /// Parse [`CookieJar`] from a JSON array of cookies, in the format used by browser
+    /// extensions and [`CookieJar::to_json`].
+    ///
+    /// Expired cookies are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::cookie::CookieJar;
+    ///
+    /// let input = r#"[
+    ///     { "name": "session", "value": "secret", "domain": ".example.com", "path": "/" },
+    ///     { "name": "old", "value": "1", "domain": "example.com", "expirationDate": 1 }
+    /// ]"#;
+    ///
+    /// let jar = CookieJar::from_json(input).unwrap();
+    /// assert_eq!(jar.len(), 1);
+    ///
+    /// let cookie = jar.find_by_name("session")[0];
+    /// assert_eq!(**cookie.domain(), "example.com");
+    /// assert!(cookie.subdomains());
+    /// ```
+    pub fn from_json(input: &str) -> QuartzResult<Self> {
+        let list: Vec<JsonCookie> = serde_json::from_str(input)?;
+        let mut cookies = Self::default();
+
+        for item in list {
+            let mut cookie = Cookie::builder();
+            let host_only = item.host_only.unwrap_or(!item.domain.starts_with('.'));
+
+            cookie
+                .domain(item.domain.trim_start_matches('.'))
+                .subdomains(!host_only)
+                .path(item.path)
+                .secure(item.secure)
+                .http_only(item.http_only)
+                .same_site(item.same_site.and_then(|v| v.parse().ok()))
+                .partitioned(item.partitioned)
+                .name(item.name)
+                .value(item.value);
+
+            if let Some(seconds) = item.expiration_date {
+                // Truncated expiration would turn it into a session cookie
+                cookie.expires_at(((seconds * 1_000_000.0) as i64).max(1));
+            }
+
+            let cookie = cookie.build()?;
+            if !cookie.expired() {
+                cookies.replace(cookie);
+            }
+        }
+
         Ok(cookies)
     }
 
+    /// Converts cookies into a JSON array, readable by [`CookieJar::from_json`].
+    pub fn to_json(&self) -> QuartzResult<String> {
+        let mut list: Vec<JsonCookie> = self
+            .iter()
+            .map(|c| JsonCookie {
+                domain: c.domain().to_string(),
+                host_only: Some(!c.subdomains()),
+                path: c.path().to_string(),
+                secure: c.secure(),
+                http_only: c.http_only(),
+                same_site: c.same_site().map(|v| v.to_string()),
+                partitioned: c.partitioned(),
+                expiration_date: match c.expires_at() {
+                    0 => None,
+                    _ => Some(c.expires_at_seconds() as f64),
+                },
+                name: c.name().to_string(),
+                value: c.value().to_string(),
+            })
+            .collect();
+
+        list.sort_by(|a, b| (&a.domain, &a.name).cmp(&(&b.domain, &b.name)));
+
+        Ok(serde_json::to_string_pretty(&list)?)
+    }
+
     /// Write cookie jar contents to environment cookie jar in Netspace HTTP Cookie file format.
     pub fn write(&self) -> std::io::Result<()> {
         self.write_at(&self.path)
@@ -535,6 +1065,36 @@ impl CookieJar {
     }
 }
 
+/// Cookie representation in JSON cookie files.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonCookie {
+    domain: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    host_only: Option<bool>,
+    #[serde(default = "JsonCookie::default_path")]
+    path: String,
+    #[serde(default)]
+    secure: bool,
+    #[serde(default)]
+    http_only: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    same_site: Option<String>,
+    #[serde(default)]
+    partitioned: bool,
+    /// Seconds since UNIX epoch. Session cookies have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expiration_date: Option<f64>,
+    name: String,
+    value: String,
+}
+
+impl JsonCookie {
+    fn default_path() -> String {
+        String::from("/")
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct PathAttr(Vec<String>);
 
@@ -606,7 +1166,7 @@ impl From<&str> for PathAttr {
     }
 }
 
-impl ToString for PathAttr {
+impl Display for PathAttr {
     /// Converts this into a Path attribute-value string.
     ///
     /// # Examples
@@ -615,12 +1175,8 @@ impl ToString for PathAttr {
     /// use quartz_cli::cookie::PathAttr;
     ///
     /// ```
-    fn to_string(&self) -> String {
-        let mut s = String::from("/");
-
-        s.push_str(&self.join("/"));
-
-        s
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "/{}", self.join("/"))
     }
 }
 
@@ -676,8 +1232,8 @@ mod test {
     fn jar_set_overwrite() {
         let mut jar = CookieJar::default();
 
-        jar.set("example.com", "foo=bar");
-        jar.set("example.com", "foo=baz");
+        jar.set("example.com", "foo=bar").unwrap();
+        jar.set("example.com", "foo=baz").unwrap();
 
         let found = jar.find_by_name("foo");
         assert_eq!(found.len(), 1);
@@ -688,8 +1244,8 @@ mod test {
     fn jar_set_same_name_different_domain() {
         let mut jar = CookieJar::default();
 
-        jar.set("example.com", "mycookie=true");
-        jar.set("httpbin.org", "mycookie=false");
+        jar.set("example.com", "mycookie=true").unwrap();
+        jar.set("httpbin.org", "mycookie=false").unwrap();
 
         let cookies = jar.find_by_name("mycookie");
         assert_eq!(cookies.len(), 2);
@@ -709,24 +1265,161 @@ mod test {
         assert_eq!(cookie.value(), "false");
     }
 
+    #[test]
+    fn jar_set_attributes() {
+        let mut jar = CookieJar::default();
+
+        let cookie = jar
+            .set(
+                "https://www.example.com/api/login",
+                "session=abc; Domain=.Example.com; Secure; HttpOnly; SameSite=Lax; Partitioned",
+            )
+            .unwrap();
+
+        assert_eq!(**cookie.domain(), "example.com");
+        assert!(cookie.subdomains());
+        assert_eq!(cookie.path().to_string(), "/api");
+        assert!(cookie.secure());
+        assert!(cookie.http_only());
+        assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+        assert!(cookie.partitioned());
+
+        let line = cookie.to_string();
+        assert_eq!(
+            line,
+            "#HttpOnly_example.com\tTRUE\t/api\tTRUE\t0\tsession\tabc\tSameSite=Lax; Partitioned"
+        );
+
+        let parsed = Cookie::from_str(&line).unwrap();
+        assert!(parsed.http_only());
+        assert_eq!(parsed.same_site(), Some(SameSite::Lax));
+        assert!(parsed.partitioned());
+    }
+
+    #[test]
+    fn jar_set_expiration() {
+        let mut jar = CookieJar::default();
+
+        let cookie = jar.set("example.com", "a=1; Max-Age=60").unwrap();
+        assert!(!cookie.expired());
+        assert!(cookie.expires_at() > Utc::now().timestamp_micros());
+        assert!(cookie.expires_at() <= Utc::now().timestamp_micros() + 60_000_000);
+
+        // Max-Age has precedence over Expires
+        let cookie = jar
+            .set(
+                "example.com",
+                "b=1; Max-Age=60; Expires=Sun, 06 Nov 1994 08:49:37 GMT",
+            )
+            .unwrap();
+        assert!(!cookie.expired());
+
+        let cookie = jar.set("example.com", "c=1; Max-Age=0").unwrap();
+        assert!(cookie.expired());
+
+        // Invalid attributes are ignored, but the cookie is kept
+        let cookie = jar
+            .set("example.com", "d=1; Max-Age=soon; Expires=someday")
+            .unwrap();
+        assert_eq!(cookie.expires_at(), 0);
+
+        assert_eq!(jar.len(), 3);
+    }
+
+    #[test]
+    fn jar_set_malformed() {
+        let mut jar = CookieJar::default();
+
+        assert!(jar.set("example.com", "novalue").is_err());
+        assert!(jar.set("example.com", "=value").is_err());
+        assert!(jar.set("www.example.com", "a=1; Domain=other.com").is_err());
+        assert!(jar.is_empty());
+    }
+
+    #[test]
+    fn jar_select() {
+        let mut jar = CookieJar::default();
+
+        for line in [
+            "example.com\tFALSE\t/\tFALSE\t0\troot\t1",
+            "example.com\tFALSE\t/api/v1\tFALSE\t0\tnested\t2",
+            "example.com\tFALSE\t/api\tFALSE\t0\tapi\t3",
+            "example.com\tFALSE\t/\tTRUE\t0\tsecure\t4",
+            "www.example.com\tFALSE\t/\tFALSE\t0\tsub\t5",
+            "example.com\tFALSE\t/\tFALSE\t1\texpired\t6",
+        ] {
+            jar.insert(Cookie::from_str(line).unwrap());
+        }
+
+        let uri = Uri::from_static("http://example.com/api/v1/users");
+        let names: Vec<&str> = jar.select(&uri).iter().map(|c| c.name()).collect();
+        assert_eq!(names, vec!["nested", "api", "root"]);
+
+        let uri = Uri::from_static("https://example.com/");
+        let names: Vec<&str> = jar.select(&uri).iter().map(|c| c.name()).collect();
+        assert_eq!(names, vec!["root", "secure"]);
+    }
+
     #[test]
     fn jar_set_remove() {
         let mut jar = CookieJar::default();
 
-        let foo = jar.set("httpbin.org", "foo=bar");
-        let baz = jar.set("httpbin.org", "baz=baz");
+        let foo = jar.set("httpbin.org", "foo=bar").unwrap();
+        let baz = jar.set("httpbin.org", "baz=baz").unwrap();
         assert_eq!(jar.len(), 2);
         assert!(jar.contains(&foo));
 
-        jar.set("httpbin.org", "foo=; Expires=Sun, 06 Nov 1994 08:49:37 GMT");
+        jar.set("httpbin.org", "foo=; Expires=Sun, 06 Nov 1994 08:49:37 GMT")
+            .unwrap();
         assert_eq!(jar.len(), 1);
         assert!(!jar.contains(&foo));
 
         jar.set(
             "httpbin.org",
             "baz=bar; Expires=Sun, 06 Nov 1994 08:49:37 GMT",
-        );
+        )
+        .unwrap();
         assert_eq!(jar.len(), 0);
         assert!(!jar.contains(&baz));
     }
+
+    #[test]
+    fn jar_expiration_seconds() {
+        let jar = CookieJar::parse(
+            "example.com\tFALSE\t/\tFALSE\t32503680000\tseconds\t1\n\
+             example.com\tFALSE\t/\tFALSE\t32503680000000000\tmicros\t2\n",
+        );
+
+        for name in ["seconds", "micros"] {
+            let cookie = jar.find_by_name(name)[0];
+            assert_eq!(cookie.expires_at(), 32503680000000000);
+            assert!(cookie.to_string().contains("\t32503680000\t"));
+        }
+    }
+
+    #[test]
+    fn jar_json_roundtrip() {
+        let mut jar = CookieJar::default();
+        jar.set(
+            "https://example.com/api/users",
+            "session=abc; Domain=example.com; Secure; HttpOnly; SameSite=Strict; Max-Age=60",
+        )
+        .unwrap();
+        jar.set("https://example.com", "theme=dark").unwrap();
+
+        let parsed = CookieJar::from_json(&jar.to_json().unwrap()).unwrap();
+        assert_eq!(parsed.len(), 2);
+
+        let session = parsed.find_by_name("session")[0];
+        assert!(session.subdomains());
+        assert!(session.secure());
+        assert!(session.http_only());
+        assert_eq!(session.same_site(), Some(SameSite::Strict));
+        assert_eq!(session.path().to_string(), "/api");
+        assert!(session.expires_at() > 0);
+
+        let theme = parsed.find_by_name("theme")[0];
+        assert!(!theme.subdomains());
+        assert_eq!(theme.expires_at(), 0);
+    }
 }
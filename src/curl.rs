@@ -0,0 +1,524 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::{
+    encoding::base64,
+    endpoint::Endpoint,
+    form::{self, Field, Form},
+};
+
+/// Endpoint read from a cURL command line.
+#[derive(Debug, Default)]
+pub struct Command {
+    pub endpoint: Endpoint,
+
+    /// Options that could not be converted.
+    pub warnings: Vec<String>,
+}
+
+/// Options ignored because they change how cURL behaves rather than the request itself.
+const FLAGS: [&str; 22] = [
+    "-L",
+    "--location",
+    "-k",
+    "--insecure",
+    "-s",
+    "--silent",
+    "-S",
+    "--show-error",
+    "-v",
+    "--verbose",
+    "-i",
+    "--include",
+    "-f",
+    "--fail",
+    "--compressed",
+    "--http1.1",
+    "--http2",
+    "--http2-prior-knowledge",
+    "--globoff",
+    "-g",
+    "-#",
+    "--progress-bar",
+];
+
+/// Options ignored along with their value.
+const IGNORED: [&str; 8] = [
+    "-o",
+    "--output",
+    "-w",
+    "--write-out",
+    "-c",
+    "--cookie-jar",
+    "--retry",
+    "--proxy",
+];
+
+/// Parses a cURL command, as copied from browser devtools, into an endpoint. Words are split
+/// the way a POSIX shell does, including `$'...'` quoting.
+///
+/// # Examples
+///
+/// ```
+/// use quartz_cli::curl;
+///
+/// let command = curl::parse(
+///     "curl 'https://example.com/users?page=2' -H 'Accept: application/json' --data-raw '{\"name\":\"Rex\"}'",
+/// )
+/// .unwrap();
+///
+/// let endpoint = command.endpoint;
+/// assert_eq!(endpoint.method, "POST");
+/// assert_eq!(endpoint.url, "https://example.com/users");
+/// assert_eq!(endpoint.query.get("page").unwrap(), "2");
+/// assert_eq!(endpoint.body.as_deref(), Some("{\"name\":\"Rex\"}".as_bytes()));
+/// ```
+pub fn parse(input: &str) -> Result<Command, String> {
+    let words = split(input)?;
+    let mut words = words.into_iter().peekable();
+
+    if words.peek().is_some_and(|word| word == "curl") {
+        words.next();
+    }
+
+    let mut command = Command::default();
+    let mut method: Option<String> = None;
+    let mut url: Option<String> = None;
+    let mut data: Vec<String> = Vec::new();
+    let mut form: Vec<Field> = Vec::new();
+    let mut json: Vec<String> = Vec::new();
+    let mut get = false;
+    let mut head = false;
+
+    while let Some(word) = words.next() {
+        if !word.starts_with('-') || word == "-" {
+            url.get_or_insert(word);
+            continue;
+        }
+
+        // Short options may be grouped, as in "-sSL", or followed by their value, as in "-XPOST"
+        let (option, attached) = match word.strip_prefix('-') {
+            Some(rest) if !rest.starts_with('-') && rest.len() > 1 => {
+                if rest
+                    .chars()
+                    .all(|c| FLAGS.contains(&format!("-{c}").as_str()))
+                {
+                    continue;
+                }
+
+                let (option, value) = rest.split_at(1);
+                (format!("-{option}"), Some(value.to_string()))
+            }
+            _ => (word, None),
+        };
+
+        if FLAGS.contains(&option.as_str()) {
+            continue;
+        }
+
+        let mut value = || {
+            attached
+                .clone()
+                .or_else(|| words.next())
+                .ok_or_else(|| format!("missing value for {option}"))
+        };
+
+        match option.as_str() {
+            "-X" | "--request" => method = Some(value()?),
+            "--url" => url = Some(value()?),
+            "-H" | "--header" => {
+                let header = value()?;
+                match header.split_once(':') {
+                    Some((key, value)) => {
+                        command
+                            .endpoint
+                            .headers
+                            .insert(key.trim().to_string(), value.trim().to_string());
+                    }
+                    // "Key;" sends the header with an empty value
+                    None => match header.strip_suffix(';') {
+                        Some(key) => {
+                            command
+                                .endpoint
+                                .headers
+                                .insert(key.trim().to_string(), String::new());
+                        }
+                        None => command.warnings.push(format!("malformed header: {header}")),
+                    },
+                }
+            }
+            "--data-urlencode" => {
+                let content = value()?;
+
+                // Only the value is encoded, the name is sent as given
+                let content = match content.split_once('=') {
+                    Some(("", value)) => form::encode(value),
+                    Some((name, value)) => format!("{name}={}", form::encode(value)),
+                    None if content.contains('@') => {
+                        command.warnings.push(format!(
+                            "{option} {content}: bodies read from files are not supported"
+                        ));
+                        continue;
+                    }
+                    None => form::encode(&content),
+                };
+
+                data.push(content);
+            }
+            "-d" | "--data" | "--data-ascii" | "--data-binary" | "--data-raw" | "--json" => {
+                let mut content = value()?;
+
+                if content.starts_with('@') && option != "--data-raw" {
+                    command.warnings.push(format!(
+                        "{option} {content}: bodies read from files are not supported"
+                    ));
+                    continue;
+                }
+
+                if option == "-d" || option == "--data" || option == "--data-ascii" {
+                    content.retain(|c| c != '\n' && c != '\r');
+                }
+
+                // Unlike the other data options, curl appends `--json` parts with no separator
+                if option == "--json" {
+                    json.push(content);
+                } else {
+                    data.push(content);
+                }
+            }
+            "-F" | "--form" => form.push(Field::parse(&value()?)?),
+            "--form-string" => form.push(Field::text(&value()?)?),
+            "-G" | "--get" => get = true,
+            "-I" | "--head" => head = true,
+            "-b" | "--cookie" => {
+                let cookie = value()?;
+
+                if cookie.contains('=') {
+                    command.endpoint.headers.insert("Cookie".into(), cookie);
+                } else {
+                    command
+                        .warnings
+                        .push(format!("{option} {cookie}: cookie files are not supported"));
+                }
+            }
+            "-u" | "--user" => {
+                let credentials = value()?;
+                command.endpoint.headers.insert(
+                    "Authorization".into(),
+                    format!("Basic {}", base64(credentials.as_bytes())),
+                );
+            }
+            "-A" | "--user-agent" => {
+                command
+                    .endpoint
+                    .headers
+                    .insert("User-Agent".into(), value()?);
+            }
+            "-e" | "--referer" => {
+                command.endpoint.headers.insert("Referer".into(), value()?);
+            }
+            "-m" | "--max-time" => command.endpoint.timeout.total = Some(millis(&value()?)?),
+            "--connect-timeout" => command.endpoint.timeout.connect = Some(millis(&value()?)?),
+            _ if IGNORED.contains(&option.as_str()) => {
+                value()?;
+            }
+            _ => command
+                .warnings
+                .push(format!("{option}: option is not supported")),
+        }
+    }
+
+    let url = url.ok_or("missing URL in cURL command")?;
+    let (base, query) = url.split_once('?').unwrap_or((&url, ""));
+    command.endpoint.url = base.to_string();
+
+    let mut params: Vec<&str> = query.split('&').collect();
+    let mut data = data.join("&");
+    data.push_str(&json.concat());
+    if get {
+        params.extend(data.split('&'));
+    } else if !data.is_empty() {
+        let headers = &mut command.endpoint.headers;
+        let has_content_type = headers
+            .keys()
+            .any(|key| key.eq_ignore_ascii_case("content-type"));
+
+        if !json.is_empty() {
+            if !has_content_type {
+                headers.insert("Content-Type".into(), "application/json".into());
+            }
+            if !headers.keys().any(|key| key.eq_ignore_ascii_case("accept")) {
+                headers.insert("Accept".into(), "application/json".into());
+            }
+        } else if !has_content_type {
+            headers.insert(
+                "Content-Type".into(),
+                "application/x-www-form-urlencoded".into(),
+            );
+        }
+
+        command.endpoint.body = Some(data.into_bytes());
+    }
+
+    if !form.is_empty() {
+        command
+            .endpoint
+            .headers
+            .retain(|key, _| !key.eq_ignore_ascii_case("content-type"));
+        command.endpoint.form = Some(Form::Multipart(form));
+    }
+
+    for param in params.into_iter().filter(|p| !p.is_empty()) {
+        let (key, value) = param.split_once('=').unwrap_or((param, ""));
+        command
+            .endpoint
+            .query
+            .insert(key.to_string(), value.to_string());
+    }
+
+    command.endpoint.method = match method {
+        Some(method) => method.to_uppercase(),
+        None if head => "HEAD".into(),
+        None if command.endpoint.body.is_some() || command.endpoint.form.is_some() => "POST".into(),
+        None => "GET".into(),
+    };
+
+    Ok(command)
+}
+
+/// Converts seconds, which cURL allows to be fractional, to milliseconds.
+fn millis(seconds: &str) -> Result<u64, String> {
+    seconds
+        .parse::<f64>()
+        .map(|s| (s * 1000.0) as u64)
+        .map_err(|_| format!("invalid number of seconds: {seconds}"))
+}
+
+/// Splits `input` into words as a POSIX shell would, handling quotes, escapes and line
+/// continuations.
+fn split(input: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' | '\n' | '\r' => {
+                if let Some(word) = word.take() {
+                    words.push(word);
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some('\r') => {
+                    chars.next_if_eq(&'\n');
+                }
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => {}
+            },
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated quote in cURL command".into()),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err("unterminated quote in cURL command".into()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated quote in cURL command".into()),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => word.push(escape(&mut chars)?),
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated quote in cURL command".into()),
+                    }
+                }
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if let Some(word) = word {
+        words.push(word);
+    }
+
+    Ok(words)
+}
+
+/// Reads an escape sequence of `$'...'` quoting, after its backslash.
+fn escape(chars: &mut Peekable<Chars>) -> Result<char, String> {
+    let c = match chars.next() {
+        Some('n') => '\n',
+        Some('t') => '\t',
+        Some('r') => '\r',
+        Some('0') => '\0',
+        Some('x') => hex(chars, 2)?,
+        Some('u') => hex(chars, 4)?,
+        Some('U') => hex(chars, 8)?,
+        Some(c) => c,
+        None => return Err("unterminated quote in cURL command".into()),
+    };
+
+    Ok(c)
+}
+
+/// Reads a character code of up to `len` hexadecimal digits.
+fn hex(chars: &mut Peekable<Chars>, len: usize) -> Result<char, String> {
+    let mut digits = String::new();
+    while digits.len() < len {
+        match chars.next_if(char::is_ascii_hexdigit) {
+            Some(c) => digits.push(c),
+            None => break,
+        }
+    }
+
+    u32::from_str_radix(&digits, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or_else(|| format!("invalid escape sequence: {digits}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curl_split() {
+        let words = split("curl 'a b' \"c \\\"d\\\"\" $'e\\'f\\n' g\\ h \\\n  i").unwrap();
+        assert_eq!(words, ["curl", "a b", "c \"d\"", "e'f\n", "g h", "i"]);
+
+        assert!(split("curl 'a").is_err());
+    }
+
+    #[test]
+    fn curl_parse() {
+        let command = parse(
+            r#"curl -sSL -XPUT --url 'https://example.com/users/1' \
+              -H 'Content-Type: application/json' -H 'X-Empty;' \
+              -u rex:secret -b 'session=abc' --max-time 1.5 \
+              --data-binary '{"name":"Rex"}' --proxy localhost:8080 --foo"#,
+        )
+        .unwrap();
+
+        let endpoint = command.endpoint;
+        assert_eq!(endpoint.method, "PUT");
+        assert_eq!(endpoint.url, "https://example.com/users/1");
+        assert_eq!(
+            endpoint.headers.get("Content-Type").unwrap(),
+            "application/json"
+        );
+        assert_eq!(endpoint.headers.get("X-Empty").unwrap(), "");
+        assert_eq!(
+            endpoint.headers.get("Authorization").unwrap(),
+            "Basic cmV4OnNlY3JldA=="
+        );
+        assert_eq!(endpoint.headers.get("Cookie").unwrap(), "session=abc");
+        assert_eq!(endpoint.timeout.total, Some(1500));
+        assert_eq!(
+            endpoint.body.as_deref(),
+            Some("{\"name\":\"Rex\"}".as_bytes())
+        );
+        assert_eq!(command.warnings, ["--foo: option is not supported"]);
+    }
+
+    #[test]
+    fn curl_parse_get_data() {
+        let command = parse("curl -G https://example.com/search -d q=rex -d page=2").unwrap();
+
+        let endpoint = command.endpoint;
+        assert_eq!(endpoint.method, "GET");
+        assert_eq!(endpoint.query.get("q").unwrap(), "rex");
+        assert_eq!(endpoint.query.get("page").unwrap(), "2");
+        assert!(endpoint.body.is_none());
+
+        let command = parse("curl https://example.com --json '{}'").unwrap();
+        assert_eq!(command.endpoint.method, "POST");
+        assert_eq!(
+            command.endpoint.headers.get("Accept").unwrap(),
+            "application/json"
+        );
+    }
+
+    #[test]
+    fn curl_parse_json_parts() {
+        let command =
+            parse(r#"curl https://example.com --json '{"name":' --json '"Rex"}'"#).unwrap();
+        assert_eq!(
+            command.endpoint.body.as_deref(),
+            Some(&br#"{"name":"Rex"}"#[..])
+        );
+
+        let command = parse("curl https://example.com -d a=1 --json '{}' -d b=2").unwrap();
+        assert_eq!(command.endpoint.body.as_deref(), Some(&b"a=1&b=2{}"[..]));
+        assert_eq!(
+            command.endpoint.headers.get("Content-Type").unwrap(),
+            "application/json"
+        );
+    }
+
+    #[test]
+    fn curl_parse_data_urlencode() {
+        let command = parse(
+            "curl https://example.com/search --data-urlencode 'q=a&b=c' \
+              --data-urlencode '=x y' --data-urlencode 'café' --data-urlencode 'notes@notes.txt'",
+        )
+        .unwrap();
+
+        assert_eq!(
+            command.endpoint.body.as_deref(),
+            Some("q=a%26b%3Dc&x+y&caf%C3%A9".as_bytes())
+        );
+        assert_eq!(
+            command.warnings,
+            ["--data-urlencode notes@notes.txt: bodies read from files are not supported"]
+        );
+
+        let command = parse("curl -G https://example.com --data-urlencode 'q=a&b=c'").unwrap();
+        assert_eq!(command.endpoint.query.get("q").unwrap(), "a%26b%3Dc");
+    }
+
+    #[test]
+    fn curl_parse_form() {
+        let command = parse(
+            "curl https://example.com/pets -H 'Content-Type: multipart/form-data' \
+              -F name=Rex -F 'photo=@rex.png;type=image/png' --form-string 'note=@home'",
+        )
+        .unwrap();
+
+        let endpoint = command.endpoint;
+        assert_eq!(endpoint.method, "POST");
+        assert!(endpoint.headers.is_empty());
+
+        let form = endpoint.form.unwrap();
+        assert!(form.is_multipart());
+
+        let fields = form.fields();
+        assert_eq!(fields[0].value, "Rex");
+        assert_eq!(fields[1].file.as_deref(), Some("rex.png"));
+        assert_eq!(fields[1].content_type.as_deref(), Some("image/png"));
+        assert_eq!(fields[2].value, "@home");
+    }
+}
@@ -0,0 +1,284 @@
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use hyper::Uri;
+use openssl::sha::Sha256;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+use crate::QuartzResult;
+
+/// Where a download is resumed from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Offset {
+    /// Size of the existing output file, given as "-".
+    Auto,
+    At(u64),
+}
+
+impl FromStr for Offset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "-" => Ok(Self::Auto),
+            s => s
+                .parse()
+                .map(Self::At)
+                .map_err(|_| format!("invalid offset: {s}")),
+        }
+    }
+}
+
+impl Offset {
+    /// Number of bytes already downloaded to `path`.
+    pub fn resolve(&self, path: &Path) -> u64 {
+        match self {
+            Self::At(offset) => *offset,
+            Self::Auto => std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        }
+    }
+}
+
+/// Last segment of the `url` path, used as file name by `--remote-name`.
+pub fn remote_name(url: &Uri) -> Option<&str> {
+    url.path()
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+}
+
+/// Response body being written to a file, hashed as it is written.
+pub struct Download {
+    path: PathBuf,
+    file: File,
+    hasher: Sha256,
+
+    /// Bytes in the file so far.
+    size: u64,
+
+    progress: Option<Progress>,
+}
+
+impl Download {
+    /// Creates the file at `path`, or keeps its first `resume` bytes to append the body to them.
+    /// When `total` size is known, a progress bar can show how much of it was written.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file could not be written or holds fewer
+    /// bytes than it should be resumed from.
+    pub async fn open(
+        path: &Path,
+        resume: Option<u64>,
+        total: Option<u64>,
+        progress: bool,
+    ) -> QuartzResult<Self> {
+        let context = |err: std::io::Error| format!("{}: {err}", path.display());
+
+        let mut hasher = Sha256::new();
+        let mut size = 0;
+
+        let file = match resume {
+            Some(offset) => {
+                let mut file = tokio::fs::OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .create(true)
+                    .truncate(false)
+                    .open(path)
+                    .await
+                    .map_err(context)?;
+
+                let len = file.metadata().await.map_err(context)?.len();
+                if len < offset {
+                    return Err(format!(
+                        "cannot resume at {offset} bytes, {} holds only {len}",
+                        path.display()
+                    )
+                    .into());
+                }
+                file.set_len(offset).await.map_err(context)?;
+
+                // Kept bytes are part of the hash of the whole file
+                let mut buf = vec![0; 64 * 1024];
+                loop {
+                    let n = file.read(&mut buf).await.map_err(context)?;
+                    if n == 0 {
+                        break;
+                    }
+
+                    hasher.update(&buf[..n]);
+                    size += n as u64;
+                }
+                file.seek(SeekFrom::End(0)).await.map_err(context)?;
+
+                file
+            }
+            None => File::create(path).await.map_err(context)?,
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            hasher,
+            size,
+            progress: progress.then(|| Progress::new(total)),
+        })
+    }
+
+    pub async fn write(&mut self, chunk: &[u8]) -> QuartzResult {
+        self.file
+            .write_all(chunk)
+            .await
+            .map_err(|err| format!("{}: {err}", self.path.display()))?;
+
+        self.hasher.update(chunk);
+        self.size += chunk.len() as u64;
+
+        if let Some(progress) = self.progress.as_mut() {
+            progress.draw(self.size, false);
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the file and describes it, to be recorded in history in place of the body.
+    pub async fn finish(mut self) -> QuartzResult<String> {
+        self.file
+            .flush()
+            .await
+            .map_err(|err| format!("{}: {err}", self.path.display()))?;
+
+        if let Some(progress) = self.progress.as_mut() {
+            progress.draw(self.size, true);
+        }
+
+        let hash: String = self
+            .hasher
+            .finish()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+
+        Ok(format!(
+            "* body saved to {} ({} bytes, sha256 {hash})",
+            self.path.display(),
+            self.size
+        ))
+    }
+}
+
+/// Progress bar drawn on standard error.
+struct Progress {
+    term: console::Term,
+    total: Option<u64>,
+    drawn: Option<Instant>,
+}
+
+impl Progress {
+    const WIDTH: usize = 30;
+
+    fn new(total: Option<u64>) -> Self {
+        Self {
+            term: console::Term::stderr(),
+            total,
+            drawn: None,
+        }
+    }
+
+    /// Redraws the bar with `size` bytes written, at most every tenth of a second unless
+    /// `force`d.
+    fn draw(&mut self, size: u64, force: bool) {
+        if !force
+            && self
+                .drawn
+                .is_some_and(|at| at.elapsed() < Duration::from_millis(100))
+        {
+            return;
+        }
+
+        let line = match self.total {
+            Some(total) if total > 0 => {
+                let ratio = (size as f64 / total as f64).min(1.0);
+                let filled = (ratio * Self::WIDTH as f64) as usize;
+
+                format!(
+                    "[{}{}] {:>3}% {} / {}",
+                    "#".repeat(filled),
+                    " ".repeat(Self::WIDTH - filled),
+                    (ratio * 100.0) as u8,
+                    human_size(size),
+                    human_size(total)
+                )
+            }
+            _ => format!("{} received", human_size(size)),
+        };
+
+        let _ = self.term.clear_line();
+        let _ = self.term.write_str(&line);
+        self.drawn = Some(Instant::now());
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        // Leaves the last drawn bar in its own line
+        if self.drawn.is_some() {
+            let _ = self.term.write_line("");
+        }
+    }
+}
+
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut size = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if size < 1024.0 {
+            break;
+        }
+
+        size /= 1024.0;
+        unit = next;
+    }
+
+    format!("{size:.1} {unit}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn download_offset() {
+        assert_eq!("-".parse::<Offset>(), Ok(Offset::Auto));
+        assert_eq!("1024".parse::<Offset>(), Ok(Offset::At(1024)));
+        assert!("-1".parse::<Offset>().is_err());
+
+        assert_eq!(Offset::Auto.resolve(Path::new("/nonexistent/file")), 0);
+    }
+
+    #[test]
+    fn download_remote_name() {
+        let url = Uri::from_static("https://example.com/reports/2024.csv?token=1");
+        assert_eq!(remote_name(&url), Some("2024.csv"));
+
+        let url = Uri::from_static("https://example.com/reports/");
+        assert_eq!(remote_name(&url), None);
+    }
+
+    #[test]
+    fn download_human_size() {
+        assert_eq!(human_size(512), "512 B");
+        assert_eq!(human_size(1536), "1.5 KiB");
+        assert_eq!(human_size(10 * 1024 * 1024), "10.0 MiB");
+    }
+}
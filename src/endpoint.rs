@@ -8,9 +8,13 @@ use std::io::Write;
 use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
 
+use crate::assertion::Assertion;
+use crate::capture::Capture;
 use crate::env::{Env, Variables};
+use crate::form::{Field, Form};
 use crate::state::StateField;
 use crate::tree::Tree;
+use crate::websocket;
 use crate::{Ctx, PairMap};
 
 #[derive(Default, Debug, Serialize, Deserialize, Clone)]
@@ -100,6 +104,70 @@ impl PairMap<'_> for Headers {
     }
 }
 
+/// Time limits applied when sending a request, in milliseconds.
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Timeout {
+    /// Maximum time to establish a connection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect: Option<u64>,
+
+    /// Maximum time to wait for the response head or between body chunks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read: Option<u64>,
+
+    /// Maximum time for the whole exchange, including retries and redirects.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+}
+
+impl Timeout {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Kind of connection opened by `send`.
+#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    #[default]
+    Http,
+    /// Upgraded to a WebSocket connection to exchange messages.
+    Websocket,
+}
+
+impl Kind {
+    pub fn is_http(&self) -> bool {
+        self == &Self::Http
+    }
+}
+
+/// Policy to send a request again when an attempt fails.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Retry {
+    /// Maximum number of attempts, including the first one.
+    pub attempts: u32,
+
+    /// Milliseconds to wait before the first retry. It doubles after each retry.
+    #[serde(default)]
+    pub backoff: u64,
+
+    /// Response status codes that also trigger a retry. Connection errors and read timeouts
+    /// always do.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on: Vec<u16>,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self {
+            attempts: 1,
+            backoff: 0,
+            on: Vec::new(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct EndpointHandle {
     /// List of ordered parent names
@@ -113,12 +181,34 @@ pub struct Endpoint {
     /// HTTP Request method
     pub method: String,
 
+    /// WebSocket endpoints can also be told apart by a ws:// or wss:// URL.
+    #[serde(default, skip_serializing_if = "Kind::is_http")]
+    pub kind: Kind,
+
     /// Query params.
     pub query: Query,
 
     /// List of (key, value) pairs.
     pub headers: Headers,
 
+    /// Form body, sent instead of the body file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub form: Option<Form>,
+
+    #[serde(default, skip_serializing_if = "Timeout::is_empty")]
+    pub timeout: Timeout,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<Retry>,
+
+    /// Expectations checked by `quartz test`.
+    #[serde(default, rename = "assert", skip_serializing_if = "Vec::is_empty")]
+    pub assertions: Vec<Assertion>,
+
+    /// Response values stored in environment variables after a successful request.
+    #[serde(default, rename = "capture", skip_serializing_if = "Vec::is_empty")]
+    pub captures: Vec<Capture>,
+
     /// Variable values applied from a [`Env`]
     #[serde(skip_serializing, skip_deserializing)]
     pub variables: Variables,
@@ -126,11 +216,12 @@ pub struct Endpoint {
     #[serde(skip_serializing, skip_deserializing)]
     pub path: PathBuf,
 
+    /// Raw request body, which may not be text.
     #[serde(skip_serializing, skip_deserializing)]
-    pub body: Option<String>,
+    pub body: Option<Vec<u8>>,
 }
 
-#[derive(Debug, clap::Args)]
+#[derive(Debug, Clone, clap::Args)]
 #[group(multiple = false)]
 pub struct ContentTypeGroup {
     /// Use JSON data in request body with the appropriate content-type header
@@ -140,9 +231,19 @@ pub struct ContentTypeGroup {
     /// Use raw data in request body
     #[arg(long = "data", short = 'd', value_name = "DATA")]
     pub raw: Option<String>,
+
+    /// Add a multipart/form-data field as NAME=VALUE, or a file as NAME=@PATH with optional
+    /// ";type=" and ";filename=". This argument can be passed multiple times
+    #[arg(long, short = 'F', value_name = "FIELD")]
+    pub form: Vec<String>,
+
+    /// Add an application/x-www-form-urlencoded field as NAME=VALUE. This argument can be
+    /// passed multiple times
+    #[arg(long, value_name = "FIELD")]
+    pub urlencoded: Vec<String>,
 }
 
-#[derive(Default, Debug, clap::Args)]
+#[derive(Default, Debug, Clone, clap::Args)]
 pub struct EndpointPatch {
     /// Patch request URL
     #[arg(long)]
@@ -164,6 +265,27 @@ pub struct EndpointPatch {
     pub data: Option<ContentTypeGroup>,
 }
 
+impl ContentTypeGroup {
+    /// Form built from `--form` or `--urlencoded` fields, if any.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a field is malformed.
+    pub fn form(&self) -> Result<Option<Form>, String> {
+        if !self.urlencoded.is_empty() {
+            let fields = self.urlencoded.iter().map(|input| Field::text(input));
+            return Ok(Some(Form::Urlencoded(fields.collect::<Result<_, _>>()?)));
+        }
+
+        if !self.form.is_empty() {
+            let fields = self.form.iter().map(|input| Field::parse(input));
+            return Ok(Some(Form::Multipart(fields.collect::<Result<_, _>>()?)));
+        }
+
+        Ok(None)
+    }
+}
+
 impl EndpointPatch {
     pub fn has_changes(&self) -> bool {
         self.url.is_some()
@@ -216,6 +338,14 @@ impl EndpointHandle {
         self.path.last().unwrap_or(&String::new()).clone()
     }
 
+    /// Whether every segment of this handle names its own directory, unlike "." or "..", which
+    /// would point outside of it.
+    pub fn is_valid(&self) -> bool {
+        self.path
+            .iter()
+            .all(|name| !matches!(Endpoint::name_to_dir(name).as_str(), "" | "." | ".."))
+    }
+
     pub fn dir(&self, ctx: &Ctx) -> PathBuf {
         let mut result = ctx.path().join("endpoints");
 
@@ -294,6 +424,7 @@ impl EndpointHandle {
             }
         }
 
+        list.sort_by_key(|handle| handle.handle());
         list
     }
 
@@ -337,6 +468,11 @@ impl Endpoint {
         }
     }
 
+    /// Whether `send` opens a WebSocket connection instead of making a request.
+    pub fn is_websocket(&self) -> bool {
+        self.kind == Kind::Websocket || websocket::is_websocket_url(&self.url)
+    }
+
     pub fn name_to_dir(name: &str) -> String {
         name.trim().replace(['/', '\\'], "-")
     }
@@ -373,15 +509,27 @@ impl Endpoint {
         }
 
         if let Some(data) = &src.data {
-            if let Some(maybe_json) = &data.json {
+            self.form = data.form().unwrap_or_else(|err| panic!("{err}"));
+
+            if let Some(form) = &self.form {
+                // Multipart content-type is only known when sending, as it holds the boundary
+                self.headers
+                    .retain(|key, _| !key.eq_ignore_ascii_case("content-type"));
+                if !form.is_multipart() {
+                    self.headers
+                        .insert("Content-type".into(), form.content_type(""));
+                }
+
+                self.body = None;
+            } else if let Some(maybe_json) = &data.json {
                 self.headers
                     .insert("Content-type".into(), "application/json".into());
 
                 if let Some(json) = maybe_json {
-                    self.body = Some(json.to_owned());
+                    self.body = Some(json.as_bytes().to_vec());
                 }
             } else if let Some(raw) = &data.raw {
-                self.body = Some(raw.to_owned());
+                self.body = Some(raw.as_bytes().to_vec());
             }
         }
     }
@@ -390,27 +538,32 @@ impl Endpoint {
         toml::to_string(&self)
     }
 
-    pub fn load_body(&mut self) -> Option<&String> {
-        match std::fs::read_to_string(self.path.join("body")) {
-            Ok(mut content) => {
+    pub fn load_body(&mut self) -> Option<&Vec<u8>> {
+        let content = std::fs::read(self.path.join("body")).ok()?;
+
+        // Variables only apply to text, so binary bodies are sent as they are
+        let content = match String::from_utf8(content) {
+            Ok(mut text) => {
                 for (key, value) in self.variables.iter() {
                     let key_match = format!("{{{{{}}}}}", key);
 
-                    content = content.replace(&key_match, value);
+                    text = text.replace(&key_match, value);
                 }
 
-                if content.trim().is_empty() {
+                if text.trim().is_empty() {
                     return None;
                 }
 
-                self.body = Some(content.to_owned());
-                self.body.as_ref()
+                text.into_bytes()
             }
-            Err(_) => None,
-        }
+            Err(err) => err.into_bytes(),
+        };
+
+        self.body = Some(content);
+        self.body.as_ref()
     }
 
-    pub fn body(&mut self) -> Option<&String> {
+    pub fn body(&mut self) -> Option<&Vec<u8>> {
         if self.body.is_some() {
             self.body.as_ref()
         } else {
@@ -422,14 +575,18 @@ impl Endpoint {
         self.path = handle.dir(ctx).to_path_buf();
     }
 
+    /// Closest parent handle with an endpoint, skipping empty handles.
     pub fn parent(&self) -> Option<Self> {
         let mut path = self.path.clone();
 
-        if path.pop() {
-            Self::from_dir(&path).ok()
-        } else {
-            None
+        // Every handle directory has a spec file, unlike the endpoints directory itself
+        while path.pop() && path.join("spec").exists() {
+            if let Ok(endpoint) = Self::from_dir(&path) {
+                return Some(endpoint);
+            }
         }
+
+        None
     }
 
     /// Inherits parent URL when it starts with "**".
@@ -483,6 +640,10 @@ impl Endpoint {
                     (h_key.clone(), h_value.clone())
                 })
                 .collect();
+
+            if let Some(form) = &mut self.form {
+                form.replace(&key_match, value);
+            }
         }
 
         self.variables = env.variables.clone();
@@ -580,7 +741,13 @@ impl Default for Endpoint {
         Self {
             method: String::from("GET"),
             url: Default::default(),
+            kind: Default::default(),
             headers: Default::default(),
+            form: Default::default(),
+            timeout: Default::default(),
+            retry: Default::default(),
+            assertions: Default::default(),
+            captures: Default::default(),
             variables: Default::default(),
             query: Default::default(),
             path: Default::default(),
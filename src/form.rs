@@ -0,0 +1,426 @@
+use std::path::Path;
+
+use hyper::body::Bytes;
+use hyper::Body;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncReadExt;
+
+use crate::{random_bytes, QuartzResult};
+
+/// Form body of an endpoint, sent as `multipart/form-data` or
+/// `application/x-www-form-urlencoded`.
+///
+/// In the endpoint file, each field is an entry of a `[[form.multipart]]` or
+/// `[[form.urlencoded]]` list:
+///
+/// ```toml
+/// [[form.multipart]]
+/// name = "avatar"
+/// file = "avatar.png"
+/// type = "image/png"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Form {
+    Multipart(Vec<Field>),
+    Urlencoded(Vec<Field>),
+}
+
+/// Field of a [`Form`], holding either a text value or a file path.
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub value: String,
+
+    /// File whose content is sent as the value. It is read when sending, relative to the
+    /// project directory, which holds `.quartz`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+
+    /// Content type of a multipart field. Files default to one guessed from their extension.
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+
+    /// File name of a multipart field, which defaults to the name of its file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+}
+
+/// Piece of a multipart body, either in memory or streamed from a file.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Part {
+    Data(String),
+    File(String),
+}
+
+impl Field {
+    /// Parses a multipart field in the cURL `--form` syntax: "name=value" for text, or
+    /// "name=@path" for a file, optionally followed by ";type=..." and ";filename=...".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::form::Field;
+    ///
+    /// let field = Field::parse("avatar=@me.png;type=image/png").unwrap();
+    /// assert_eq!(field.name, "avatar");
+    /// assert_eq!(field.file.as_deref(), Some("me.png"));
+    /// assert_eq!(field.content_type.as_deref(), Some("image/png"));
+    ///
+    /// // Text values are kept as they are
+    /// let field = Field::parse("note=a;b").unwrap();
+    /// assert_eq!(field.value, "a;b");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` has no "=" or an unknown attribute.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let (name, value) = split(input)?;
+
+        let Some(file) = value.strip_prefix('@') else {
+            return Ok(Self {
+                name,
+                value,
+                ..Default::default()
+            });
+        };
+
+        let mut attributes = file.split(';');
+        let mut field = Self {
+            name,
+            file: attributes.next().map(str::to_string),
+            ..Default::default()
+        };
+
+        for attribute in attributes {
+            match attribute.trim().split_once('=') {
+                Some(("type", value)) => field.content_type = Some(value.to_string()),
+                Some(("filename", value)) => field.filename = Some(value.to_string()),
+                _ => return Err(format!("unknown form field attribute: {attribute}")),
+            }
+        }
+
+        Ok(field)
+    }
+
+    /// Parses a text field, as "name=value", keeping the value as it is.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` has no "=".
+    pub fn text(input: &str) -> Result<Self, String> {
+        let (name, value) = split(input)?;
+
+        Ok(Self {
+            name,
+            value,
+            ..Default::default()
+        })
+    }
+
+    /// File name written in the multipart body, if this is a file field.
+    pub fn filename(&self) -> Option<String> {
+        let file = self.file.as_ref()?;
+
+        self.filename.clone().or_else(|| {
+            Path::new(file)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+    }
+
+    /// Content type written in the multipart body, if any.
+    pub fn content_type(&self) -> Option<String> {
+        self.content_type
+            .clone()
+            .or_else(|| self.file.as_deref().map(|file| mime(file).to_string()))
+    }
+
+    fn replace(&mut self, from: &str, to: &str) {
+        self.name = self.name.replace(from, to);
+        self.value = self.value.replace(from, to);
+        for value in [&mut self.file, &mut self.content_type, &mut self.filename]
+            .into_iter()
+            .flatten()
+        {
+            *value = value.replace(from, to);
+        }
+    }
+}
+
+impl Form {
+    pub fn fields(&self) -> &[Field] {
+        match self {
+            Form::Multipart(fields) | Form::Urlencoded(fields) => fields,
+        }
+    }
+
+    pub fn is_multipart(&self) -> bool {
+        matches!(self, Form::Multipart(_))
+    }
+
+    /// Replaces `from` with `to` in every field.
+    pub fn replace(&mut self, from: &str, to: &str) {
+        let (Form::Multipart(fields) | Form::Urlencoded(fields)) = self;
+
+        for field in fields {
+            field.replace(from, to);
+        }
+    }
+
+    /// Random multipart boundary, unlikely to appear in field values.
+    pub fn boundary() -> String {
+        let id: String = random_bytes::<8>()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+
+        format!("------------------------quartz{id}")
+    }
+
+    /// Content-Type header value for this form.
+    pub fn content_type(&self, boundary: &str) -> String {
+        match self {
+            Form::Multipart(_) => format!("multipart/form-data; boundary={boundary}"),
+            Form::Urlencoded(_) => String::from("application/x-www-form-urlencoded"),
+        }
+    }
+
+    /// URL-encoded body, as "name=value&...". File fields are sent as text.
+    pub fn urlencoded(&self) -> String {
+        self.fields()
+            .iter()
+            .map(|field| format!("{}={}", encode(&field.name), encode(&field.value)))
+            .collect::<Vec<String>>()
+            .join("&")
+    }
+
+    /// Multipart body pieces in order, with files left to be read when sending.
+    pub fn parts(&self, boundary: &str) -> Vec<Part> {
+        let mut parts = Vec::new();
+        let mut data = String::new();
+
+        for field in self.fields() {
+            data.push_str(&format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"{}\"",
+                escape(&field.name)
+            ));
+            if let Some(filename) = field.filename() {
+                data.push_str(&format!("; filename=\"{}\"", escape(&filename)));
+            }
+            data.push_str("\r\n");
+            if let Some(content_type) = field.content_type() {
+                data.push_str(&format!("Content-Type: {content_type}\r\n"));
+            }
+            data.push_str("\r\n");
+
+            match &field.file {
+                Some(file) => {
+                    parts.push(Part::Data(std::mem::take(&mut data)));
+                    parts.push(Part::File(file.clone()));
+                }
+                None => data.push_str(&field.value),
+            }
+
+            data.push_str("\r\n");
+        }
+
+        data.push_str(&format!("--{boundary}--\r\n"));
+        parts.push(Part::Data(data));
+
+        parts
+    }
+
+    /// Multipart body with files written as "< path" lines, like in `.http` files.
+    pub fn preview(&self, boundary: &str) -> String {
+        self.parts(boundary)
+            .into_iter()
+            .map(|part| match part {
+                Part::Data(data) => data,
+                Part::File(file) => format!("< {file}"),
+            })
+            .collect()
+    }
+
+    /// Multipart body streaming files from disk, along with its length. Relative file paths
+    /// are read from `dir`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a file does not exist.
+    pub fn body(&self, boundary: &str, dir: &Path) -> QuartzResult<(Body, u64)> {
+        let mut parts = self.parts(boundary);
+
+        let mut length = 0;
+        for part in &mut parts {
+            length += match part {
+                Part::Data(data) => data.len() as u64,
+                Part::File(file) => {
+                    let path = dir.join(&*file);
+                    let len = std::fs::metadata(&path)
+                        .map_err(|err| format!("failed to read {file}: {err}"))?
+                        .len();
+
+                    *file = path.to_string_lossy().into_owned();
+                    len
+                }
+            };
+        }
+
+        let (mut sender, body) = Body::channel();
+        tokio::spawn(async move {
+            for part in parts {
+                let file = match part {
+                    Part::Data(data) => {
+                        if sender.send_data(Bytes::from(data)).await.is_err() {
+                            return;
+                        }
+                        continue;
+                    }
+                    Part::File(file) => file,
+                };
+
+                let Ok(mut file) = tokio::fs::File::open(file).await else {
+                    sender.abort();
+                    return;
+                };
+
+                let mut buf = vec![0; 64 * 1024];
+                loop {
+                    match file.read(&mut buf).await {
+                        Ok(0) => break,
+                        Ok(n) => {
+                            if sender
+                                .send_data(Bytes::copy_from_slice(&buf[..n]))
+                                .await
+                                .is_err()
+                            {
+                                return;
+                            }
+                        }
+                        Err(_) => {
+                            sender.abort();
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok((body, length))
+    }
+}
+
+fn split(input: &str) -> Result<(String, String), String> {
+    input
+        .split_once('=')
+        .map(|(name, value)| (name.trim().to_string(), value.to_string()))
+        .ok_or_else(|| format!("malformed form field: {input}. Expected NAME=VALUE"))
+}
+
+/// Escapes a quoted multipart header parameter, as browsers do.
+pub(crate) fn escape(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Percent-encodes a form value, with spaces as "+".
+pub(crate) fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'*' => {
+                (b as char).to_string()
+            }
+            b' ' => String::from("+"),
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Content type guessed from the extension of `path`.
+pub fn mime(path: &str) -> &'static str {
+    let extension = Path::new(path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "txt" => "text/plain",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "js" => "text/javascript",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn form_parse() {
+        let field = Field::parse("doc=@files/a b.txt;filename=report.txt").unwrap();
+        assert_eq!(field.file.as_deref(), Some("files/a b.txt"));
+        assert_eq!(field.filename().as_deref(), Some("report.txt"));
+        assert_eq!(field.content_type().as_deref(), Some("text/plain"));
+
+        let field = Field::text("email=@me").unwrap();
+        assert_eq!(field.value, "@me");
+        assert!(field.file.is_none());
+
+        assert!(Field::parse("novalue").is_err());
+        assert!(Field::parse("f=@a.txt;size=1").is_err());
+    }
+
+    #[test]
+    fn form_urlencoded() {
+        let form = Form::Urlencoded(vec![
+            Field::text("name=Rex Dog").unwrap(),
+            Field::text("tags=a&b=c").unwrap(),
+        ]);
+
+        assert_eq!(form.urlencoded(), "name=Rex+Dog&tags=a%26b%3Dc");
+    }
+
+    #[test]
+    fn form_multipart() {
+        let form = Form::Multipart(vec![
+            Field::parse("name=Rex").unwrap(),
+            Field::parse("photo=@rex.png").unwrap(),
+        ]);
+
+        assert_eq!(
+            form.parts("X"),
+            [
+                Part::Data(String::from(concat!(
+                    "--X\r\n",
+                    "Content-Disposition: form-data; name=\"name\"\r\n",
+                    "\r\n",
+                    "Rex\r\n",
+                    "--X\r\n",
+                    "Content-Disposition: form-data; name=\"photo\"; filename=\"rex.png\"\r\n",
+                    "Content-Type: image/png\r\n",
+                    "\r\n",
+                ))),
+                Part::File(String::from("rex.png")),
+                Part::Data(String::from("\r\n--X--\r\n")),
+            ]
+        );
+    }
+}
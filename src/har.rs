@@ -0,0 +1,598 @@
+use std::collections::HashSet;
+
+use chrono::{SecondsFormat, TimeZone, Utc};
+use hyper::StatusCode;
+use regex::Regex;
+use serde_json::{json, Value};
+
+use crate::{
+    collection::{slug, Collection},
+    encoding::base64_decode,
+    endpoint::{Endpoint, EndpointHandle},
+    history::{Entry, Timing},
+    QuartzResult,
+};
+
+/// HTTP Archive, version 1.2, as exported by browsers and proxies.
+pub struct Document {
+    doc: Value,
+}
+
+/// Which entries of an archive become endpoints.
+#[derive(Debug, Default)]
+pub struct Filter {
+    /// Only entries whose URL matches.
+    pub url: Option<Regex>,
+
+    /// Only entries with this method.
+    pub method: Option<String>,
+
+    /// Keep a single entry per method and URL template, where IDs in the path become variables.
+    pub dedupe: bool,
+}
+
+/// Headers set by the client or the connection, rather than by the request author.
+const SKIPPED_HEADERS: [&str; 3] = ["host", "content-length", "connection"];
+
+impl Document {
+    /// # Errors
+    ///
+    /// This function will return an error if `input` is not an HTTP Archive.
+    pub fn parse(input: &str) -> QuartzResult<Self> {
+        let doc: Value =
+            serde_json::from_str(input).map_err(|err| format!("invalid JSON: {err}"))?;
+
+        if !doc.pointer("/log/entries").is_some_and(Value::is_array) {
+            return Err("not an HTTP Archive".into());
+        }
+
+        Ok(Self { doc })
+    }
+
+    /// Maps entries selected by `filter` to endpoints under `root`, named by their host and
+    /// path. Entries exported by quartz keep their original handle.
+    pub fn collection(&self, root: &EndpointHandle, filter: &Filter) -> Collection {
+        let mut collection = Collection::default();
+        let mut taken = HashSet::new();
+        let mut templates = HashSet::new();
+
+        for entry in list(self.doc.pointer("/log/entries")) {
+            let Some(request) = entry.get("request") else {
+                continue;
+            };
+
+            let method = str(request, "method").to_uppercase();
+            let url = str(request, "url");
+
+            if filter.url.as_ref().is_some_and(|re| !re.is_match(url))
+                || filter
+                    .method
+                    .as_ref()
+                    .is_some_and(|m| !m.eq_ignore_ascii_case(&method))
+            {
+                continue;
+            }
+
+            let (base, query) = url.split_once('?').unwrap_or((url, ""));
+            let (origin, path) = split_origin(base);
+
+            let mut endpoint = Endpoint {
+                method: method.clone(),
+                url: base.to_string(),
+                ..Default::default()
+            };
+
+            let mut name_path = path.to_string();
+            if filter.dedupe {
+                let (template, variables) = template(path);
+                if !templates.insert(format!("{method} {origin}{template}")) {
+                    continue;
+                }
+
+                for (key, value) in variables {
+                    collection.variables.entry(key).or_insert(value);
+                }
+
+                endpoint.url = format!("{origin}{template}");
+                name_path = template;
+            }
+
+            for param in query.split('&').filter(|p| !p.is_empty()) {
+                let (key, value) = param.split_once('=').unwrap_or((param, ""));
+                endpoint.query.insert(key.to_string(), value.to_string());
+            }
+
+            for header in list(request.get("headers")) {
+                let key = str(header, "name");
+                if key.starts_with(':') || SKIPPED_HEADERS.contains(&key.to_lowercase().as_str()) {
+                    continue;
+                }
+
+                endpoint
+                    .headers
+                    .insert(key.to_string(), str(header, "value").to_string());
+            }
+
+            let name: Vec<String> = match entry.get("_handle").and_then(Value::as_str) {
+                Some(handle) => handle.split('/').map(String::from).collect(),
+                None => vec![slug(host(origin)), slug(&format!("{method} {name_path}"))],
+            };
+
+            let mut handle = root.clone();
+            handle.path.extend(name);
+
+            let head = handle.head();
+            let mut n = 1;
+            while !taken.insert(handle.handle()) {
+                n += 1;
+                *handle.path.last_mut().unwrap() = format!("{head}-{n}");
+            }
+
+            if let Some(data) = request.get("postData") {
+                endpoint.body =
+                    post_data(data, &handle, &mut collection.warnings).map(String::into_bytes);
+            }
+
+            collection.endpoints.push((handle, endpoint));
+        }
+
+        collection
+    }
+}
+
+/// Writes history `entries` as an HTTP Archive, with an entry for every request they sent,
+/// including redirects and retries.
+pub fn export(entries: &[Entry]) -> Value {
+    let mut list = Vec::new();
+
+    for entry in entries {
+        for (i, exchange) in exchanges(entry).into_iter().enumerate() {
+            list.push(har_entry(entry, entry.timings().get(i), exchange));
+        }
+    }
+
+    json!({
+        "log": {
+            "version": "1.2",
+            "creator": { "name": "quartz", "version": env!("CARGO_PKG_VERSION") },
+            "entries": list,
+        }
+    })
+}
+
+/// Messages of a single request and its response, as recorded in a history entry.
+#[derive(Default)]
+struct Exchange<'a> {
+    request: &'a str,
+    request_body: Option<&'a str>,
+    response: Option<&'a str>,
+    response_body: Option<&'a str>,
+    error: Option<&'a str>,
+
+    /// Whether the request body is base64 encoded, as it was not valid UTF-8.
+    request_binary: bool,
+
+    /// Whether the response body is base64 encoded, as it was not valid UTF-8.
+    response_binary: bool,
+}
+
+/// Splits the messages of `entry` into exchanges. Bodies follow the head they belong to.
+fn exchanges(entry: &Entry) -> Vec<Exchange<'_>> {
+    let mut exchanges: Vec<Exchange> = Vec::new();
+
+    for (i, message) in entry.messages().iter().enumerate() {
+        if entry.is_body(i) {
+            let Some(exchange) = exchanges.last_mut() else {
+                continue;
+            };
+
+            if exchange.response.is_some() {
+                exchange.response_body = Some(message);
+                exchange.response_binary = entry.is_binary(i);
+            } else {
+                exchange.request_body = Some(message);
+                exchange.request_binary = entry.is_binary(i);
+            }
+            continue;
+        }
+
+        // Other messages are heads and notes, which are written by quartz itself
+        if message.starts_with("> ") {
+            exchanges.push(Exchange {
+                request: message,
+                ..Default::default()
+            });
+            continue;
+        }
+
+        let Some(exchange) = exchanges.last_mut() else {
+            continue;
+        };
+
+        if message.starts_with("< ") {
+            exchange.response = Some(message);
+        } else if let Some(error) = message.strip_prefix("* ") {
+            exchange.error = Some(error);
+        }
+    }
+
+    exchanges
+}
+
+fn har_entry(entry: &Entry, timing: Option<&Timing>, exchange: Exchange) -> Value {
+    let (request_line, request_headers) = head(exchange.request, '>');
+    let mut words = request_line.split_whitespace();
+    let method = words.next().unwrap_or_default();
+    let path = words.next().unwrap_or_default();
+    let version = words.next().unwrap_or_default();
+
+    // Entries recorded by older versions only kept the request path and host
+    let url = match timing {
+        Some(timing) => timing.url.clone(),
+        None => {
+            let host = request_headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case("host"))
+                .map(|(_, value)| *value)
+                .unwrap_or_default();
+
+            format!("http://{host}{path}")
+        }
+    };
+
+    let query: Vec<Value> = url
+        .split_once('?')
+        .map(|(_, query)| query.split('&').filter(|p| !p.is_empty()).collect())
+        .unwrap_or_else(Vec::new)
+        .into_iter()
+        .map(|param| {
+            let (name, value) = param.split_once('=').unwrap_or((param, ""));
+            json!({ "name": name, "value": value })
+        })
+        .collect();
+
+    let cookies: Vec<Value> = request_headers
+        .iter()
+        .filter(|(key, _)| key.eq_ignore_ascii_case("cookie"))
+        .flat_map(|(_, value)| value.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect();
+
+    // HAR has no encoding for request bodies, so binary ones are written as lossy text
+    let request_body = exchange
+        .request_body
+        .map(|body| match exchange.request_binary {
+            true => String::from_utf8_lossy(&base64_decode(body).unwrap_or_default()).to_string(),
+            false => body.to_string(),
+        });
+
+    let mut request = json!({
+        "method": method,
+        "url": url,
+        "httpVersion": version,
+        "cookies": cookies,
+        "headers": headers(&request_headers),
+        "queryString": query,
+        "headersSize": -1,
+        "bodySize": exchange.request_body.map(|body| size(body, exchange.request_binary)).unwrap_or(0),
+    });
+
+    if let Some(body) = request_body {
+        request["postData"] = json!({
+            "mimeType": content_type(&request_headers),
+            "text": body,
+        });
+    }
+
+    let response = match exchange.response {
+        Some(message) => {
+            let (status_line, response_headers) = head(message, '<');
+            let mut words = status_line.split_whitespace();
+            let version = words.next().unwrap_or_default();
+            let status: u16 = words.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+
+            let cookies: Vec<Value> = response_headers
+                .iter()
+                .filter(|(key, _)| key.eq_ignore_ascii_case("set-cookie"))
+                .filter_map(|(_, value)| value.split(';').next()?.trim().split_once('='))
+                .map(|(name, value)| json!({ "name": name, "value": value }))
+                .collect();
+
+            let redirect = response_headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case("location"))
+                .map(|(_, value)| *value)
+                .unwrap_or_default();
+
+            let body = exchange.response_body.unwrap_or_default();
+            let mut content = json!({
+                "size": size(body, exchange.response_binary),
+                "mimeType": content_type(&response_headers),
+            });
+            if exchange.response_body.is_some() {
+                content["text"] = json!(body);
+            }
+            if exchange.response_binary {
+                content["encoding"] = json!("base64");
+            }
+
+            json!({
+                "status": status,
+                "statusText": StatusCode::from_u16(status)
+                    .ok()
+                    .and_then(|s| s.canonical_reason())
+                    .unwrap_or_default(),
+                "httpVersion": version,
+                "cookies": cookies,
+                "headers": headers(&response_headers),
+                "content": content,
+                "redirectURL": redirect,
+                "headersSize": -1,
+                "bodySize": size(body, exchange.response_binary),
+            })
+        }
+        // No response was received, such as on connection errors
+        None => json!({
+            "status": 0,
+            "statusText": "",
+            "httpVersion": "",
+            "cookies": [],
+            "headers": [],
+            "content": { "size": 0, "mimeType": "" },
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": -1,
+        }),
+    };
+
+    let started = timing.map(|t| t.started).unwrap_or(entry.timestemp());
+    let wait = timing.map(|t| t.wait).unwrap_or(0.0);
+    let receive = timing.map(|t| t.receive).unwrap_or(0.0);
+
+    let mut value = json!({
+        "startedDateTime": Utc
+            .timestamp_micros(started)
+            .single()
+            .unwrap_or_default()
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+        "time": wait + receive,
+        "request": request,
+        "response": response,
+        "cache": {},
+        "timings": { "send": 0, "wait": wait, "receive": receive },
+        "_handle": entry.handle(),
+    });
+
+    if let Some(error) = exchange.error {
+        value["comment"] = json!(error);
+    }
+
+    value
+}
+
+/// Length in bytes of a body message, which is base64 encoded when `binary`.
+fn size(body: &str, binary: bool) -> usize {
+    match binary {
+        true => base64_decode(body).map(|bytes| bytes.len()).unwrap_or(0),
+        false => body.len(),
+    }
+}
+
+/// First line and headers of a message head, whose lines start with `marker`.
+fn head(message: &str, marker: char) -> (&str, Vec<(&str, &str)>) {
+    let mut lines = message.lines().map(|line| {
+        line.strip_prefix(marker)
+            .unwrap_or(line)
+            .trim_start_matches(' ')
+    });
+
+    let first = lines.next().unwrap_or_default();
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect();
+
+    (first, headers)
+}
+
+fn headers(headers: &[(&str, &str)]) -> Vec<Value> {
+    headers
+        .iter()
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect()
+}
+
+fn content_type<'a>(headers: &[(&str, &'a str)]) -> &'a str {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-type"))
+        .map(|(_, value)| *value)
+        .unwrap_or_default()
+}
+
+/// Body of a request's `postData`, which holds either its text or form params.
+fn post_data(data: &Value, handle: &EndpointHandle, warnings: &mut Vec<String>) -> Option<String> {
+    if let Some(text) = data.get("text").and_then(Value::as_str) {
+        return Some(text.to_string()).filter(|text| !text.is_empty());
+    }
+
+    let params = list(data.get("params"));
+    if params.is_empty() {
+        return None;
+    }
+
+    if str(data, "mimeType").starts_with("multipart/") {
+        warnings.push(format!(
+            "{}: multipart bodies are not supported",
+            handle.handle()
+        ));
+        return None;
+    }
+
+    let pairs: Vec<String> = params
+        .iter()
+        .map(|param| format!("{}={}", str(param, "name"), str(param, "value")))
+        .collect();
+
+    Some(pairs.join("&"))
+}
+
+/// Replaces path segments that look like IDs, such as numbers and UUIDs, with variables
+/// named after the segment before them.
+fn template(path: &str) -> (String, Vec<(String, String)>) {
+    let id = Regex::new(r"^(\d+|[0-9a-fA-F]{8}-[0-9a-fA-F-]{27}|[0-9a-fA-F]{16,})$").unwrap();
+
+    let mut variables: Vec<(String, String)> = Vec::new();
+    let mut previous = "";
+    let mut segments = Vec::new();
+
+    for segment in path.split('/') {
+        if !id.is_match(segment) {
+            segments.push(segment.to_string());
+            previous = segment;
+            continue;
+        }
+
+        let mut name = match slug(previous).replace('-', "_") {
+            prefix if prefix.is_empty() => String::from("id"),
+            prefix => format!("{}_id", prefix.strip_suffix('s').unwrap_or(&prefix)),
+        };
+
+        if variables.iter().any(|(key, _)| *key == name) {
+            name = format!("{name}_{}", variables.len() + 1);
+        }
+
+        segments.push(format!("{{{{{name}}}}}"));
+        variables.push((name, segment.to_string()));
+    }
+
+    (segments.join("/"), variables)
+}
+
+/// Splits a URL without query into its origin and path.
+fn split_origin(url: &str) -> (&str, &str) {
+    let start = url.find("://").map(|i| i + 3).unwrap_or(0);
+
+    match url[start..].find('/') {
+        Some(i) => url.split_at(start + i),
+        None => (url, ""),
+    }
+}
+
+fn host(origin: &str) -> &str {
+    origin
+        .split_once("://")
+        .map(|(_, host)| host)
+        .unwrap_or(origin)
+}
+
+fn str<'a>(value: &'a Value, key: &str) -> &'a str {
+    value.get(key).and_then(Value::as_str).unwrap_or_default()
+}
+
+fn list(value: Option<&Value>) -> Vec<&Value> {
+    value
+        .and_then(Value::as_array)
+        .map(|list| list.iter().collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARCHIVE: &str = r#"{
+      "log": {
+        "version": "1.2",
+        "entries": [
+          {
+            "request": {
+              "method": "GET",
+              "url": "https://api.example.com/users/42?fields=name",
+              "headers": [
+                { "name": ":authority", "value": "api.example.com" },
+                { "name": "Accept", "value": "application/json" }
+              ]
+            }
+          },
+          {
+            "request": { "method": "GET", "url": "https://api.example.com/users/7" }
+          },
+          {
+            "request": {
+              "method": "POST",
+              "url": "https://api.example.com/login",
+              "postData": {
+                "mimeType": "application/x-www-form-urlencoded",
+                "params": [{ "name": "user", "value": "rex" }]
+              }
+            }
+          },
+          {
+            "request": { "method": "GET", "url": "https://cdn.example.com/app.js" }
+          }
+        ]
+      }
+    }"#;
+
+    #[test]
+    fn har_collection() {
+        let document = Document::parse(ARCHIVE).unwrap();
+        let collection = document.collection(&EndpointHandle::from("har"), &Filter::default());
+
+        let handles: Vec<String> = collection
+            .endpoints
+            .iter()
+            .map(|(handle, _)| handle.handle())
+            .collect();
+        assert_eq!(
+            handles,
+            [
+                "har/api-example-com/get-users-42",
+                "har/api-example-com/get-users-7",
+                "har/api-example-com/post-login",
+                "har/cdn-example-com/get-app-js",
+            ]
+        );
+
+        let (_, user) = &collection.endpoints[0];
+        assert_eq!(user.url, "https://api.example.com/users/42");
+        assert_eq!(user.query.get("fields").unwrap(), "name");
+        assert!(!user.headers.contains_key(":authority"));
+
+        let (_, login) = &collection.endpoints[2];
+        assert_eq!(login.body.as_deref(), Some("user=rex".as_bytes()));
+    }
+
+    #[test]
+    fn har_collection_dedupe() {
+        let document = Document::parse(ARCHIVE).unwrap();
+        let filter = Filter {
+            url: Some(Regex::new("api.example.com").unwrap()),
+            method: Some(String::from("get")),
+            dedupe: true,
+        };
+        let collection = document.collection(&EndpointHandle::from("har"), &filter);
+
+        assert_eq!(collection.endpoints.len(), 1);
+
+        let (handle, user) = &collection.endpoints[0];
+        assert_eq!(handle.handle(), "har/api-example-com/get-users-user-id");
+        assert_eq!(user.url, "https://api.example.com/users/{{user_id}}");
+        assert_eq!(collection.variables.get("user_id").unwrap(), "42");
+    }
+
+    #[test]
+    fn har_template() {
+        let (template, variables) =
+            template("/orgs/12/users/3f2504e0-4f89-11d3-9a0c-0305e82c3301/tokens/9");
+
+        assert_eq!(
+            template,
+            "/orgs/{{org_id}}/users/{{user_id}}/tokens/{{token_id}}"
+        );
+        assert_eq!(variables.len(), 3);
+        assert_eq!(variables[0], ("org_id".into(), "12".into()));
+    }
+}
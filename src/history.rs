@@ -1,7 +1,15 @@
-use crate::{snippet, Ctx, QuartzError, QuartzResult};
+use crate::{
+    encoding::{base64, base64_decode},
+    snippet,
+    sse::Event,
+    Ctx, QuartzError, QuartzResult,
+};
 use std::fmt::Display;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::Utc;
 
 use serde::{Deserialize, Serialize};
 
@@ -12,6 +20,41 @@ pub struct Entry {
 
     /// List of exchanged HTTP messages
     messages: Vec<String>,
+
+    /// URL and timings of each request sent, in the same order as request messages
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    timings: Vec<Timing>,
+
+    /// Positions of messages holding base64 encoded bodies, which were not valid UTF-8
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    binary: Vec<usize>,
+
+    /// Positions of messages holding request or response bodies, which may start like heads or
+    /// notes do
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    bodies: Vec<usize>,
+
+    /// Events received from event stream responses
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    events: Vec<Event>,
+}
+
+/// Where and when a request was sent, and how long its response took.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Timing {
+    /// Full URL of the request.
+    pub url: String,
+
+    /// Microseconds since the epoch when the request was sent.
+    pub started: i64,
+
+    /// Milliseconds until the response head was received.
+    #[serde(default)]
+    pub wait: f64,
+
+    /// Milliseconds spent reading the response body.
+    #[serde(default)]
+    pub receive: f64,
 }
 
 #[derive(Default)]
@@ -19,6 +62,10 @@ pub struct EntryBuilder {
     timestemp: i64,
     handle: Option<String>,
     messages: Vec<String>,
+    timings: Vec<Timing>,
+    binary: Vec<usize>,
+    bodies: Vec<usize>,
+    events: Vec<Event>,
 }
 
 pub struct History {
@@ -50,12 +97,14 @@ impl History {
     }
 
     pub fn entries(&self, ctx: &Ctx) -> Vec<Entry> {
-        self.entries
-            .iter()
-            .filter_map(|timestemp| {
-                Entry::read(&History::dir(ctx).join(timestemp.to_string())).ok()
-            })
-            .collect()
+        self.iter(ctx).collect()
+    }
+
+    /// Entries from newest to oldest, each read only once it is reached.
+    pub fn iter<'a>(&'a self, ctx: &'a Ctx) -> impl Iterator<Item = Entry> + 'a {
+        self.entries.iter().filter_map(|timestemp| {
+            Entry::read(&History::dir(ctx).join(timestemp.to_string())).ok()
+        })
     }
 
     pub fn dir(ctx: &Ctx) -> PathBuf {
@@ -76,6 +125,7 @@ impl History {
         std::fs::OpenOptions::new()
             .create(true)
             .write(true)
+            .truncate(true)
             .open(History::dir(ctx).join(entry.timestemp.to_string()))?
             .write_all(content.as_bytes())?;
 
@@ -106,6 +156,54 @@ impl EntryBuilder {
         self
     }
 
+    /// Adds a body message, which is stored as base64 when it is not valid UTF-8.
+    pub fn message_bytes(&mut self, value: Vec<u8>) -> &mut Self {
+        self.bodies.push(self.messages.len());
+
+        match String::from_utf8(value) {
+            Ok(text) => self.messages.push(text),
+            Err(err) => {
+                self.binary.push(self.messages.len());
+                self.messages.push(base64(err.as_bytes()));
+            }
+        }
+        self
+    }
+
+    pub fn event(&mut self, value: Event) -> &mut Self {
+        self.events.push(value);
+        self
+    }
+
+    /// Starts timing a request sent to `url`.
+    pub fn timing<T>(&mut self, url: T) -> &mut Self
+    where
+        T: Into<String>,
+    {
+        self.timings.push(Timing {
+            url: url.into(),
+            started: Utc::now().timestamp_micros(),
+            ..Default::default()
+        });
+        self
+    }
+
+    /// Records how long the last request waited for its response head.
+    pub fn waited(&mut self, duration: Duration) -> &mut Self {
+        if let Some(timing) = self.timings.last_mut() {
+            timing.wait = duration.as_secs_f64() * 1000.0;
+        }
+        self
+    }
+
+    /// Records how long the body of the last response took to be read.
+    pub fn received(&mut self, duration: Duration) -> &mut Self {
+        if let Some(timing) = self.timings.last_mut() {
+            timing.receive = duration.as_secs_f64() * 1000.0;
+        }
+        self
+    }
+
     pub fn timestemp(&mut self, value: i64) -> &mut Self {
         self.timestemp = value;
         self
@@ -122,6 +220,10 @@ impl EntryBuilder {
             handle,
             timestemp: self.timestemp,
             messages: self.messages,
+            timings: self.timings,
+            binary: self.binary,
+            bodies: self.bodies,
+            events: self.events,
         })
     }
 }
@@ -135,10 +237,99 @@ impl Entry {
         &self.handle
     }
 
+    /// Exchanged messages, with binary bodies in base64. See [`Entry::is_binary`].
     pub fn messages(&self) -> &Vec<String> {
         &self.messages
     }
 
+    /// Whether the message at `index` is a binary body, stored in base64.
+    pub fn is_binary(&self, index: usize) -> bool {
+        self.binary.contains(&index)
+    }
+
+    /// Whether the message at `index` is a request or response body, rather than a head or a
+    /// note. Entries recorded by older versions only hold heads and bodies, told apart by the
+    /// prefix of heads.
+    pub fn is_body(&self, index: usize) -> bool {
+        if !self.timings.is_empty() {
+            return self.bodies.contains(&index);
+        }
+
+        self.messages
+            .get(index)
+            .is_some_and(|m| !m.starts_with("> ") && !m.starts_with("< "))
+    }
+
+    /// Exact bytes of the message at `index`.
+    pub fn message_bytes(&self, index: usize) -> Option<Vec<u8>> {
+        let message = self.messages.get(index)?;
+
+        match self.is_binary(index) {
+            true => base64_decode(message),
+            false => Some(message.as_bytes().to_vec()),
+        }
+    }
+
+    /// Events received from event stream responses, in order.
+    pub fn events(&self) -> &Vec<Event> {
+        &self.events
+    }
+
+    /// Microseconds since the epoch when the entry was recorded.
+    pub fn timestemp(&self) -> i64 {
+        self.timestemp
+    }
+
+    /// URL and timings of each request, which entries recorded by older versions lack.
+    pub fn timings(&self) -> &Vec<Timing> {
+        &self.timings
+    }
+
+    /// Position of the last response head, which is followed by its body.
+    fn response_index(&self) -> Option<usize> {
+        (0..self.messages.len())
+            .rev()
+            .find(|&i| !self.is_body(i) && self.messages[i].starts_with("< HTTP"))
+    }
+
+    /// Position of the last response body, or of the error recorded in its place.
+    pub fn response_body_index(&self) -> Option<usize> {
+        Some(self.response_index()? + 1).filter(|i| *i < self.messages.len())
+    }
+
+    /// Status code of the last response.
+    pub fn status(&self) -> Option<u16> {
+        let head = &self.messages[self.response_index()?];
+
+        head.split_whitespace().nth(2)?.parse().ok()
+    }
+
+    /// Headers of the last response.
+    pub fn response_headers(&self) -> Vec<(&str, &str)> {
+        let Some(index) = self.response_index() else {
+            return Vec::new();
+        };
+
+        self.messages[index]
+            .lines()
+            .skip(1)
+            .filter_map(|line| line.trim_start_matches('<').split_once(':'))
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .collect()
+    }
+
+    /// Body of the last response, when it was fully received and is text.
+    pub fn response_body(&self) -> Option<&str> {
+        let index = self.response_body_index()?;
+        let body = &self.messages[index];
+
+        // Read errors are recorded in place of the body
+        match !self.is_body(index) || self.is_binary(index) {
+            true => None,
+            false => Some(body),
+        }
+    }
+
     pub fn read(path: &Path) -> QuartzResult<Self> {
         let content = std::fs::read_to_string(path)?;
 
@@ -149,7 +340,24 @@ impl Entry {
 impl Display for Entry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "{}", self.handle)?;
-        write!(f, "{}", self.messages.join("\n"))?;
+
+        let messages: Vec<String> = self
+            .messages
+            .iter()
+            .enumerate()
+            .map(|(i, message)| match self.is_binary(i) {
+                true => format!(
+                    "[binary body, {} bytes]",
+                    self.message_bytes(i).unwrap_or_default().len()
+                ),
+                false => message.to_owned(),
+            })
+            .collect();
+        write!(f, "{}", messages.join("\n"))?;
+
+        for event in &self.events {
+            write!(f, "\n\n{}", event.to_string().trim_end())?;
+        }
 
         Ok(())
     }
@@ -0,0 +1,328 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use regex::Regex;
+
+use crate::{
+    collection::{slug, Collection},
+    endpoint::{Endpoint, EndpointHandle},
+};
+
+const METHODS: [&str; 9] = [
+    "GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS", "TRACE", "CONNECT",
+];
+
+/// Parses `.http` files, as used by the VS Code REST Client and JetBrains HTTP Client, into
+/// endpoints under `root`.
+///
+/// Requests are separated by lines starting with `###`. They are named by a `# @name`
+/// annotation, whose slashes create nested handles, or by the text following `###`. File
+/// variables, such as `@host = localhost`, are kept as variables.
+///
+/// # Examples
+///
+/// ```
+/// use quartz_cli::{endpoint::EndpointHandle, http_file};
+///
+/// let collection = http_file::parse(
+///     "@host = http://localhost\n\n### Get user\nGET {{host}}/users/1\nAccept: */*\n",
+///     &EndpointHandle::from("api"),
+/// );
+///
+/// let (handle, endpoint) = &collection.endpoints[0];
+/// assert_eq!(handle.handle(), "api/get-user");
+/// assert_eq!(endpoint.url, "{{host}}/users/1");
+/// assert_eq!(collection.variables.get("host").unwrap(), "http://localhost");
+/// ```
+pub fn parse(input: &str, root: &EndpointHandle) -> Collection {
+    let mut collection = Collection::default();
+    let mut taken = HashSet::new();
+
+    let mut blocks: Vec<(Option<&str>, Vec<&str>)> = vec![(None, Vec::new())];
+    for line in input.lines() {
+        match line.strip_prefix("###") {
+            Some(title) => blocks.push((Some(title.trim()), Vec::new())),
+            None => blocks.last_mut().unwrap().1.push(line),
+        }
+    }
+
+    for (title, lines) in blocks {
+        let Some((name, endpoint)) = request(&lines, &mut collection) else {
+            continue;
+        };
+
+        let name = name
+            .map(|name| name.split('/').map(String::from).collect())
+            .or(title.map(slug).filter(|t| !t.is_empty()).map(|t| vec![t]))
+            .unwrap_or_else(|| {
+                vec![slug(&format!(
+                    "{} {}",
+                    endpoint.method,
+                    path(&endpoint.url)
+                ))]
+            });
+
+        let mut handle = root.clone();
+        handle.path.extend(name);
+
+        let head = handle.head();
+        let mut n = 1;
+        while !taken.insert(handle.handle()) {
+            n += 1;
+            *handle.path.last_mut().unwrap() = format!("{head}-{n}");
+        }
+
+        let dynamic = endpoint.url.contains("{{$")
+            || endpoint.headers.values().any(|v| v.contains("{{$"))
+            || endpoint
+                .body
+                .as_ref()
+                .is_some_and(|b| String::from_utf8_lossy(b).contains("{{$"));
+        if dynamic {
+            collection.warnings.push(format!(
+                "{}: dynamic variables are not supported",
+                handle.handle()
+            ));
+        }
+
+        collection.endpoints.push((handle, endpoint));
+    }
+
+    collection
+}
+
+/// Parses the lines of a request block, returning its `@name` annotation and endpoint. File
+/// variables found along the way are added to `collection`.
+fn request(lines: &[&str], collection: &mut Collection) -> Option<(Option<String>, Endpoint)> {
+    let annotation = Regex::new(r"^(?:#|//)\s*@name\s*=?\s*(\S+)").unwrap();
+    let variable = Regex::new(r"^@([\w.-]+)\s*=\s*(.*)$").unwrap();
+
+    let mut lines = lines.iter().map(|line| line.trim_end());
+    let mut name = None;
+
+    let request_line = loop {
+        let line = lines.next()?;
+
+        if let Some(captures) = annotation.captures(line) {
+            name = Some(captures[1].trim_matches('/').to_string());
+        } else if let Some(captures) = variable.captures(line) {
+            collection
+                .variables
+                .insert(captures[1].to_string(), captures[2].trim().to_string());
+        } else if !(line.trim().is_empty() || line.starts_with('#') || line.starts_with("//")) {
+            break line;
+        }
+    };
+
+    let mut words: Vec<&str> = request_line.split_whitespace().collect();
+    if words.len() > 1 && words.last().is_some_and(|w| w.starts_with("HTTP/")) {
+        words.pop();
+    }
+
+    let (method, url) = match words.first() {
+        Some(word) if METHODS.contains(word) && words.len() > 1 => (*word, words[1..].join(" ")),
+        _ => ("GET", words.join(" ")),
+    };
+
+    let mut endpoint = Endpoint {
+        method: method.to_string(),
+        ..Default::default()
+    };
+
+    let mut url = url;
+    let mut lines = lines.peekable();
+
+    // Query params may continue on the following lines
+    while let Some(line) = lines.next_if(|l| l.trim_start().starts_with(['?', '&'])) {
+        url.push_str(line.trim());
+    }
+
+    let (base, query) = url.split_once('?').unwrap_or((&url, ""));
+    endpoint.url = base.to_string();
+    for param in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = param.split_once('=').unwrap_or((param, ""));
+        endpoint.query.insert(key.to_string(), value.to_string());
+    }
+
+    for line in lines.by_ref() {
+        if line.trim().is_empty() {
+            break;
+        }
+
+        if line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+
+        if let Some((key, value)) = line.split_once(':') {
+            endpoint
+                .headers
+                .insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+
+    let mut body: Vec<&str> = Vec::new();
+    let mut script = false;
+    for line in lines {
+        if script {
+            script = !line.contains("%}");
+        } else if line.starts_with("> {%") {
+            // Response handler scripts, which quartz does not run
+            script = !line.contains("%}");
+            collection
+                .warnings
+                .push(format!("{url}: response handler scripts are not supported"));
+        } else if line.starts_with(">>") || line.starts_with("<>") {
+            continue;
+        } else if line.starts_with("< ") && body.is_empty() {
+            collection
+                .warnings
+                .push(format!("{url}: bodies read from files are not supported"));
+            return Some((name, endpoint));
+        } else {
+            body.push(line);
+        }
+    }
+
+    while body.last().is_some_and(|line| line.trim().is_empty()) {
+        body.pop();
+    }
+
+    if !body.is_empty() {
+        endpoint.body = Some(body.join("\n").into_bytes());
+    }
+
+    Some((name, endpoint))
+}
+
+/// Writes `endpoints` as requests of a `.http` file, named by their handle, so they are
+/// imported back into the same handles.
+///
+/// URLs should already be resolved, so the file does not depend on parent handles.
+pub fn write(endpoints: Vec<(EndpointHandle, Endpoint)>) -> String {
+    let mut out = String::new();
+
+    for (handle, mut endpoint) in endpoints {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+
+        let _ = writeln!(out, "###");
+        let _ = writeln!(out, "# @name {}", handle.handle());
+
+        let mut url = endpoint.url.clone();
+        let query = endpoint.query_string();
+        if !query.is_empty() {
+            url.push(if url.contains('?') { '&' } else { '?' });
+            url.push_str(&query);
+        }
+        let _ = writeln!(out, "{} {url}", endpoint.method);
+
+        let mut headers: Vec<(&String, &String)> = endpoint.headers.iter().collect();
+        headers.sort();
+        for (key, value) in headers {
+            let _ = writeln!(out, "{key}: {value}");
+        }
+
+        if let Some(body) = endpoint.body() {
+            let body = String::from_utf8_lossy(body);
+            let _ = writeln!(out, "\n{}", body.trim_end_matches('\n'));
+        }
+    }
+
+    out
+}
+
+/// Path of `url`, without its origin, which may be a variable as in "{{host}}/users".
+fn path(url: &str) -> &str {
+    let rest = match url.split_once("://") {
+        Some((_, rest)) => rest,
+        None if url.starts_with("{{") => url.split_once("}}").map(|(_, r)| r).unwrap_or(url),
+        None => url,
+    };
+
+    match rest.find('/') {
+        Some(i) => &rest[i..],
+        None => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = r#"
+@baseUrl = https://example.com
+@token = abc
+
+### Create user
+# @name users/create
+POST {{baseUrl}}/users HTTP/1.1
+Content-Type: application/json
+Authorization: Bearer {{token}}
+
+{
+  "name": "Rex"
+}
+
+> {% client.global.set("id", response.body.id); %}
+
+###
+GET {{baseUrl}}/users
+    ?page=2
+    &limit=10
+
+### Upload
+PUT {{baseUrl}}/files
+Content-Type: text/plain
+
+< ./file.txt
+"#;
+
+    #[test]
+    fn http_file_parse() {
+        let collection = parse(FILE, &EndpointHandle::from("api"));
+        let handles: Vec<String> = collection
+            .endpoints
+            .iter()
+            .map(|(handle, _)| handle.handle())
+            .collect();
+
+        assert_eq!(handles, ["api/users/create", "api/get-users", "api/upload"]);
+        assert_eq!(collection.variables.get("token").unwrap(), "abc");
+
+        let (_, create) = &collection.endpoints[0];
+        assert_eq!(create.method, "POST");
+        assert_eq!(create.url, "{{baseUrl}}/users");
+        assert_eq!(
+            create.headers.get("Authorization").unwrap(),
+            "Bearer {{token}}"
+        );
+        assert_eq!(
+            create.body.as_deref(),
+            Some("{\n  \"name\": \"Rex\"\n}".as_bytes())
+        );
+
+        let (_, list) = &collection.endpoints[1];
+        assert_eq!(list.query.get("page").unwrap(), "2");
+        assert_eq!(list.query.get("limit").unwrap(), "10");
+
+        let (_, upload) = &collection.endpoints[2];
+        assert!(upload.body.is_none());
+        assert_eq!(collection.warnings.len(), 2);
+    }
+
+    #[test]
+    fn http_file_round_trip() {
+        let collection = parse(FILE, &EndpointHandle::from("api"));
+        let written = write(collection.endpoints);
+        let parsed = parse(&written, &EndpointHandle::QUARTZ);
+
+        let (handle, create) = &parsed.endpoints[0];
+        assert_eq!(handle.handle(), "api/users/create");
+        assert_eq!(
+            create.body.as_deref(),
+            Some("{\n  \"name\": \"Rex\"\n}".as_bytes())
+        );
+        assert!(written.contains("GET {{baseUrl}}/users?limit=10&page=2\n"));
+    }
+}
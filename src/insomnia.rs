@@ -0,0 +1,587 @@
+use std::collections::HashSet;
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::{
+    collection::{slug, Collection},
+    encoding::base64,
+    endpoint::{Endpoint, EndpointHandle},
+    env::{Env, Variables},
+    QuartzResult,
+};
+
+/// Insomnia export, either in format 4, a flat list of resources linked by their parent ID,
+/// or in format 5, a tree of requests.
+pub struct Document {
+    doc: Value,
+    version: Version,
+}
+
+#[derive(Clone, Copy)]
+enum Version {
+    V4,
+    V5,
+}
+
+enum Kind<'a> {
+    Folder,
+    Request,
+    Unsupported(&'a str),
+}
+
+impl Document {
+    /// Parses an export written in JSON or YAML.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` is malformed or is not an Insomnia export.
+    pub fn parse(input: &str) -> QuartzResult<Self> {
+        let doc: Value = if input.trim_start().starts_with('{') {
+            serde_json::from_str(input).map_err(|err| format!("invalid JSON: {err}"))?
+        } else {
+            serde_yaml::from_str(input).map_err(|err| format!("invalid YAML: {err}"))?
+        };
+
+        let v4 = doc.get("_type").and_then(Value::as_str) == Some("export")
+            && doc.get("resources").is_some_and(Value::is_array);
+        let v5 = doc
+            .get("type")
+            .and_then(Value::as_str)
+            .is_some_and(|kind| kind.starts_with("collection.insomnia.rest/5"));
+
+        let version = match (v4, v5) {
+            (true, _) => Version::V4,
+            (_, true) => Version::V5,
+            _ => return Err("not an Insomnia export".into()),
+        };
+
+        Ok(Self { doc, version })
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        match self.version {
+            Version::V4 => self.workspaces().first()?.get("name")?.as_str(),
+            Version::V5 => self.doc.get("name")?.as_str(),
+        }
+    }
+
+    /// Maps request groups to empty handles and requests to endpoints under `root`. Variables
+    /// of the base environment are kept as variables.
+    pub fn collection(&self, root: &EndpointHandle) -> Collection {
+        let mut collection = Collection::default();
+
+        if let Some(base) = self.base_environment() {
+            flatten("", base.get("data"), &mut collection.variables);
+        }
+
+        let mut taken = HashSet::new();
+        match self.version {
+            Version::V4 => {
+                let workspaces = self.workspaces();
+
+                for workspace in &workspaces {
+                    // Each workspace gets its own handle when there are many
+                    let mut parent = root.clone();
+                    if workspaces.len() > 1 {
+                        parent.path.push(name_of(workspace));
+                    }
+
+                    let items = self.children(workspace);
+                    self.walk(items, &parent, &mut taken, &mut collection);
+                }
+            }
+            Version::V5 => {
+                let items = list(self.doc.get("collection"));
+                self.walk(items, root, &mut taken, &mut collection);
+            }
+        }
+
+        collection
+    }
+
+    /// Sub-environments, each including the variables of the base environment.
+    pub fn environments(&self) -> Vec<Env> {
+        let Some(base) = self.base_environment() else {
+            return Vec::new();
+        };
+
+        let subs = match self.version {
+            Version::V4 => self.resources("environment", base.get("_id")),
+            Version::V5 => list(base.get("subEnvironments")),
+        };
+
+        subs.into_iter()
+            .map(|sub| {
+                let mut env = Env::new(&name_of(sub));
+                flatten("", base.get("data"), &mut env.variables);
+                flatten("", sub.get("data"), &mut env.variables);
+
+                env
+            })
+            .collect()
+    }
+
+    fn walk(
+        &self,
+        items: Vec<&Value>,
+        parent: &EndpointHandle,
+        taken: &mut HashSet<String>,
+        collection: &mut Collection,
+    ) {
+        for item in items {
+            let Some(kind) = self.kind(item) else {
+                continue;
+            };
+
+            let name = name_of(item);
+            let mut path = parent.path.clone();
+            path.push(name.clone());
+
+            let mut handle = EndpointHandle::new(path);
+            let mut n = 1;
+            while !taken.insert(handle.handle()) {
+                n += 1;
+                *handle.path.last_mut().unwrap() = format!("{name}-{n}");
+            }
+
+            match kind {
+                Kind::Folder => {
+                    let mut variables = Variables::default();
+                    flatten("", item.get("environment"), &mut variables);
+
+                    for (key, value) in variables.0 {
+                        match collection.variables.get(&key) {
+                            Some(existing) if *existing != value => {
+                                collection.warnings.push(format!(
+                                    "{}: folder variable {key} conflicts with another value",
+                                    handle.handle()
+                                ));
+                            }
+                            _ => {
+                                collection.variables.insert(key, value);
+                            }
+                        }
+                    }
+
+                    self.walk(self.children(item), &handle, taken, collection);
+                }
+                Kind::Request => {
+                    let endpoint = request(item, &handle.handle(), &mut collection.warnings);
+                    collection.endpoints.push((handle, endpoint));
+                }
+                Kind::Unsupported(kind) => collection.warnings.push(format!(
+                    "{}: {kind} requests are not supported",
+                    handle.handle()
+                )),
+            }
+        }
+    }
+
+    fn kind<'a>(&self, item: &'a Value) -> Option<Kind<'a>> {
+        match self.version {
+            Version::V4 => match item.get("_type")?.as_str()? {
+                "request_group" => Some(Kind::Folder),
+                "request" => Some(Kind::Request),
+                kind => kind.strip_suffix("_request").map(Kind::Unsupported),
+            },
+            Version::V5 if item.get("children").is_some() => Some(Kind::Folder),
+            Version::V5 if item.get("method").is_some() => Some(Kind::Request),
+            Version::V5 => Some(Kind::Unsupported("non-HTTP")),
+        }
+    }
+
+    fn children<'a>(&'a self, item: &'a Value) -> Vec<&'a Value> {
+        match self.version {
+            Version::V4 => {
+                let mut children: Vec<&Value> = list(self.doc.get("resources"))
+                    .into_iter()
+                    .filter(|r| r.get("parentId").is_some() && r.get("parentId") == item.get("_id"))
+                    .collect();
+
+                children.sort_by(|a, b| {
+                    let key = |r: &Value| r.get("metaSortKey").and_then(Value::as_f64);
+                    key(a)
+                        .partial_cmp(&key(b))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+
+                children
+            }
+            Version::V5 => list(item.get("children")),
+        }
+    }
+
+    fn workspaces(&self) -> Vec<&Value> {
+        self.resources("workspace", None)
+    }
+
+    /// Resources of type `kind`, with the given parent ID when there is one.
+    fn resources(&self, kind: &str, parent: Option<&Value>) -> Vec<&Value> {
+        list(self.doc.get("resources"))
+            .into_iter()
+            .filter(|r| r.get("_type").and_then(Value::as_str) == Some(kind))
+            .filter(|r| parent.is_none() || r.get("parentId") == parent)
+            .collect()
+    }
+
+    fn base_environment(&self) -> Option<&Value> {
+        match self.version {
+            Version::V4 => {
+                let workspaces: Vec<&Value> = self
+                    .workspaces()
+                    .into_iter()
+                    .filter_map(|w| w.get("_id"))
+                    .collect();
+
+                self.resources("environment", None).into_iter().find(|env| {
+                    env.get("parentId")
+                        .is_some_and(|id| workspaces.contains(&id))
+                })
+            }
+            Version::V5 => self.doc.get("environments"),
+        }
+    }
+}
+
+fn request(item: &Value, name: &str, warnings: &mut Vec<String>) -> Endpoint {
+    let mut convert = |input: &str| template(input, name, warnings);
+
+    let mut endpoint = Endpoint {
+        method: item
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or("GET")
+            .to_string(),
+        url: convert(item.get("url").and_then(Value::as_str).unwrap_or_default()),
+        ..Default::default()
+    };
+
+    for (key, value) in pairs(item.get("parameters")) {
+        endpoint.query.insert(convert(&key), convert(&value));
+    }
+
+    for (key, value) in pairs(item.get("headers")) {
+        endpoint.headers.insert(convert(&key), convert(&value));
+    }
+
+    if let Some(auth) = item.get("authentication") {
+        self::auth(auth, &mut endpoint, name, warnings);
+    }
+
+    if let Some(body) = item.get("body") {
+        self::body(body, &mut endpoint, name, warnings);
+    }
+
+    endpoint
+}
+
+fn auth(auth: &Value, endpoint: &mut Endpoint, name: &str, warnings: &mut Vec<String>) {
+    if auth.get("disabled") == Some(&Value::Bool(true)) {
+        return;
+    }
+
+    let mut attribute = |key: &str| {
+        let value = auth.get(key).and_then(Value::as_str).unwrap_or_default();
+        template(value, name, warnings)
+    };
+
+    match auth.get("type").and_then(Value::as_str) {
+        None | Some("none") => (),
+        Some("bearer") => {
+            let prefix = attribute("prefix");
+            let prefix = if prefix.is_empty() {
+                "Bearer".into()
+            } else {
+                prefix
+            };
+            let token = attribute("token");
+
+            endpoint
+                .headers
+                .insert("Authorization".into(), format!("{prefix} {token}"));
+        }
+        Some("basic") => {
+            let credentials = format!("{}:{}", attribute("username"), attribute("password"));
+
+            if credentials.contains("{{") {
+                warnings.push(format!(
+                    "{name}: basic authentication with variables is not supported"
+                ));
+            } else {
+                endpoint.headers.insert(
+                    "Authorization".into(),
+                    format!("Basic {}", base64(credentials.as_bytes())),
+                );
+            }
+        }
+        Some("apikey") => {
+            let key = attribute("key");
+            let value = attribute("value");
+
+            if attribute("addTo") == "queryParams" {
+                endpoint.query.insert(key, value);
+            } else {
+                endpoint.headers.insert(key, value);
+            }
+        }
+        Some(kind) => warnings.push(format!("{name}: {kind} authentication is not supported")),
+    }
+}
+
+fn body(body: &Value, endpoint: &mut Endpoint, name: &str, warnings: &mut Vec<String>) {
+    let media = body
+        .get("mimeType")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+
+    let content = if let Some(text) = body.get("text").and_then(Value::as_str) {
+        template(text, name, warnings)
+    } else if media == "application/x-www-form-urlencoded" {
+        pairs(body.get("params"))
+            .into_iter()
+            .map(|(key, value)| template(&format!("{key}={value}"), name, warnings))
+            .collect::<Vec<String>>()
+            .join("&")
+    } else if body.get("params").is_some() || body.get("fileName").is_some() {
+        warnings.push(format!("{name}: {media} bodies are not supported"));
+        return;
+    } else {
+        return;
+    };
+
+    if content.is_empty() {
+        return;
+    }
+
+    let has_type = endpoint
+        .headers
+        .keys()
+        .any(|key| key.eq_ignore_ascii_case("content-type"));
+
+    if !has_type && !media.is_empty() {
+        // GraphQL queries are sent as JSON
+        let media = match media {
+            "application/graphql" => "application/json",
+            media => media,
+        };
+
+        endpoint
+            .headers
+            .insert("Content-type".into(), media.to_string());
+    }
+
+    endpoint.body = Some(content.into_bytes());
+}
+
+/// Converts Insomnia template variables, such as `{{ _.base_url }}`, into quartz variables.
+/// Template tags, such as `{% uuid %}`, are reported.
+fn template(input: &str, name: &str, warnings: &mut Vec<String>) -> String {
+    let variable = Regex::new(r"\{\{\s*(?:_\.)?([\w.-]+)\s*\}\}").unwrap();
+
+    if input.contains("{%") {
+        let warning = format!("{name}: template tags are not supported");
+
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    }
+
+    variable.replace_all(input, "{{$1}}").to_string()
+}
+
+/// Adds the values of `data` to `variables`, naming nested values by their path, such as
+/// "auth.token".
+fn flatten(prefix: &str, data: Option<&Value>, variables: &mut Variables) {
+    let Some(Value::Object(data)) = data else {
+        return;
+    };
+
+    for (key, value) in data {
+        let key = if prefix.is_empty() {
+            key.to_owned()
+        } else {
+            format!("{prefix}.{key}")
+        };
+
+        match value {
+            Value::Object(_) => flatten(&key, Some(value), variables),
+            Value::Null => (),
+            Value::String(s) => {
+                variables.insert(key, template(s, "", &mut Vec::new()));
+            }
+            value => {
+                variables.insert(key, value.to_string());
+            }
+        }
+    }
+}
+
+fn name_of(item: &Value) -> String {
+    let name = item
+        .get("name")
+        .and_then(Value::as_str)
+        .map(slug)
+        .unwrap_or_default();
+
+    if name.is_empty() {
+        String::from("request")
+    } else {
+        name
+    }
+}
+
+fn list(value: Option<&Value>) -> Vec<&Value> {
+    value
+        .and_then(Value::as_array)
+        .map(|values| values.iter().collect())
+        .unwrap_or_default()
+}
+
+/// Names and values of entries that are not disabled.
+fn pairs(value: Option<&Value>) -> Vec<(String, String)> {
+    list(value)
+        .into_iter()
+        .filter(|entry| entry.get("disabled") != Some(&Value::Bool(true)))
+        .filter_map(|entry| {
+            let name = entry.get("name")?.as_str()?;
+            let value = match entry.get("value") {
+                Some(Value::String(s)) => s.to_owned(),
+                Some(Value::Null) | None => String::new(),
+                Some(value) => value.to_string(),
+            };
+
+            Some((name.to_string(), value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V4: &str = r#"{
+      "_type": "export",
+      "__export_format": 4,
+      "resources": [
+        { "_id": "req_2", "_type": "request", "parentId": "fld_1", "metaSortKey": 2, "name": "Create user",
+          "method": "POST", "url": "{{ _.base_url }}/users",
+          "body": { "mimeType": "application/json", "text": "{\"id\": \"{% uuid 'v4' %}\"}" },
+          "authentication": { "type": "bearer", "token": "{{ token }}" } },
+        { "_id": "req_1", "_type": "request", "parentId": "fld_1", "metaSortKey": 1, "name": "List users",
+          "method": "GET", "url": "{{ _.base_url }}/users",
+          "parameters": [{ "name": "page", "value": "1" }, { "name": "debug", "value": "1", "disabled": true }],
+          "headers": [{ "name": "Accept", "value": "application/json" }] },
+        { "_id": "fld_1", "_type": "request_group", "parentId": "wrk_1", "name": "Users" },
+        { "_id": "ws_1", "_type": "websocket_request", "parentId": "wrk_1", "name": "Live" },
+        { "_id": "wrk_1", "_type": "workspace", "parentId": null, "name": "My Workspace" },
+        { "_id": "env_1", "_type": "environment", "parentId": "wrk_1", "name": "Base Environment",
+          "data": { "base_url": "http://localhost", "auth": { "user": "admin" } } },
+        { "_id": "env_2", "_type": "environment", "parentId": "env_1", "name": "Production",
+          "data": { "base_url": "https://example.com" } }
+      ]
+    }"#;
+
+    const V5: &str = r#"
+type: collection.insomnia.rest/5.0
+name: My Collection
+collection:
+  - name: Users
+    children:
+      - name: List users
+        method: GET
+        url: "{{ _.base_url }}/users"
+        authentication:
+          type: apikey
+          key: X-Key
+          value: secret
+environments:
+  name: Base Environment
+  data:
+    base_url: http://localhost
+  subEnvironments:
+    - name: Staging
+      data:
+        base_url: https://staging.example.com
+"#;
+
+    fn handles(collection: &Collection) -> Vec<String> {
+        collection
+            .endpoints
+            .iter()
+            .map(|(handle, _)| handle.handle())
+            .collect()
+    }
+
+    #[test]
+    fn insomnia_v4() {
+        let document = Document::parse(V4).unwrap();
+        assert_eq!(document.name(), Some("My Workspace"));
+
+        let collection = document.collection(&EndpointHandle::from("api"));
+        assert_eq!(
+            handles(&collection),
+            ["api/users/list-users", "api/users/create-user"]
+        );
+
+        let (_, list) = &collection.endpoints[0];
+        assert_eq!(list.url, "{{base_url}}/users");
+        assert_eq!(list.query.get("page").unwrap(), "1");
+        assert!(list.query.get("debug").is_none());
+        assert_eq!(list.headers.get("Accept").unwrap(), "application/json");
+
+        let (_, create) = &collection.endpoints[1];
+        assert_eq!(
+            create.headers.get("Authorization").unwrap(),
+            "Bearer {{token}}"
+        );
+        assert_eq!(
+            create.headers.get("Content-type").unwrap(),
+            "application/json"
+        );
+
+        assert_eq!(collection.variables.get("auth.user").unwrap(), "admin");
+        assert_eq!(
+            collection.warnings,
+            [
+                "api/users/create-user: template tags are not supported",
+                "api/live: websocket requests are not supported",
+            ]
+        );
+
+        let envs = document.environments();
+        assert_eq!(envs.len(), 1);
+        assert_eq!(envs[0].name, "production");
+        assert_eq!(
+            envs[0].variables.get("base_url").unwrap(),
+            "https://example.com"
+        );
+        assert_eq!(envs[0].variables.get("auth.user").unwrap(), "admin");
+    }
+
+    #[test]
+    fn insomnia_v5() {
+        let document = Document::parse(V5).unwrap();
+        assert_eq!(document.name(), Some("My Collection"));
+
+        let collection = document.collection(&EndpointHandle::from("api"));
+        assert_eq!(handles(&collection), ["api/users/list-users"]);
+
+        let (_, list) = &collection.endpoints[0];
+        assert_eq!(list.url, "{{base_url}}/users");
+        assert_eq!(list.headers.get("X-Key").unwrap(), "secret");
+
+        let envs = document.environments();
+        assert_eq!(envs[0].name, "staging");
+        assert_eq!(
+            envs[0].variables.get("base_url").unwrap(),
+            "https://staging.example.com"
+        );
+    }
+
+    #[test]
+    fn insomnia_parse_malformed() {
+        assert!(Document::parse("{ \"item\": [] }").is_err());
+        assert!(Document::parse("type: collection.insomnia.rest/5.0\nname: [").is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::{env::Variables, QuartzResult};
+
+/// Reads iteration data from a CSV or JSON file at `path`, returning one set of variables per
+/// row.
+///
+/// JSON is used for files ending with ".json" or starting with "[", CSV otherwise.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read or is malformed.
+pub fn read(path: &Path) -> QuartzResult<Vec<Variables>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| format!("could not read {}: {err}", path.display()))?;
+
+    let json =
+        path.extension().is_some_and(|ext| ext == "json") || content.trim_start().starts_with('[');
+
+    if json {
+        parse_json(&content)
+    } else {
+        parse_csv(&content)
+    }
+}
+
+/// Parses a JSON array of objects into rows of variables.
+///
+/// String values are used as they are, while other values are written as JSON. Null values
+/// are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use quartz_cli::iteration;
+///
+/// let rows = iteration::parse_json(r#"[{ "id": 1, "name": "quartz" }, { "id": 2 }]"#).unwrap();
+///
+/// assert_eq!(rows.len(), 2);
+/// assert_eq!(rows[0].get("id").unwrap(), "1");
+/// assert_eq!(rows[0].get("name").unwrap(), "quartz");
+/// assert!(rows[1].get("name").is_none());
+/// ```
+pub fn parse_json(input: &str) -> QuartzResult<Vec<Variables>> {
+    let list: Vec<serde_json::Map<String, Value>> = serde_json::from_str(input)
+        .map_err(|err| format!("iteration data must be an array of objects: {err}"))?;
+
+    let rows = list
+        .into_iter()
+        .map(|object| {
+            let mut variables = Variables::default();
+
+            for (key, value) in object {
+                let value = match value {
+                    Value::Null => continue,
+                    Value::String(s) => s,
+                    value => value.to_string(),
+                };
+
+                variables.insert(key, value);
+            }
+
+            variables
+        })
+        .collect();
+
+    Ok(rows)
+}
+
+/// Parses CSV content, whose first line names the variables, into rows of variables.
+///
+/// Fields may be quoted to contain commas, line breaks, or quotes escaped as `""`. Empty lines
+/// are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use quartz_cli::iteration;
+///
+/// let rows = iteration::parse_csv("id,name\n1,quartz\n2,\"hello, \"\"world\"\"\"\n").unwrap();
+///
+/// assert_eq!(rows.len(), 2);
+/// assert_eq!(rows[0].get("name").unwrap(), "quartz");
+/// assert_eq!(rows[1].get("name").unwrap(), "hello, \"world\"");
+/// ```
+pub fn parse_csv(input: &str) -> QuartzResult<Vec<Variables>> {
+    let mut records = csv_records(input)?.into_iter();
+
+    let header = records.next().ok_or("iteration data has no header")?;
+
+    records
+        .enumerate()
+        .map(|(i, record)| {
+            if record.len() != header.len() {
+                return Err(format!(
+                    "iteration data row {} has {} fields, expected {}",
+                    i + 1,
+                    record.len(),
+                    header.len()
+                )
+                .into());
+            }
+
+            let mut variables = Variables::default();
+            for (key, value) in header.iter().zip(record) {
+                variables.insert(key.trim().to_string(), value);
+            }
+
+            Ok(variables)
+        })
+        .collect()
+}
+
+fn csv_records(input: &str) -> QuartzResult<Vec<Vec<String>>> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => (),
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+
+                if record.len() > 1 || !record[0].is_empty() {
+                    records.push(std::mem::take(&mut record));
+                } else {
+                    record.clear();
+                }
+            }
+            ch => field.push(ch),
+        }
+    }
+
+    if quoted {
+        return Err("iteration data has an unclosed quote".into());
+    }
+
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_line_breaks() {
+        let rows = parse_csv("id,note\r\n1,\"first\nline\"\r\n\r\n2,\r\n").unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].get("note").unwrap(), "first\nline");
+        assert_eq!(rows[1].get("id").unwrap(), "2");
+        assert_eq!(rows[1].get("note").unwrap(), "");
+    }
+
+    #[test]
+    fn csv_malformed() {
+        assert!(parse_csv("").is_err());
+        assert!(parse_csv("id,name\n1\n").is_err());
+        assert!(parse_csv("id\n\"1\n").is_err());
+    }
+}
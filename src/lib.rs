@@ -1,14 +1,32 @@
 pub mod action;
+pub mod assertion;
+pub mod capture;
 pub mod cli;
+pub mod collection;
 pub mod config;
 pub mod cookie;
+pub mod curl;
+pub mod download;
+pub mod encoding;
 pub mod endpoint;
 pub mod env;
+pub mod form;
+pub mod har;
 pub mod history;
+pub mod http_file;
+pub mod insomnia;
+pub mod iteration;
+pub mod openapi;
+pub mod postman;
+pub mod report;
+pub mod request;
 pub mod snippet;
+pub mod sse;
 pub mod state;
+pub mod template;
 pub mod tree;
 pub mod validator;
+pub mod websocket;
 
 use std::error::Error;
 use std::fmt::Display;
@@ -42,6 +60,16 @@ impl Display for QuartzError {
 
 impl Error for QuartzError {}
 
+/// Bytes from a cryptographically secure generator, for values peers must not be able to
+/// predict.
+pub(crate) fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0; N];
+    openssl::rand::rand_bytes(&mut bytes)
+        .unwrap_or_else(|err| panic!("failed to generate random bytes: {err}"));
+
+    bytes
+}
+
 pub trait PairMap<'a, K = String, V = String>
 where
     K: Eq + PartialEq + Hash + From<&'a str>,
@@ -266,8 +294,9 @@ impl Ctx {
                 panic!("failed to open pager: {}\n\n{}", pager, err);
             });
 
-        child.stdin.as_mut().unwrap().write_all(input)?;
+        let written = child.stdin.as_mut().unwrap().write_all(input);
         child.wait()?;
+        written?;
 
         Ok(())
     }
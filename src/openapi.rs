@@ -0,0 +1,777 @@
+use std::collections::{HashMap, HashSet};
+
+use colored::Colorize;
+use hyper::StatusCode;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    collection::{slug, Collection},
+    endpoint::{Endpoint, EndpointHandle},
+    env::Variables,
+    history::Entry,
+    QuartzResult,
+};
+
+/// Operation methods, in the order they are imported for each path.
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// Headers described by other parts of the specification, which are ignored as parameters.
+const RESERVED_HEADERS: [&str; 3] = ["accept", "authorization", "content-type"];
+
+/// Nesting limit when following references or building example bodies, so recursive schemas
+/// terminate.
+const MAX_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum GroupBy {
+    /// One handle per tag, with its operations as sub-handles
+    Tags,
+    /// One handle per path segment, with a sub-handle per method
+    Paths,
+}
+
+/// OpenAPI 3 specification document.
+pub struct Spec {
+    doc: Value,
+}
+
+impl Spec {
+    /// Parses a specification written in JSON or YAML.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` is malformed or is not an OpenAPI 3
+    /// document.
+    pub fn parse(input: &str) -> QuartzResult<Self> {
+        let doc: Value = if input.trim_start().starts_with('{') {
+            serde_json::from_str(input).map_err(|err| format!("invalid JSON: {err}"))?
+        } else {
+            serde_yaml::from_str(input).map_err(|err| format!("invalid YAML: {err}"))?
+        };
+
+        // YAML reads versions such as `3.1` as numbers
+        let version = match doc.get("openapi") {
+            Some(Value::String(version)) => Some(version.to_owned()),
+            Some(Value::Number(version)) => Some(version.to_string()),
+            _ => None,
+        };
+
+        match version {
+            Some(version) if version.starts_with("3.") => Ok(Self { doc }),
+            Some(version) => Err(format!("unsupported OpenAPI version: {version}").into()),
+            None => Err("not an OpenAPI 3 specification".into()),
+        }
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.doc.pointer("/info/title").and_then(Value::as_str)
+    }
+
+    /// URL of the first server, with its variables set to their default values.
+    pub fn base_url(&self) -> Option<String> {
+        let server = self.doc.pointer("/servers/0")?;
+        let mut url = server.get("url")?.as_str()?.to_string();
+
+        if let Some(Value::Object(variables)) = server.get("variables") {
+            for (name, variable) in variables {
+                if let Some(default) = variable.get("default").and_then(Value::as_str) {
+                    url = url.replace(&format!("{{{name}}}"), default);
+                }
+            }
+        }
+
+        Some(url.trim_end_matches('/').to_string())
+    }
+
+    /// Maps every operation to an endpoint under `root`, whose URL is the `baseUrl` variable.
+    pub fn collection(&self, root: &EndpointHandle, group_by: GroupBy) -> Collection {
+        let mut collection = Collection::default();
+        let mut taken = HashSet::new();
+
+        let base = Endpoint {
+            url: String::from("{{baseUrl}}"),
+            ..Default::default()
+        };
+        collection.endpoints.push((root.clone(), base));
+        taken.insert(root.handle());
+
+        if let Some(url) = self.base_url() {
+            collection.variables.insert(String::from("baseUrl"), url);
+        }
+
+        let Some(Value::Object(paths)) = self.doc.get("paths") else {
+            return collection;
+        };
+
+        for (path, item) in paths {
+            let item = self.resolve(item);
+
+            for method in METHODS {
+                let Some(operation) = item.get(method) else {
+                    continue;
+                };
+
+                let mut handle = root.path.clone();
+                match group_by {
+                    GroupBy::Tags => {
+                        if let Some(tag) = operation.pointer("/tags/0").and_then(Value::as_str) {
+                            handle.push(tag.replace('/', "-"));
+                        }
+
+                        let name = match operation.get("operationId").and_then(Value::as_str) {
+                            Some(id) => id.replace('/', "-"),
+                            None => slug(&format!("{method} {path}")),
+                        };
+                        handle.push(name);
+                    }
+                    GroupBy::Paths => {
+                        handle.extend(path.split('/').filter(|s| !s.is_empty()).map(String::from));
+                        handle.push(method.to_string());
+                    }
+                }
+
+                let mut handle = EndpointHandle::new(handle);
+                let name = handle.head();
+                let mut n = 1;
+                while !taken.insert(handle.handle()) {
+                    n += 1;
+                    *handle.path.last_mut().unwrap() = format!("{name}-{n}");
+                }
+
+                let endpoint = self.endpoint(method, path, item, operation);
+                collection.endpoints.push((handle, endpoint));
+            }
+        }
+
+        collection
+    }
+
+    fn endpoint(&self, method: &str, path: &str, item: &Value, operation: &Value) -> Endpoint {
+        let param = Regex::new(r"\{([^{}/]+)\}").unwrap();
+        let mut endpoint = Endpoint {
+            method: method.to_uppercase(),
+            url: format!("**{}", param.replace_all(path, "{{$1}}")),
+            ..Default::default()
+        };
+
+        for parameter in self.parameters(item, operation) {
+            let Some(name) = parameter.get("name").and_then(Value::as_str) else {
+                continue;
+            };
+
+            let required = parameter.get("required") == Some(&Value::Bool(true));
+            let example = self.parameter_example(parameter);
+            let value = match &example {
+                Some(Value::String(s)) => s.to_owned(),
+                Some(value) => value.to_string(),
+                None => format!("{{{{{name}}}}}"),
+            };
+
+            match parameter.get("in").and_then(Value::as_str) {
+                Some("query") if required || example.is_some() => {
+                    endpoint.query.insert(name.to_string(), value);
+                }
+                Some("header")
+                    if required && !RESERVED_HEADERS.contains(&name.to_lowercase().as_str()) =>
+                {
+                    endpoint.headers.insert(name.to_string(), value);
+                }
+                _ => (),
+            }
+        }
+
+        if let Some(Value::Object(content)) = operation
+            .get("requestBody")
+            .map(|body| self.resolve(body))
+            .and_then(|body| body.get("content"))
+        {
+            let json = content
+                .keys()
+                .find(|media| is_json(media))
+                .or(content.keys().next());
+
+            if let Some(media) = json {
+                endpoint
+                    .headers
+                    .insert(String::from("Content-type"), media.to_owned());
+
+                endpoint.body = match self.media_example(&content[media]) {
+                    Value::Null => None,
+                    Value::String(s) => Some(s),
+                    value if is_json(media) => serde_json::to_string_pretty(&value).ok(),
+                    _ => None,
+                }
+                .map(String::into_bytes);
+            }
+        }
+
+        endpoint
+    }
+
+    /// Path item parameters, overridden by operation parameters of the same name and location.
+    fn parameters<'a>(&'a self, item: &'a Value, operation: &'a Value) -> Vec<&'a Value> {
+        let mut list: Vec<&Value> = Vec::new();
+
+        for source in [item, operation] {
+            let Some(Value::Array(parameters)) = source.get("parameters") else {
+                continue;
+            };
+
+            for parameter in parameters.iter().map(|p| self.resolve(p)) {
+                let key = |p: &Value| (p.get("name").cloned(), p.get("in").cloned());
+
+                list.retain(|p| key(p) != key(parameter));
+                list.push(parameter);
+            }
+        }
+
+        list
+    }
+
+    fn parameter_example(&self, parameter: &Value) -> Option<Value> {
+        if let Some(example) = parameter.get("example") {
+            return Some(example.clone());
+        }
+
+        if let Some(example) = self.first_example(parameter) {
+            return Some(example);
+        }
+
+        let schema = self.resolve(parameter.get("schema")?);
+        schema.get("example").or(schema.get("default")).cloned()
+    }
+
+    /// Example value of a media type object, built from its schema when none is given.
+    fn media_example(&self, media: &Value) -> Value {
+        if let Some(example) = media.get("example") {
+            return example.clone();
+        }
+
+        if let Some(example) = self.first_example(media) {
+            return example;
+        }
+
+        match media.get("schema") {
+            Some(schema) => self.sample(schema, 0),
+            None => Value::Null,
+        }
+    }
+
+    fn first_example(&self, value: &Value) -> Option<Value> {
+        let Some(Value::Object(examples)) = value.get("examples") else {
+            return None;
+        };
+
+        examples
+            .values()
+            .next()
+            .and_then(|example| self.resolve(example).get("value"))
+            .cloned()
+    }
+
+    /// Builds a value matching `schema`, preferring its examples and defaults.
+    fn sample(&self, schema: &Value, depth: usize) -> Value {
+        let schema = self.resolve(schema);
+
+        if depth > MAX_DEPTH {
+            return Value::Null;
+        }
+
+        if let Some(example) = schema.get("example").or(schema.get("default")) {
+            return example.clone();
+        }
+
+        if let Some(Value::Array(values)) = schema.get("enum") {
+            return values.first().cloned().unwrap_or_default();
+        }
+
+        if let Some(Value::Array(all)) = schema.get("allOf") {
+            let mut object = Map::new();
+            for part in all {
+                if let Value::Object(fields) = self.sample(part, depth + 1) {
+                    object.extend(fields);
+                }
+            }
+
+            return Value::Object(object);
+        }
+
+        for key in ["oneOf", "anyOf"] {
+            if let Some(first) = schema.get(key).and_then(|v| v.get(0)) {
+                return self.sample(first, depth + 1);
+            }
+        }
+
+        let kind = match schema.get("type") {
+            Some(Value::String(kind)) => Some(kind.as_str()),
+            // OpenAPI 3.1 allows a list of types, such as ["string", "null"]
+            Some(Value::Array(kinds)) => kinds
+                .iter()
+                .filter_map(Value::as_str)
+                .find(|kind| *kind != "null"),
+            _ => None,
+        };
+
+        match kind {
+            Some("object") | None if schema.get("properties").is_some() => {
+                let mut object = Map::new();
+
+                if let Some(Value::Object(properties)) = schema.get("properties") {
+                    for (name, property) in properties {
+                        object.insert(name.to_owned(), self.sample(property, depth + 1));
+                    }
+                }
+
+                Value::Object(object)
+            }
+            Some("object") => Value::Object(Map::new()),
+            Some("array") => match schema.get("items") {
+                Some(items) => Value::Array(vec![self.sample(items, depth + 1)]),
+                None => Value::Array(Vec::new()),
+            },
+            Some("string") => Value::String(String::from("string")),
+            Some("integer") | Some("number") => Value::from(0),
+            Some("boolean") => Value::Bool(false),
+            _ => Value::Null,
+        }
+    }
+
+    /// Follows `$ref` pointers within the document. Unresolvable references are returned as
+    /// they are.
+    fn resolve<'a>(&'a self, mut value: &'a Value) -> &'a Value {
+        for _ in 0..MAX_DEPTH {
+            let Some(target) = value
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(|r| r.strip_prefix('#'))
+                .and_then(|pointer| self.doc.pointer(pointer))
+            else {
+                break;
+            };
+
+            value = target;
+        }
+
+        value
+    }
+}
+
+impl Spec {
+    /// Describes `endpoints`, whose URLs must already be resolved, as a specification. Response
+    /// schemas are inferred from the latest `history` entry of each handle.
+    ///
+    /// Variables in the server part of URLs become server variables, with their value in
+    /// `variables` as default, while variables in paths become path parameters.
+    pub fn export(
+        title: &str,
+        endpoints: Vec<(EndpointHandle, Endpoint)>,
+        history: &HashMap<String, Entry>,
+        variables: &Variables,
+    ) -> Self {
+        let template = Regex::new(r"\{\{([^{}]+)\}\}").unwrap();
+        let mut servers: Vec<String> = Vec::new();
+        let mut paths = Map::new();
+        let mut ids = HashSet::new();
+
+        for (handle, mut endpoint) in endpoints {
+            let (server, path, mut query) = split_url(&endpoint.url);
+            let path = template.replace_all(&path, "{$1}").to_string();
+            let method = endpoint.method.to_lowercase();
+
+            let Value::Object(item) = paths
+                .entry(path.clone())
+                .or_insert_with(|| Value::Object(Map::new()))
+            else {
+                continue;
+            };
+
+            if item.contains_key(&method) {
+                eprintln!(
+                    "{}: skipping {}, as {} {path} is already described",
+                    "warning".yellow().bold(),
+                    handle.handle(),
+                    endpoint.method,
+                );
+                continue;
+            }
+
+            let mut operation = Map::new();
+            operation.insert("summary".into(), handle.handle().into());
+
+            let mut id = handle.head();
+            let mut n = 1;
+            while !ids.insert(id.clone()) {
+                n += 1;
+                id = format!("{}-{n}", handle.head());
+            }
+            operation.insert("operationId".into(), id.into());
+
+            if let Some((_, parents)) = handle.path.split_last() {
+                if !parents.is_empty() {
+                    operation.insert("tags".into(), json!([parents.join("/")]));
+                }
+            }
+
+            if !servers.contains(&server) {
+                servers.push(server.clone());
+            }
+            if server != servers[0] {
+                operation.insert("servers".into(), json!([server_object(&server, variables)]));
+            }
+
+            let mut parameters = Vec::new();
+            let mut names = HashSet::new();
+            for captures in template.captures_iter(&endpoint.url) {
+                let name = &captures[1];
+
+                if path.contains(&format!("{{{name}}}")) && names.insert(name.to_string()) {
+                    parameters.push(json!({
+                        "name": name,
+                        "in": "path",
+                        "required": true,
+                        "schema": { "type": "string" },
+                    }));
+                }
+            }
+
+            query.extend(endpoint.query.iter().map(|(k, v)| (k.clone(), v.clone())));
+            query.sort();
+            for (name, value) in query {
+                parameters.push(parameter(&name, "query", &value));
+            }
+
+            let mut headers: Vec<(&String, &String)> = endpoint.headers.iter().collect();
+            headers.sort();
+            let mut content_type = None;
+            for (name, value) in headers {
+                if name.eq_ignore_ascii_case("content-type") {
+                    content_type = Some(value.clone());
+                } else {
+                    parameters.push(parameter(name, "header", value));
+                }
+            }
+
+            if !parameters.is_empty() {
+                operation.insert("parameters".into(), Value::Array(parameters));
+            }
+
+            if let Some(body) = endpoint.body() {
+                let body = String::from_utf8_lossy(body);
+                let media = content_type.unwrap_or_else(|| guess_media(&body));
+                operation.insert(
+                    "requestBody".into(),
+                    json!({ "content": { media.clone(): media_object(&media, &body) } }),
+                );
+            }
+
+            operation.insert("responses".into(), responses(history.get(&handle.handle())));
+
+            item.insert(method, Value::Object(operation));
+        }
+
+        let servers: Vec<Value> = servers
+            .first()
+            .map(|server| server_object(server, variables))
+            .into_iter()
+            .collect();
+
+        Self {
+            doc: json!({
+                "openapi": "3.0.3",
+                "info": { "title": title, "version": "1.0.0" },
+                "servers": servers,
+                "paths": paths,
+            }),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.doc).unwrap_or_default()
+    }
+
+    pub fn to_yaml(&self) -> String {
+        serde_yaml::to_string(&self.doc).unwrap_or_default()
+    }
+}
+
+/// Splits `url` into its origin, path and query params. A variable at the start of the URL is
+/// taken as the whole origin, as in "{{baseUrl}}/users".
+fn split_url(url: &str) -> (String, String, Vec<(String, String)>) {
+    let (url, query) = url.split_once('?').unwrap_or((url, ""));
+
+    let start = match (url.find("://"), url.starts_with("{{")) {
+        (Some(i), _) => i + 3,
+        (None, true) => url.find("}}").map(|i| i + 2).unwrap_or_default(),
+        (None, false) => 0,
+    };
+
+    let (server, path) = match url[start..].find('/') {
+        Some(i) => url.split_at(start + i),
+        None => (url, "/"),
+    };
+
+    let server = if server.contains("://") || server.starts_with("{{") {
+        server.to_string()
+    } else {
+        // Requests default to HTTP when the scheme is missing
+        format!("http://{server}")
+    };
+
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (key.to_string(), value.to_string())
+        })
+        .collect();
+
+    (server, path.to_string(), query)
+}
+
+fn server_object(url: &str, variables: &Variables) -> Value {
+    let template = Regex::new(r"\{\{([^{}]+)\}\}").unwrap();
+    let mut defaults = Map::new();
+
+    for captures in template.captures_iter(url) {
+        let name = &captures[1];
+        let default = variables.get(name).map(String::as_str).unwrap_or(name);
+
+        defaults.insert(name.to_string(), json!({ "default": default }));
+    }
+
+    let mut server = json!({ "url": template.replace_all(url, "{$1}") });
+    if !defaults.is_empty() {
+        server["variables"] = Value::Object(defaults);
+    }
+
+    server
+}
+
+/// Parameter whose value is used as example, unless it is a variable.
+fn parameter(name: &str, location: &str, value: &str) -> Value {
+    let mut parameter = json!({
+        "name": name,
+        "in": location,
+        "schema": { "type": "string" },
+    });
+
+    if !value.contains("{{") {
+        parameter["example"] = value.into();
+    }
+
+    parameter
+}
+
+fn responses(entry: Option<&Entry>) -> Value {
+    let Some(status) = entry.and_then(Entry::status) else {
+        return json!({ "default": { "description": "Response" } });
+    };
+    let entry = entry.unwrap();
+
+    let description = StatusCode::from_u16(status)
+        .ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or("Response");
+    let mut response = json!({ "description": description });
+
+    if let Some(body) = entry.response_body().filter(|body| !body.is_empty()) {
+        let media = entry
+            .response_headers()
+            .into_iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case("content-type"))
+            .map(|(_, value)| value.to_string())
+            .unwrap_or_else(|| guess_media(body));
+
+        response["content"] = json!({ media.clone(): media_object(&media, body) });
+    }
+
+    json!({ status.to_string(): response })
+}
+
+fn guess_media(body: &str) -> String {
+    match serde_json::from_str::<Value>(body) {
+        Ok(_) => String::from("application/json"),
+        Err(_) => String::from("text/plain"),
+    }
+}
+
+fn media_object(media: &str, body: &str) -> Value {
+    match serde_json::from_str::<Value>(body) {
+        Ok(value) if is_json(media) => json!({ "schema": schema_of(&value), "example": value }),
+        _ => json!({ "schema": { "type": "string" }, "example": body }),
+    }
+}
+
+/// Infers the schema of a JSON value, describing the items of arrays by their first element.
+fn schema_of(value: &Value) -> Value {
+    match value {
+        Value::Null => json!({ "nullable": true }),
+        Value::Bool(_) => json!({ "type": "boolean" }),
+        Value::Number(n) if n.is_f64() => json!({ "type": "number" }),
+        Value::Number(_) => json!({ "type": "integer" }),
+        Value::String(_) => json!({ "type": "string" }),
+        Value::Array(items) => match items.first() {
+            Some(first) => json!({ "type": "array", "items": schema_of(first) }),
+            None => json!({ "type": "array", "items": {} }),
+        },
+        Value::Object(fields) => {
+            let properties: Map<String, Value> = fields
+                .iter()
+                .map(|(key, value)| (key.clone(), schema_of(value)))
+                .collect();
+
+            json!({ "type": "object", "properties": properties })
+        }
+    }
+}
+
+fn is_json(media: &str) -> bool {
+    let essence = media.split(';').next().unwrap_or_default().trim();
+    essence == "application/json" || essence.ends_with("+json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = r##"
+openapi: 3.0.3
+info:
+  title: Petstore
+servers:
+  - url: https://{region}.example.com/v1/
+    variables:
+      region:
+        default: eu
+paths:
+  /pets:
+    get:
+      operationId: listPets
+      tags: [pets]
+      parameters:
+        - { name: limit, in: query, schema: { type: integer, default: 20 } }
+        - { name: cursor, in: query, schema: { type: string } }
+        - { name: X-Tenant, in: header, required: true, schema: { type: string } }
+    post:
+      tags: [pets]
+      requestBody:
+        content:
+          application/json:
+            schema: { $ref: "#/components/schemas/Pet" }
+  /pets/{petId}:
+    parameters:
+      - $ref: "#/components/parameters/PetId"
+    get:
+      tags: [pets]
+      operationId: showPet
+components:
+  parameters:
+    PetId: { name: petId, in: path, required: true, schema: { type: string } }
+  schemas:
+    Pet:
+      type: object
+      properties:
+        name: { type: string, example: Rex }
+        tags: { type: array, items: { type: string } }
+        owner: { $ref: "#/components/schemas/Owner" }
+    Owner:
+      type: object
+      properties:
+        id: { type: integer }
+"##;
+
+    #[test]
+    fn openapi_collection() {
+        let spec = Spec::parse(SPEC).unwrap();
+        assert_eq!(spec.title(), Some("Petstore"));
+        assert_eq!(spec.base_url().unwrap(), "https://eu.example.com/v1");
+
+        let collection = spec.collection(&EndpointHandle::from("api"), GroupBy::Tags);
+        let handles: Vec<String> = collection
+            .endpoints
+            .iter()
+            .map(|(handle, _)| handle.handle())
+            .collect();
+
+        assert_eq!(
+            handles,
+            [
+                "api",
+                "api/pets/listPets",
+                "api/pets/post-pets",
+                "api/pets/showPet"
+            ]
+        );
+
+        let (_, list) = &collection.endpoints[1];
+        assert_eq!(list.url, "**/pets");
+        assert_eq!(list.query.get("limit").unwrap(), "20");
+        assert!(list.query.get("cursor").is_none());
+        assert_eq!(list.headers.get("X-Tenant").unwrap(), "{{X-Tenant}}");
+
+        let (_, create) = &collection.endpoints[2];
+        let body: Value = serde_json::from_slice(create.body.as_ref().unwrap()).unwrap();
+        assert_eq!(create.method, "POST");
+        assert_eq!(
+            body,
+            serde_json::json!({ "name": "Rex", "tags": ["string"], "owner": { "id": 0 } })
+        );
+
+        let (_, show) = &collection.endpoints[3];
+        assert_eq!(show.url, "**/pets/{{petId}}");
+    }
+
+    #[test]
+    fn openapi_group_by_paths() {
+        let spec = Spec::parse(SPEC).unwrap();
+        let collection = spec.collection(&EndpointHandle::from("api"), GroupBy::Paths);
+        let handles: Vec<String> = collection
+            .endpoints
+            .iter()
+            .map(|(handle, _)| handle.handle())
+            .collect();
+
+        assert_eq!(
+            handles,
+            [
+                "api",
+                "api/pets/get",
+                "api/pets/post",
+                "api/pets/{petId}/get"
+            ]
+        );
+    }
+
+    #[test]
+    fn openapi_split_url() {
+        let (server, path, query) = split_url("{{baseUrl}}/users/{{id}}?page=2&all");
+        assert_eq!(server, "{{baseUrl}}");
+        assert_eq!(path, "/users/{{id}}");
+        assert_eq!(
+            query,
+            [
+                (String::from("page"), String::from("2")),
+                (String::from("all"), String::new())
+            ]
+        );
+
+        let (server, path, _) = split_url("https://{{host}}:8080");
+        assert_eq!(server, "https://{{host}}:8080");
+        assert_eq!(path, "/");
+
+        let (server, path, _) = split_url("localhost:3000/health");
+        assert_eq!(server, "http://localhost:3000");
+        assert_eq!(path, "/health");
+    }
+
+    #[test]
+    fn openapi_parse_malformed() {
+        assert!(Spec::parse("swagger: \"2.0\"").is_err());
+        assert!(Spec::parse("{ \"openapi\": ").is_err());
+        assert!(Spec::parse("openapi: 2.0.0").is_err());
+    }
+}
@@ -0,0 +1,511 @@
+use std::collections::HashSet;
+
+use regex::Regex;
+use serde_json::{json, Value};
+
+use crate::{
+    collection::{slug, Collection},
+    encoding::base64,
+    endpoint::{Endpoint, EndpointHandle},
+    env::Env,
+    QuartzResult,
+};
+
+/// Postman collection export, version 2.1.
+pub struct Document {
+    doc: Value,
+}
+
+impl Document {
+    /// # Errors
+    ///
+    /// This function will return an error if `input` is not a Postman collection.
+    pub fn parse(input: &str) -> QuartzResult<Self> {
+        let doc: Value =
+            serde_json::from_str(input).map_err(|err| format!("invalid JSON: {err}"))?;
+
+        if !doc.get("item").is_some_and(Value::is_array) {
+            return Err("not a Postman collection v2.1".into());
+        }
+
+        Ok(Self { doc })
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.doc.pointer("/info/name").and_then(Value::as_str)
+    }
+
+    /// Maps folders to empty handles and requests to endpoints under `root`. Collection
+    /// variables are kept as variables.
+    pub fn collection(&self, root: &EndpointHandle) -> Collection {
+        let mut collection = Collection::default();
+
+        for variable in list(self.doc.get("variable")) {
+            if let Some((key, value)) = pair(variable) {
+                collection.variables.insert(key, value);
+            }
+        }
+
+        scripts(&self.doc, &root.handle(), &mut collection);
+
+        let mut taken = HashSet::new();
+        items(
+            self.doc.get("item"),
+            root,
+            self.doc.get("auth"),
+            &mut taken,
+            &mut collection,
+        );
+
+        collection
+    }
+}
+
+/// Reads a Postman environment export into an [`Env`] named after it.
+///
+/// # Errors
+///
+/// This function will return an error if `input` is not a Postman environment.
+pub fn environment(input: &str) -> QuartzResult<Env> {
+    let doc: Value = serde_json::from_str(input).map_err(|err| format!("invalid JSON: {err}"))?;
+
+    let (Some(name), Some(Value::Array(values))) =
+        (doc.get("name").and_then(Value::as_str), doc.get("values"))
+    else {
+        return Err("not a Postman environment".into());
+    };
+
+    let mut env = Env::new(&slug(name));
+    for value in values {
+        if let Some((key, value)) = pair(value) {
+            env.variables.insert(key, value);
+        }
+    }
+
+    Ok(env)
+}
+
+fn items(
+    items: Option<&Value>,
+    parent: &EndpointHandle,
+    auth: Option<&Value>,
+    taken: &mut HashSet<String>,
+    collection: &mut Collection,
+) {
+    for item in list(items) {
+        let name = item.get("name").and_then(Value::as_str).map(slug);
+        let name = name
+            .filter(|name| !name.is_empty())
+            .unwrap_or("request".into());
+
+        let mut path = parent.path.clone();
+        path.push(name.clone());
+
+        let mut handle = EndpointHandle::new(path);
+        let mut n = 1;
+        while !taken.insert(handle.handle()) {
+            n += 1;
+            *handle.path.last_mut().unwrap() = format!("{name}-{n}");
+        }
+
+        scripts(item, &handle.handle(), collection);
+
+        if item.get("item").is_some() {
+            let auth = own_auth(item).or(auth);
+            self::items(item.get("item"), &handle, auth, taken, collection);
+        } else if let Some(request) = item.get("request") {
+            let endpoint = self::request(request, auth, &handle.handle(), collection);
+            collection.endpoints.push((handle, endpoint));
+        }
+    }
+}
+
+fn request(
+    request: &Value,
+    auth: Option<&Value>,
+    name: &str,
+    collection: &mut Collection,
+) -> Endpoint {
+    let mut endpoint = Endpoint {
+        method: string(request.get("method")).unwrap_or("GET".into()),
+        ..Default::default()
+    };
+
+    // Requests may be written as a URL only
+    let url = match request {
+        Value::String(_) => Some(request),
+        _ => request.get("url"),
+    };
+    self::url(url, &mut endpoint, collection);
+
+    for header in list(request.get("header")) {
+        if let Some((key, value)) = pair(header) {
+            endpoint.headers.insert(key, value);
+        }
+    }
+
+    if let Some(auth) = own_auth(request).or(auth) {
+        self::auth(auth, &mut endpoint, name, &mut collection.warnings);
+    }
+
+    if let Some(body) = request.get("body") {
+        self::body(body, &mut endpoint, name, &mut collection.warnings);
+    }
+
+    let dynamic = endpoint.url.contains("{{$")
+        || endpoint.headers.values().any(|v| v.contains("{{$"))
+        || endpoint
+            .body
+            .as_ref()
+            .is_some_and(|b| String::from_utf8_lossy(b).contains("{{$"));
+    if dynamic {
+        collection
+            .warnings
+            .push(format!("{name}: dynamic variables are not supported"));
+    }
+
+    endpoint
+}
+
+fn url(url: Option<&Value>, endpoint: &mut Endpoint, collection: &mut Collection) {
+    let raw = match url {
+        Some(Value::String(raw)) => raw.to_owned(),
+        Some(url) => string(url.get("raw")).unwrap_or_default(),
+        None => String::new(),
+    };
+
+    let (base, query) = raw.split_once('?').unwrap_or((&raw, ""));
+
+    // Path variables, such as "/users/:id"
+    let param = Regex::new(r"/:([A-Za-z_][A-Za-z0-9_-]*)").unwrap();
+    endpoint.url = param.replace_all(base, "/{{$1}}").to_string();
+
+    match url.and_then(|url| url.get("query")) {
+        Some(params) => {
+            for param in list(Some(params)) {
+                if let Some((key, value)) = pair(param) {
+                    endpoint.query.insert(key, value);
+                }
+            }
+        }
+        None => {
+            for param in query.split('&').filter(|p| !p.is_empty()) {
+                let (key, value) = param.split_once('=').unwrap_or((param, ""));
+                endpoint.query.insert(key.to_string(), value.to_string());
+            }
+        }
+    }
+
+    for variable in list(url.and_then(|url| url.get("variable"))) {
+        if let Some((key, value)) = pair(variable).filter(|(_, value)| !value.is_empty()) {
+            collection.variables.entry(key).or_insert(value);
+        }
+    }
+}
+
+fn auth(auth: &Value, endpoint: &mut Endpoint, name: &str, warnings: &mut Vec<String>) {
+    let kind = auth.get("type").and_then(Value::as_str).unwrap_or("noauth");
+
+    // Version 2.1 lists attributes as key-value pairs, while 2.0 uses an object
+    let attribute = |key: &str| -> Option<String> {
+        match auth.get(kind)? {
+            Value::Array(attributes) => attributes
+                .iter()
+                .find(|a| a.get("key").and_then(Value::as_str) == Some(key))
+                .and_then(|a| string(a.get("value"))),
+            attributes => string(attributes.get(key)),
+        }
+    };
+
+    match kind {
+        "noauth" => (),
+        "bearer" => {
+            let token = attribute("token").unwrap_or_default();
+            endpoint
+                .headers
+                .insert("Authorization".into(), format!("Bearer {token}"));
+        }
+        "basic" => {
+            let credentials = format!(
+                "{}:{}",
+                attribute("username").unwrap_or_default(),
+                attribute("password").unwrap_or_default()
+            );
+
+            if credentials.contains("{{") {
+                warnings.push(format!(
+                    "{name}: basic authentication with variables is not supported"
+                ));
+            } else {
+                endpoint.headers.insert(
+                    "Authorization".into(),
+                    format!("Basic {}", base64(credentials.as_bytes())),
+                );
+            }
+        }
+        "apikey" => {
+            let key = attribute("key").unwrap_or_default();
+            let value = attribute("value").unwrap_or_default();
+
+            if attribute("in").as_deref() == Some("query") {
+                endpoint.query.insert(key, value);
+            } else {
+                endpoint.headers.insert(key, value);
+            }
+        }
+        kind => warnings.push(format!("{name}: {kind} authentication is not supported")),
+    }
+}
+
+fn body(body: &Value, endpoint: &mut Endpoint, name: &str, warnings: &mut Vec<String>) {
+    if body.get("disabled") == Some(&Value::Bool(true)) {
+        return;
+    }
+
+    let (content, media) = match body.get("mode").and_then(Value::as_str) {
+        Some("raw") => {
+            let media = match body
+                .pointer("/options/raw/language")
+                .and_then(Value::as_str)
+            {
+                Some("json") => "application/json",
+                Some("xml") => "application/xml",
+                Some("html") => "text/html",
+                Some("javascript") => "application/javascript",
+                _ => "text/plain",
+            };
+
+            (string(body.get("raw")), media)
+        }
+        Some("urlencoded") => {
+            let pairs: Vec<String> = list(body.get("urlencoded"))
+                .filter_map(pair)
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect();
+
+            (Some(pairs.join("&")), "application/x-www-form-urlencoded")
+        }
+        Some("graphql") => {
+            let graphql = body.get("graphql").cloned().unwrap_or_default();
+            let variables = string(graphql.get("variables"))
+                .and_then(|v| serde_json::from_str::<Value>(&v).ok())
+                .unwrap_or_default();
+
+            let content = json!({ "query": graphql.get("query"), "variables": variables });
+            (
+                serde_json::to_string_pretty(&content).ok(),
+                "application/json",
+            )
+        }
+        Some(mode) => {
+            warnings.push(format!("{name}: {mode} bodies are not supported"));
+            return;
+        }
+        None => return,
+    };
+
+    let Some(content) = content.filter(|c| !c.is_empty()) else {
+        return;
+    };
+
+    let has_type = endpoint
+        .headers
+        .keys()
+        .any(|key| key.eq_ignore_ascii_case("content-type"));
+    if !has_type {
+        endpoint
+            .headers
+            .insert("Content-type".into(), media.to_string());
+    }
+
+    endpoint.body = Some(content.into_bytes());
+}
+
+/// Authentication set on `item`, unless it inherits from its parent.
+fn own_auth(item: &Value) -> Option<&Value> {
+    item.get("auth")
+        .filter(|auth| auth.get("type").and_then(Value::as_str) != Some("inherit"))
+}
+
+/// Reports scripts of `item`, which are not run by quartz.
+fn scripts(item: &Value, name: &str, collection: &mut Collection) {
+    for event in list(item.get("event")) {
+        let exec = event.pointer("/script/exec");
+        let empty = match exec {
+            Some(Value::Array(lines)) => lines
+                .iter()
+                .all(|line| line.as_str().is_some_and(|l| l.trim().is_empty())),
+            Some(Value::String(line)) => line.trim().is_empty(),
+            _ => true,
+        };
+
+        if empty {
+            continue;
+        }
+
+        let kind = match event.get("listen").and_then(Value::as_str) {
+            Some("prerequest") => "pre-request",
+            _ => "test",
+        };
+
+        collection
+            .warnings
+            .push(format!("{name}: {kind} scripts are not supported"));
+    }
+}
+
+/// Elements of a JSON array, if it is one.
+fn list(value: Option<&Value>) -> impl Iterator<Item = &Value> {
+    value
+        .and_then(Value::as_array)
+        .into_iter()
+        .flat_map(|values| values.iter())
+}
+
+/// Key and value of an entry that is not disabled.
+fn pair(entry: &Value) -> Option<(String, String)> {
+    let disabled = entry.get("disabled") == Some(&Value::Bool(true))
+        || entry.get("enabled") == Some(&Value::Bool(false));
+
+    if disabled {
+        return None;
+    }
+
+    let key = string(entry.get("key"))?;
+    let value = string(entry.get("value")).unwrap_or_default();
+
+    Some((key, value))
+}
+
+fn string(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(s) => Some(s.to_owned()),
+        Value::Null => None,
+        value => Some(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLLECTION: &str = r#"{
+      "info": { "name": "My API", "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json" },
+      "auth": { "type": "bearer", "bearer": [{ "key": "token", "value": "{{token}}" }] },
+      "variable": [{ "key": "baseUrl", "value": "https://example.com" }],
+      "item": [
+        {
+          "name": "Users",
+          "item": [
+            {
+              "name": "Get user",
+              "event": [{ "listen": "test", "script": { "exec": ["pm.test('ok')"] } }],
+              "request": {
+                "method": "GET",
+                "header": [
+                  { "key": "Accept", "value": "application/json" },
+                  { "key": "X-Debug", "value": "1", "disabled": true }
+                ],
+                "url": {
+                  "raw": "{{baseUrl}}/users/:id?verbose=true",
+                  "query": [{ "key": "verbose", "value": "true" }],
+                  "variable": [{ "key": "id", "value": "7" }]
+                }
+              }
+            },
+            {
+              "name": "Create user",
+              "request": {
+                "method": "POST",
+                "auth": { "type": "basic", "basic": [
+                  { "key": "username", "value": "admin" },
+                  { "key": "password", "value": "secret" }
+                ] },
+                "url": "{{baseUrl}}/users",
+                "body": { "mode": "raw", "raw": "{\"name\": \"Rex\"}", "options": { "raw": { "language": "json" } } }
+              }
+            }
+          ]
+        },
+        {
+          "name": "Upload",
+          "request": {
+            "method": "POST",
+            "auth": { "type": "oauth2" },
+            "url": "{{baseUrl}}/upload",
+            "body": { "mode": "formdata", "formdata": [] }
+          }
+        }
+      ]
+    }"#;
+
+    #[test]
+    fn postman_collection() {
+        let document = Document::parse(COLLECTION).unwrap();
+        assert_eq!(document.name(), Some("My API"));
+
+        let collection = document.collection(&EndpointHandle::from("api"));
+        let handles: Vec<String> = collection
+            .endpoints
+            .iter()
+            .map(|(handle, _)| handle.handle())
+            .collect();
+        assert_eq!(
+            handles,
+            ["api/users/get-user", "api/users/create-user", "api/upload"]
+        );
+
+        let (_, get) = &collection.endpoints[0];
+        assert_eq!(get.url, "{{baseUrl}}/users/{{id}}");
+        assert_eq!(get.query.get("verbose").unwrap(), "true");
+        assert_eq!(
+            get.headers.get("Authorization").unwrap(),
+            "Bearer {{token}}"
+        );
+        assert!(get.headers.get("X-Debug").is_none());
+
+        let (_, create) = &collection.endpoints[1];
+        assert_eq!(
+            create.headers.get("Authorization").unwrap(),
+            "Basic YWRtaW46c2VjcmV0"
+        );
+        assert_eq!(
+            create.headers.get("Content-type").unwrap(),
+            "application/json"
+        );
+        assert_eq!(
+            create.body.as_deref(),
+            Some("{\"name\": \"Rex\"}".as_bytes())
+        );
+
+        assert_eq!(
+            collection.variables.get("baseUrl").unwrap(),
+            "https://example.com"
+        );
+        assert_eq!(collection.variables.get("id").unwrap(), "7");
+
+        assert_eq!(
+            collection.warnings,
+            [
+                "api/users/get-user: test scripts are not supported",
+                "api/upload: oauth2 authentication is not supported",
+                "api/upload: formdata bodies are not supported",
+            ]
+        );
+    }
+
+    #[test]
+    fn postman_environment() {
+        let env = environment(
+            r#"{ "name": "Staging Env", "values": [
+                { "key": "token", "value": "abc", "enabled": true },
+                { "key": "old", "value": "x", "enabled": false }
+            ] }"#,
+        )
+        .unwrap();
+
+        assert_eq!(env.name, "staging-env");
+        assert_eq!(env.variables.get("token").unwrap(), "abc");
+        assert!(env.variables.get("old").is_none());
+    }
+}
@@ -0,0 +1,368 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use colored::Colorize;
+use hyper::StatusCode;
+
+use crate::{action::send::Response, assertion::Assertion};
+
+/// Maximum number of characters of the response body included in reports.
+const EXCERPT_LEN: usize = 500;
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Format {
+    /// JUnit XML, as read by most CI services
+    Junit,
+    /// Test Anything Protocol, version 13
+    Tap,
+}
+
+/// Outcome of an assertion.
+#[derive(Debug)]
+pub struct Check {
+    pub assertion: Assertion,
+    pub result: Result<(), String>,
+}
+
+/// Outcome of sending an endpoint and checking its assertions.
+#[derive(Debug)]
+pub struct Case {
+    /// Handle of the sent endpoint.
+    pub name: String,
+
+    /// Iteration data row number, starting at 1, when there is iteration data.
+    pub iteration: Option<usize>,
+
+    pub status: Option<StatusCode>,
+    pub elapsed: Duration,
+    pub checks: Vec<Check>,
+
+    /// Reason there is no response.
+    pub error: Option<String>,
+
+    /// Reason the endpoint was not sent at all.
+    pub skipped: Option<String>,
+
+    /// Beginning of the response body.
+    pub excerpt: String,
+}
+
+impl Case {
+    pub fn new(name: String, iteration: Option<usize>) -> Self {
+        Self {
+            name,
+            iteration,
+            status: None,
+            elapsed: Duration::ZERO,
+            checks: Vec::new(),
+            error: None,
+            skipped: None,
+            excerpt: String::new(),
+        }
+    }
+
+    /// Checks every assertion against `res`.
+    pub fn response(mut self, res: &Response, assertions: &[Assertion]) -> Self {
+        self.status = Some(res.status);
+        self.elapsed = res.elapsed;
+        self.excerpt = String::from_utf8_lossy(&res.body)
+            .chars()
+            .take(EXCERPT_LEN)
+            .collect();
+
+        self.checks = assertions
+            .iter()
+            .map(|assertion| Check {
+                assertion: assertion.clone(),
+                result: assertion.check(res),
+            })
+            .collect();
+
+        self
+    }
+
+    /// Marks this case as failed, as no response was received.
+    pub fn error(mut self, err: impl ToString) -> Self {
+        self.error = Some(err.to_string());
+        self
+    }
+
+    /// Marks this case as skipped, as the endpoint cannot be sent by tests.
+    pub fn skip(mut self, reason: impl ToString) -> Self {
+        self.skipped = Some(reason.to_string());
+        self
+    }
+
+    /// Full name, including the iteration number.
+    pub fn title(&self) -> String {
+        match self.iteration {
+            Some(n) => format!("{} (iteration {n})", self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    pub fn failures(&self) -> usize {
+        self.checks.iter().filter(|c| c.result.is_err()).count()
+    }
+
+    pub fn failed(&self) -> bool {
+        self.error.is_some() || self.failures() > 0
+    }
+
+    /// Prints whether each assertion passed, prefixed by `indent`.
+    pub fn print(&self, indent: &str) {
+        if let Some(reason) = &self.skipped {
+            println!("{indent}{} {reason}", "SKIP".yellow().bold());
+        }
+
+        if let Some(err) = &self.error {
+            println!("{indent}{} {err}", "ERROR".red().bold());
+        }
+
+        for check in &self.checks {
+            match &check.result {
+                Ok(()) => println!("{indent}{} {}", "PASS".green().bold(), check.assertion),
+                Err(reason) => println!(
+                    "{indent}{} {}: {reason}",
+                    "FAIL".red().bold(),
+                    check.assertion
+                ),
+            }
+        }
+    }
+
+    /// Description of every failure in this case.
+    fn messages(&self) -> Vec<String> {
+        let mut messages: Vec<String> = self.error.iter().cloned().collect();
+
+        for check in &self.checks {
+            if let Err(reason) = &check.result {
+                messages.push(format!("{}: {reason}", check.assertion));
+            }
+        }
+
+        messages
+    }
+}
+
+/// Writes `cases` in the given `format`, grouped in a suite called `name`.
+pub fn render(format: Format, name: &str, cases: &[Case]) -> String {
+    match format {
+        Format::Junit => junit(name, cases),
+        Format::Tap => tap(cases),
+    }
+}
+
+/// Writes `cases` as JUnit XML, with one testcase for each case.
+pub fn junit(name: &str, cases: &[Case]) -> String {
+    let errors = cases.iter().filter(|c| c.error.is_some()).count();
+    let failures = cases
+        .iter()
+        .filter(|c| c.error.is_none() && c.failed())
+        .count();
+    let skipped = cases.iter().filter(|c| c.skipped.is_some()).count();
+    let time: f64 = cases.iter().map(|c| c.elapsed.as_secs_f64()).sum();
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+
+    let attributes = format!(
+        "name=\"{}\" tests=\"{}\" failures=\"{failures}\" errors=\"{errors}\" skipped=\"{skipped}\" time=\"{time:.3}\"",
+        xml_escape(name),
+        cases.len(),
+    );
+
+    let _ = writeln!(out, "<testsuites {attributes}>");
+    let _ = writeln!(out, "  <testsuite {attributes}>");
+
+    for case in cases {
+        let _ = write!(
+            out,
+            "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+            xml_escape(&case.title()),
+            xml_escape(name),
+            case.elapsed.as_secs_f64()
+        );
+
+        if let Some(reason) = &case.skipped {
+            let _ = writeln!(
+                out,
+                ">\n      <skipped message=\"{}\"/>\n    </testcase>",
+                xml_escape(reason)
+            );
+            continue;
+        }
+
+        if !case.failed() {
+            out.push_str("/>\n");
+            continue;
+        }
+
+        out.push_str(">\n");
+
+        let messages = case.messages();
+        let (tag, message) = match &case.error {
+            Some(err) => ("error", err.clone()),
+            None if messages.len() == 1 => ("failure", messages[0].clone()),
+            None => ("failure", format!("{} assertions failed", messages.len())),
+        };
+
+        let mut text = messages.join("\n");
+        if let Some(status) = case.status {
+            let _ = write!(text, "\n\nResponse status: {status}");
+        }
+        if !case.excerpt.is_empty() {
+            let _ = write!(text, "\nResponse body:\n{}", case.excerpt);
+        }
+
+        let _ = writeln!(
+            out,
+            "      <{tag} message=\"{}\" type=\"{tag}\">{}</{tag}>",
+            xml_escape(&message),
+            xml_escape(&text)
+        );
+        out.push_str("    </testcase>\n");
+    }
+
+    out.push_str("  </testsuite>\n</testsuites>\n");
+    out
+}
+
+/// Writes `cases` in Test Anything Protocol, with one test point for each case and failures
+/// described in YAML blocks.
+pub fn tap(cases: &[Case]) -> String {
+    let mut out = format!("TAP version 13\n1..{}\n", cases.len());
+
+    for (i, case) in cases.iter().enumerate() {
+        let title = case.title().replace('#', "\\#");
+
+        if let Some(reason) = &case.skipped {
+            let _ = writeln!(out, "ok {} - {title} # SKIP {reason}", i + 1);
+            continue;
+        }
+
+        if !case.failed() {
+            let _ = writeln!(out, "ok {} - {title}", i + 1);
+            continue;
+        }
+
+        let _ = writeln!(out, "not ok {} - {title}", i + 1);
+        out.push_str("  ---\n");
+        out.push_str("  failures:\n");
+        for message in case.messages() {
+            let _ = writeln!(out, "    - {}", yaml_string(&message));
+        }
+        if let Some(status) = case.status {
+            let _ = writeln!(out, "  status: {}", status.as_u16());
+        }
+        if !case.excerpt.is_empty() {
+            let _ = writeln!(out, "  body: {}", yaml_string(&case.excerpt));
+        }
+        out.push_str("  ...\n");
+    }
+
+    out
+}
+
+fn xml_escape(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+
+    for ch in input.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\n' | '\t' | '\r' => out.push(ch),
+            // Other control characters are not allowed in XML 1.0
+            ch if ch.is_control() => (),
+            ch => out.push(ch),
+        }
+    }
+
+    out
+}
+
+/// JSON strings are valid YAML double-quoted scalars.
+fn yaml_string(input: &str) -> String {
+    serde_json::to_string(input).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::{body::Bytes, HeaderMap, Uri};
+
+    fn cases() -> Vec<Case> {
+        let res = Response {
+            status: StatusCode::NOT_FOUND,
+            headers: HeaderMap::new(),
+            body: Bytes::from("<missing & gone>"),
+            url: Uri::from_static("http://localhost/"),
+            elapsed: Duration::from_millis(1500),
+        };
+
+        let assertions = vec![
+            Assertion::Status { status: 200 },
+            Assertion::Body {
+                contains: String::from("gone"),
+            },
+        ];
+
+        vec![
+            Case::new(String::from("api/users"), None).response(&res, &assertions[1..]),
+            Case::new(String::from("api/users/1"), Some(2)).response(&res, &assertions),
+            Case::new(String::from("api/login"), None).error("connection refused"),
+            Case::new(String::from("api/socket"), None).skip("websocket endpoint"),
+        ]
+    }
+
+    #[test]
+    fn report_junit() {
+        let xml = junit("api", &cases());
+
+        assert!(xml.contains(
+            "<testsuite name=\"api\" tests=\"4\" failures=\"1\" errors=\"1\" skipped=\"1\" time=\"3.000\">"
+        ));
+        assert!(xml.contains("<testcase name=\"api/users\" classname=\"api\" time=\"1.500\"/>"));
+        assert!(xml.contains(
+            "<failure message=\"status is 200: got 404\" type=\"failure\">status is 200: got 404\n\nResponse status: 404 Not Found\nResponse body:\n&lt;missing &amp; gone&gt;</failure>"
+        ));
+        assert!(xml.contains("name=\"api/users/1 (iteration 2)\""));
+        assert!(xml.contains(
+            "<error message=\"connection refused\" type=\"error\">connection refused</error>"
+        ));
+        assert!(xml.contains(
+            "<testcase name=\"api/socket\" classname=\"api\" time=\"0.000\">\n      <skipped message=\"websocket endpoint\"/>\n    </testcase>"
+        ));
+    }
+
+    #[test]
+    fn report_tap() {
+        let tap = tap(&cases());
+
+        assert_eq!(
+            tap,
+            [
+                "TAP version 13",
+                "1..4",
+                "ok 1 - api/users",
+                "not ok 2 - api/users/1 (iteration 2)",
+                "  ---",
+                "  failures:",
+                "    - \"status is 200: got 404\"",
+                "  status: 404",
+                "  body: \"<missing & gone>\"",
+                "  ...",
+                "not ok 3 - api/login",
+                "  ---",
+                "  failures:",
+                "    - \"connection refused\"",
+                "  ...",
+                "ok 4 - api/socket # SKIP websocket endpoint",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
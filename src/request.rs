@@ -0,0 +1,256 @@
+use std::path::PathBuf;
+
+use hyper::Body;
+
+use crate::{
+    cookie::CookieJar,
+    endpoint::{Endpoint, EndpointPatch},
+    env::Env,
+    form::Form,
+    Ctx, QuartzResult,
+};
+
+/// Request as quartz sends it, with variables applied and environment headers, cookies and
+/// default headers attached.
+///
+/// Both `send` and code snippets are built from it, so a snippet reproduces the request quartz
+/// would make.
+#[derive(Debug, Clone, Default)]
+pub struct Request {
+    pub method: String,
+
+    /// Full URL, including query params.
+    pub url: String,
+
+    /// Query params sorted by name, which are also part of the URL.
+    pub query: Vec<(String, String)>,
+
+    /// Headers sorted by name, without the cookies from the jar.
+    pub headers: Vec<(String, String)>,
+
+    /// Cookies from the jars matching the URL.
+    pub cookies: Vec<(String, String)>,
+
+    /// Raw body, which is also set for URL-encoded forms.
+    pub body: Option<Vec<u8>>,
+
+    pub form: Option<Form>,
+
+    /// Boundary of a multipart form, also in the Content-Type header.
+    pub boundary: String,
+
+    /// Directory relative file paths of a multipart form are read from, or the current one
+    /// when empty.
+    pub dir: PathBuf,
+}
+
+/// Applies `patch` and the variables of `env` to `endpoint`, and adds the headers quartz sends
+/// by default.
+pub fn prepare(endpoint: &mut Endpoint, patch: &EndpointPatch, env: &Env) {
+    endpoint.update(&mut patch.clone());
+    endpoint.apply_env(env);
+
+    let has_user_agent = endpoint
+        .headers
+        .keys()
+        .chain(env.headers.keys())
+        .any(|key| key.eq_ignore_ascii_case("user-agent"));
+
+    if !has_user_agent {
+        endpoint
+            .headers
+            .insert(String::from("user-agent"), Ctx::user_agent());
+    }
+}
+
+impl Request {
+    /// Resolves `endpoint`, which should already be [`prepare`]d, adding the environment
+    /// headers it does not override and the cookies of `jars` matching its URL.
+    pub fn new(endpoint: &mut Endpoint, env: &Env, jars: &[&CookieJar]) -> QuartzResult<Self> {
+        let url = endpoint.full_url()?;
+
+        let mut headers: Vec<(String, String)> = endpoint
+            .headers
+            .iter()
+            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+            .collect();
+
+        for (key, value) in env.headers.iter() {
+            if !endpoint.headers.keys().any(|k| k.eq_ignore_ascii_case(key)) {
+                headers.push((key.to_owned(), value.to_owned()));
+            }
+        }
+
+        let form = endpoint.form.clone();
+        let mut boundary = String::new();
+        if let Some(form) = &form {
+            if form.is_multipart() {
+                boundary = Form::boundary();
+            }
+
+            headers.retain(|(key, _)| !key.eq_ignore_ascii_case("content-type"));
+            headers.push((String::from("Content-Type"), form.content_type(&boundary)));
+        }
+
+        headers.sort();
+
+        let cookies = jars
+            .iter()
+            .flat_map(|jar| jar.select(&url))
+            .map(|c| (c.name().to_owned(), c.value().to_owned()))
+            .collect();
+
+        let mut query: Vec<(String, String)> = endpoint
+            .query
+            .iter()
+            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+            .collect();
+        query.sort();
+
+        Ok(Self {
+            method: endpoint.method.to_uppercase(),
+            url: url.to_string(),
+            query,
+            headers,
+            cookies,
+            body: match &form {
+                Some(Form::Urlencoded(_)) => {
+                    form.as_ref().map(|form| form.urlencoded().into_bytes())
+                }
+                Some(Form::Multipart(_)) => None,
+                None => endpoint.body().cloned(),
+            },
+            form,
+            boundary,
+            dir: PathBuf::new(),
+        })
+    }
+
+    /// Headers to send, with cookies from the jars added to the Cookie header.
+    pub fn all_headers(&self) -> Vec<(String, String)> {
+        let mut headers = self.headers.clone();
+
+        if self.cookies.is_empty() {
+            return headers;
+        }
+
+        let cookies = self
+            .cookies
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<String>>()
+            .join("; ");
+
+        match headers
+            .iter_mut()
+            .find(|(key, _)| key.eq_ignore_ascii_case("cookie"))
+        {
+            Some((_, value)) => {
+                value.push_str("; ");
+                value.push_str(&cookies);
+            }
+            None => {
+                headers.push((String::from("Cookie"), cookies));
+                headers.sort();
+            }
+        }
+
+        headers
+    }
+
+    pub fn has_header(&self, name: &str) -> bool {
+        self.all_headers()
+            .iter()
+            .any(|(key, _)| key.eq_ignore_ascii_case(name))
+    }
+
+    /// Multipart form whose body is streamed when sending, if any.
+    pub fn multipart(&self) -> Option<&Form> {
+        self.form.as_ref().filter(|form| form.is_multipart())
+    }
+
+    /// Body as text, with invalid UTF-8 sequences replaced, for code that cannot hold bytes.
+    pub fn body_text(&self) -> Option<String> {
+        self.body
+            .as_ref()
+            .map(|body| String::from_utf8_lossy(body).to_string())
+    }
+
+    /// Body as recorded in history, with multipart files written as "< path" lines.
+    pub fn body_message(&self) -> Option<Vec<u8>> {
+        match self.multipart() {
+            Some(form) => Some(form.preview(&self.boundary).into_bytes()),
+            None => self.body.clone(),
+        }
+    }
+
+    /// Builds the request to send, streaming multipart files from disk.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request is malformed or a multipart file
+    /// does not exist.
+    pub fn to_hyper(&self) -> QuartzResult<hyper::Request<Body>> {
+        let mut builder = hyper::Request::builder().uri(&self.url);
+
+        if let Ok(method) = hyper::Method::from_bytes(self.method.as_bytes()) {
+            builder = builder.method(method);
+        }
+
+        for (key, value) in self.all_headers() {
+            builder = builder.header(key, value);
+        }
+
+        let body = match (self.multipart(), &self.body) {
+            (Some(form), _) => {
+                let (body, length) = form.body(&self.boundary, &self.dir)?;
+                builder = builder.header("Content-Length", length);
+                body
+            }
+            (None, Some(body)) => body.to_owned().into(),
+            (None, None) => Body::empty(),
+        };
+
+        Ok(builder.body(body).map_err(|_| "malformed request")?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_headers() {
+        let mut env = Env::default();
+        env.headers
+            .insert(String::from("Authorization"), String::from("env"));
+        env.headers.insert(String::from("X-Env"), String::from("1"));
+
+        let mut endpoint = Endpoint {
+            url: String::from("https://example.com"),
+            ..Default::default()
+        };
+        endpoint
+            .headers
+            .insert(String::from("authorization"), String::from("endpoint"));
+        endpoint
+            .headers
+            .insert(String::from("Cookie"), String::from("a=1"));
+
+        prepare(&mut endpoint, &EndpointPatch::default(), &env);
+
+        let mut jar = CookieJar::default();
+        jar.set("https://example.com", "b=2").unwrap();
+
+        let request = Request::new(&mut endpoint, &env, &[&jar]).unwrap();
+        assert_eq!(
+            request.all_headers(),
+            [
+                (String::from("Cookie"), String::from("a=1; b=2")),
+                (String::from("X-Env"), String::from("1")),
+                (String::from("authorization"), String::from("endpoint")),
+                (String::from("user-agent"), Ctx::user_agent()),
+            ]
+        );
+    }
+}
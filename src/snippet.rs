@@ -1,13 +1,27 @@
+use std::fmt::Write;
 use std::ops::Deref;
+use std::path::PathBuf;
+use std::str::FromStr;
 
-use crate::{Endpoint, QuartzResult};
-use hyper::{Body, Request, Response};
+use crate::{
+    form::{Form, Part},
+    request::Request,
+    template, Ctx, QuartzResult,
+};
+use hyper::{Body, Response, Uri};
+use serde_json::{json, Value};
+
+/// Writes code that sends a request with some language or tool.
+pub trait Snippet {
+    fn render(&self, request: &Request) -> String;
+}
 
 enum CurlOption {
     Location,
     Request,
     Header,
     Data,
+    Form,
 }
 
 #[derive(clap::Args, Debug)]
@@ -21,48 +35,94 @@ pub struct Curl {
     multiline: bool,
 }
 
-impl Curl {
-    pub fn print(&self, endpoint: &mut Endpoint) -> QuartzResult {
+impl Snippet for Curl {
+    fn render(&self, request: &Request) -> String {
         let separator = if self.multiline { " \\\n\t" } else { " " };
 
-        print!(
-            "curl {} '{}'",
+        let mut out = format!(
+            "curl {} {} {} {}",
             self.option_string(CurlOption::Location),
-            endpoint.full_url().unwrap()
-        );
-        print!(
-            " {} {}",
+            shell_quote(&request.url),
             self.option_string(CurlOption::Request),
-            endpoint.method
+            request.method
         );
 
-        for (key, value) in endpoint.headers.iter() {
-            print!(
-                "{}{} '{}: {}'",
+        let mut headers: Vec<String> = headers(request)
+            .into_iter()
+            .map(|(key, value)| match value.is_empty() {
+                // "Key:" would remove the header instead
+                true => format!("{key};"),
+                false => format!("{key}: {value}"),
+            })
+            .collect();
+
+        // cURL would otherwise send its own content-type along with the body
+        if request.body.is_some() && !request.has_header("content-type") {
+            headers.push(String::from("Content-Type:"));
+        }
+
+        for header in headers {
+            let _ = write!(
+                out,
+                "{}{} {}",
                 separator,
                 self.option_string(CurlOption::Header),
-                key,
-                value
+                shell_quote(&header)
             );
         }
 
-        if let Some(body) = endpoint.body() {
-            let mut body = body.to_owned();
-            print!("{}{} '", separator, self.option_string(CurlOption::Data));
-
-            if body.ends_with('\n') {
-                body.truncate(body.len() - 1);
+        if let Some(body) = &request.body {
+            match std::str::from_utf8(body) {
+                Ok(body) => {
+                    let _ = write!(
+                        out,
+                        "{}{} {}",
+                        separator,
+                        self.option_string(CurlOption::Data),
+                        shell_quote(body)
+                    );
+                }
+                // Shell words cannot hold every byte, so binary bodies are written to a file
+                Err(_) => {
+                    out.insert_str(0, &format!("{} > body\n", printf(body)));
+                    let _ = write!(out, "{separator}--data-binary @body");
+                }
             }
+        }
 
-            print!("{body}");
-            println!("'");
-        } else {
-            println!();
+        for field in request.multipart().map(Form::fields).unwrap_or_default() {
+            let (option, value) = match &field.file {
+                Some(file) => {
+                    let mut value = format!("{}=@{file}", field.name);
+                    if let Some(content_type) = field.content_type() {
+                        let _ = write!(value, ";type={content_type}");
+                    }
+                    if let Some(filename) = &field.filename {
+                        let _ = write!(value, ";filename={filename}");
+                    }
+
+                    (self.option_string(CurlOption::Form), value)
+                }
+                // cURL would read "@" and "<" values from files and ";" starts attributes
+                None if field.value.starts_with(['@', '<']) || field.value.contains(';') => (
+                    String::from("--form-string"),
+                    format!("{}={}", field.name, field.value),
+                ),
+                None => (
+                    self.option_string(CurlOption::Form),
+                    format!("{}={}", field.name, field.value),
+                ),
+            };
+
+            let _ = write!(out, "{separator}{option} {}", shell_quote(&value));
         }
 
-        Ok(())
+        out.push('\n');
+        out
     }
+}
 
+impl Curl {
     fn option_string(&self, option: CurlOption) -> String {
         let result = match option {
             CurlOption::Location => {
@@ -86,11 +146,13 @@ impl Curl {
                     "-H"
                 }
             }
-            CurlOption::Data => {
+            // Unlike "--data", it sends bodies starting with "@" as they are
+            CurlOption::Data => "--data-raw",
+            CurlOption::Form => {
                 if self.long {
-                    "--data"
+                    "--form"
                 } else {
-                    "-d"
+                    "-F"
                 }
             }
         };
@@ -99,6 +161,523 @@ impl Curl {
     }
 }
 
+/// Raw HTTP/1.1 request message.
+pub struct HttpMessage;
+
+impl Snippet for HttpMessage {
+    fn render(&self, request: &Request) -> String {
+        let url = Uri::from_str(&request.url).unwrap_or_default();
+        let path = url.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+
+        let mut out = format!("{} {path} HTTP/1.1\n", request.method);
+        let _ = writeln!(
+            out,
+            "Host: {}",
+            url.authority().map(|a| a.as_str()).unwrap_or_default()
+        );
+        for (key, value) in &request.all_headers() {
+            let _ = writeln!(out, "{key}: {value}");
+        }
+
+        if let Some(body) = request.body_message() {
+            let _ = write!(out, "\n{}", String::from_utf8_lossy(&body));
+        }
+
+        out
+    }
+}
+
+/// JavaScript, with the Fetch API.
+pub struct Fetch;
+
+impl Snippet for Fetch {
+    fn render(&self, request: &Request) -> String {
+        let mut out = String::new();
+
+        if let Some(form) = request.multipart() {
+            if form.fields().iter().any(|field| field.file.is_some()) {
+                out.push_str("import { openAsBlob } from \"node:fs\";\n\n");
+            }
+
+            out.push_str("const form = new FormData();\n");
+            for field in form.fields() {
+                let _ = match &field.file {
+                    Some(file) => writeln!(
+                        out,
+                        "form.append({}, await openAsBlob({}, {{ type: {} }}), {});",
+                        quote(&field.name),
+                        quote(file),
+                        quote(&field.content_type().unwrap_or_default()),
+                        quote(&field.filename().unwrap_or_default())
+                    ),
+                    None => writeln!(
+                        out,
+                        "form.append({}, {});",
+                        quote(&field.name),
+                        quote(&field.value)
+                    ),
+                };
+            }
+            out.push('\n');
+        }
+
+        let _ = writeln!(
+            out,
+            "const response = await fetch({}, {{",
+            quote(&request.url)
+        );
+        let _ = writeln!(out, "  method: {},", quote(&request.method));
+
+        let headers = headers(request);
+        if !headers.is_empty() {
+            out.push_str("  headers: {\n");
+            for (key, value) in &headers {
+                let _ = writeln!(out, "    {}: {},", quote(key), quote(value));
+            }
+            out.push_str("  },\n");
+        }
+
+        if let Some(body) = request.body_text().as_deref() {
+            let _ = writeln!(out, "  body: {},", quote(body));
+        } else if request.multipart().is_some() {
+            out.push_str("  body: form,\n");
+        }
+
+        out.push_str("});\n\nconsole.log(await response.text());\n");
+        out
+    }
+}
+
+/// Python, with the requests package.
+pub struct Python;
+
+impl Snippet for Python {
+    fn render(&self, request: &Request) -> String {
+        let mut out = String::from("import requests\n\nresponse = requests.request(\n");
+        let _ = writeln!(out, "    {},", quote(&request.method));
+        let _ = writeln!(out, "    {},", quote(&request.url));
+
+        let headers = headers(request);
+        if !headers.is_empty() {
+            out.push_str("    headers={\n");
+            for (key, value) in &headers {
+                let _ = writeln!(out, "        {}: {},", quote(key), quote(value));
+            }
+            out.push_str("    },\n");
+        }
+
+        if let Some(body) = request.body_text().as_deref() {
+            let _ = writeln!(out, "    data={},", quote(body));
+        }
+
+        if let Some(form) = request.multipart() {
+            out.push_str("    files=[\n");
+            for field in form.fields() {
+                let _ = match &field.file {
+                    Some(file) => writeln!(
+                        out,
+                        "        ({}, ({}, open({}, \"rb\"), {})),",
+                        quote(&field.name),
+                        quote(&field.filename().unwrap_or_default()),
+                        quote(file),
+                        quote(&field.content_type().unwrap_or_default())
+                    ),
+                    None => writeln!(
+                        out,
+                        "        ({}, (None, {})),",
+                        quote(&field.name),
+                        quote(&field.value)
+                    ),
+                };
+            }
+            out.push_str("    ],\n");
+        }
+
+        out.push_str(")\n\nprint(response.text)\n");
+        out
+    }
+}
+
+/// Go, with the net/http package.
+pub struct Go;
+
+impl Snippet for Go {
+    fn render(&self, request: &Request) -> String {
+        let body = request.body_text();
+        let body = body.as_deref();
+        let form = request.multipart();
+        let has_files = form.is_some_and(|form| form.fields().iter().any(|f| f.file.is_some()));
+
+        let mut imports = vec!["fmt", "io", "net/http"];
+        if body.is_some() {
+            imports.push("strings");
+        }
+        if form.is_some() {
+            imports.extend(["bytes", "mime/multipart"]);
+        }
+        if has_files {
+            imports.extend(["net/textproto", "os"]);
+        }
+        imports.sort();
+
+        let mut out = String::from("package main\n\nimport (\n");
+        for import in imports {
+            let _ = writeln!(out, "\t{}", quote(import));
+        }
+        out.push_str(")\n\nfunc main() {\n");
+
+        let reader = match (body, form) {
+            (Some(body), _) => {
+                let _ = writeln!(out, "\tbody := strings.NewReader({})", quote(body));
+                "body"
+            }
+            (None, Some(form)) => {
+                out.push_str("\tbody := &bytes.Buffer{}\n\tform := multipart.NewWriter(body)\n");
+                for field in form.fields() {
+                    let Some(file) = &field.file else {
+                        let _ = writeln!(
+                            out,
+                            "\tform.WriteField({}, {})",
+                            quote(&field.name),
+                            quote(&field.value)
+                        );
+                        continue;
+                    };
+
+                    let disposition = format!(
+                        "form-data; name=\"{}\"; filename=\"{}\"",
+                        crate::form::escape(&field.name),
+                        crate::form::escape(&field.filename().unwrap_or_default())
+                    );
+
+                    out.push_str("\t{\n\t\theader := make(textproto.MIMEHeader)\n");
+                    let _ = writeln!(
+                        out,
+                        "\t\theader.Set(\"Content-Disposition\", {})",
+                        quote(&disposition)
+                    );
+                    let _ = writeln!(
+                        out,
+                        "\t\theader.Set(\"Content-Type\", {})",
+                        quote(&field.content_type().unwrap_or_default())
+                    );
+                    out.push_str("\t\tpart, err := form.CreatePart(header)\n");
+                    out.push_str("\t\tif err != nil {\n\t\t\tpanic(err)\n\t\t}\n");
+                    let _ = writeln!(out, "\t\tfile, err := os.Open({})", quote(file));
+                    out.push_str("\t\tif err != nil {\n\t\t\tpanic(err)\n\t\t}\n");
+                    out.push_str("\t\tio.Copy(part, file)\n\t\tfile.Close()\n\t}\n");
+                }
+                out.push_str("\tform.Close()\n\n");
+                "body"
+            }
+            (None, None) => "nil",
+        };
+
+        let _ = writeln!(
+            out,
+            "\treq, err := http.NewRequest({}, {}, {reader})",
+            quote(&request.method),
+            quote(&request.url)
+        );
+        out.push_str("\tif err != nil {\n\t\tpanic(err)\n\t}\n");
+
+        for (key, value) in &headers(request) {
+            let _ = writeln!(out, "\treq.Header.Set({}, {})", quote(key), quote(value));
+        }
+        if form.is_some() {
+            out.push_str("\treq.Header.Set(\"Content-Type\", form.FormDataContentType())\n");
+        }
+
+        out.push_str(concat!(
+            "\n",
+            "\tres, err := http.DefaultClient.Do(req)\n",
+            "\tif err != nil {\n\t\tpanic(err)\n\t}\n",
+            "\tdefer res.Body.Close()\n\n",
+            "\tdata, err := io.ReadAll(res.Body)\n",
+            "\tif err != nil {\n\t\tpanic(err)\n\t}\n",
+            "\tfmt.Println(string(data))\n",
+            "}\n",
+        ));
+        out
+    }
+}
+
+/// Rust, with the blocking client of the reqwest crate.
+pub struct Reqwest;
+
+impl Snippet for Reqwest {
+    fn render(&self, request: &Request) -> String {
+        const METHODS: [&str; 9] = [
+            "GET", "POST", "PUT", "DELETE", "HEAD", "OPTIONS", "CONNECT", "PATCH", "TRACE",
+        ];
+
+        let method = if METHODS.contains(&request.method.as_str()) {
+            format!("reqwest::Method::{}", request.method)
+        } else {
+            format!("reqwest::Method::from_bytes(b{:?})?", request.method)
+        };
+
+        let mut out = String::from(
+            "fn main() -> Result<(), Box<dyn std::error::Error>> {\n    let client = reqwest::blocking::Client::new();\n",
+        );
+
+        if let Some(form) = request.multipart() {
+            out.push_str("    let form = reqwest::blocking::multipart::Form::new()");
+            for field in form.fields() {
+                let _ = match &field.file {
+                    Some(file) => write!(
+                        out,
+                        "\n        .part(\n            {:?},\n            reqwest::blocking::multipart::Part::file({file:?})?\n                .file_name({:?})\n                .mime_str({:?})?,\n        )",
+                        field.name,
+                        field.filename().unwrap_or_default(),
+                        field.content_type().unwrap_or_default()
+                    ),
+                    None => write!(out, "\n        .text({:?}, {:?})", field.name, field.value),
+                };
+            }
+            out.push_str(";\n");
+        }
+
+        out.push_str("    let response = client\n");
+        let _ = writeln!(out, "        .request({method}, {:?})", request.url);
+
+        for (key, value) in &headers(request) {
+            let _ = writeln!(out, "        .header({key:?}, {value:?})");
+        }
+
+        if let Some(body) = request.body_text().as_deref() {
+            let _ = writeln!(out, "        .body({body:?})");
+        } else if request.multipart().is_some() {
+            out.push_str("        .multipart(form)\n");
+        }
+
+        out.push_str(
+            "        .send()?;\n\n    println!(\"{}\", response.text()?);\n    Ok(())\n}\n",
+        );
+        out
+    }
+}
+
+/// HTTPie command line.
+pub struct Httpie;
+
+impl Snippet for Httpie {
+    fn render(&self, request: &Request) -> String {
+        let mut out = String::from("http");
+
+        if let Some(body) = request.body_text().as_deref() {
+            let _ = write!(out, " --raw {}", shell_quote(body));
+        } else if request.multipart().is_some() {
+            out.push_str(" --multipart");
+        }
+
+        let _ = write!(out, " {} {}", request.method, shell_quote(&request.url));
+
+        for (key, value) in &headers(request) {
+            // Headers with empty values are written as "Key;", as "Key:" would unset them
+            let item = match value.is_empty() {
+                true => format!("{key};"),
+                false => format!("{key}:{value}"),
+            };
+            let _ = write!(out, " {}", shell_quote(&item));
+        }
+
+        for field in request.multipart().map(Form::fields).unwrap_or_default() {
+            let item = match &field.file {
+                Some(file) => format!(
+                    "{}@{file};type={}",
+                    field.name,
+                    field.content_type().unwrap_or_default()
+                ),
+                None => format!("{}={}", field.name, field.value),
+            };
+            let _ = write!(out, " {}", shell_quote(&item));
+        }
+
+        out.push('\n');
+        out
+    }
+}
+
+/// GNU Wget command line, writing the response to standard output.
+pub struct Wget;
+
+impl Snippet for Wget {
+    fn render(&self, request: &Request) -> String {
+        let mut out = format!(
+            "wget --quiet --output-document - --method {}",
+            request.method
+        );
+
+        for (key, value) in &request.all_headers() {
+            let _ = write!(out, " --header {}", shell_quote(&format!("{key}: {value}")));
+        }
+
+        if let Some(form) = request.multipart() {
+            // Wget cannot build multipart bodies, so the command writes one to a file beforehand
+            let parts: Vec<String> = form
+                .parts(&request.boundary)
+                .into_iter()
+                .map(|part| match part {
+                    Part::Data(data) => printf(data.as_bytes()),
+                    Part::File(file) => format!("cat {}", shell_quote(&file)),
+                })
+                .collect();
+
+            out.insert_str(
+                0,
+                &format!("{{ {}; }} > body.multipart\n", parts.join("; ")),
+            );
+            out.push_str(" --body-file body.multipart");
+        } else if let Some(body) = &request.body {
+            match std::str::from_utf8(body) {
+                Ok(body) => {
+                    let _ = write!(out, " --body-data {}", shell_quote(body));
+                }
+                Err(_) => {
+                    out.insert_str(0, &format!("{} > body\n", printf(body)));
+                    out.push_str(" --body-file body");
+                }
+            }
+        }
+
+        let _ = writeln!(out, " {}", shell_quote(&request.url));
+        out
+    }
+}
+
+/// Headers for snippets that build multipart forms themselves, setting their own boundary.
+fn headers(request: &Request) -> Vec<(String, String)> {
+    let mut headers = request.all_headers();
+    if request.multipart().is_some() {
+        headers.retain(|(key, _)| !key.eq_ignore_ascii_case("content-type"));
+    }
+
+    headers
+}
+
+/// User-defined snippet, read from a template file.
+pub struct Template(template::Template);
+
+impl Template {
+    /// File extension of snippet templates.
+    pub const EXTENSION: &'static str = "tmpl";
+
+    /// Reads the template named `name` from the project snippets directory, or the user one.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no such template or it is malformed.
+    pub fn find(ctx: &Ctx, name: &str) -> QuartzResult<Self> {
+        let filename = format!("{name}.{}", Self::EXTENSION);
+        let path = Self::dirs(ctx)
+            .into_iter()
+            .map(|dir| dir.join(&filename))
+            .find(|path| path.exists())
+            .ok_or_else(|| format!("no such snippet: {name}"))?;
+
+        let content = std::fs::read_to_string(&path)?;
+        let template = template::Template::parse(&content)
+            .map_err(|err| format!("{}: {err}", path.display()))?;
+
+        Ok(Self(template))
+    }
+
+    /// Directories searched for templates, in order: `.quartz/snippets` and the `snippets`
+    /// directory of the user configuration, `~/.config/quartz` by default.
+    pub fn dirs(ctx: &Ctx) -> Vec<PathBuf> {
+        let mut dirs = vec![ctx.path().join("snippets")];
+
+        let config = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+        if let Some(config) = config {
+            dirs.push(config.join("quartz").join("snippets"));
+        }
+
+        dirs
+    }
+}
+
+impl Snippet for Template {
+    fn render(&self, request: &Request) -> String {
+        let pairs = |list: &[(String, String)]| -> Vec<Value> {
+            list.iter()
+                .map(|(name, value)| json!({ "name": name, "value": value }))
+                .collect()
+        };
+
+        let fields = |form: &Form| -> Vec<Value> {
+            form.fields()
+                .iter()
+                .map(|field| {
+                    json!({
+                        "name": field.name,
+                        "value": field.value,
+                        "file": field.file,
+                        "type": field.content_type(),
+                        "filename": field.filename(),
+                    })
+                })
+                .collect()
+        };
+
+        let cookie = request
+            .all_headers()
+            .into_iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case("cookie"))
+            .map(|(_, value)| value);
+
+        let context = json!({
+            "method": request.method,
+            "url": request.url,
+            "base_url": request.url.split_once('?').map(|(base, _)| base).unwrap_or(&request.url),
+            "query": pairs(&request.query),
+            "headers": pairs(&request.headers),
+            "cookies": pairs(&request.cookies),
+            "cookie": cookie,
+            "body": request.body_text(),
+            "multipart": request.multipart().map(fields),
+            "urlencoded": request.form.as_ref().filter(|form| !form.is_multipart()).map(fields),
+        });
+
+        self.0.render(&context)
+    }
+}
+
+/// Double-quoted string literal, whose escapes are valid in JavaScript, Python and Go.
+fn quote(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+/// Single-quoted shell word, where single quotes are closed, escaped and reopened.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Shell command printing `data` byte for byte, with line breaks and bytes outside of printable
+/// ASCII written as escapes.
+fn printf(data: &[u8]) -> String {
+    let mut format = String::new();
+
+    for (i, byte) in data.iter().enumerate() {
+        match byte {
+            b'\\' => format.push_str("\\\\"),
+            b'%' => format.push_str("%%"),
+            b'\r' => format.push_str("\\r"),
+            b'\n' => format.push_str("\\n"),
+            // A leading dash would be read as an option
+            b'-' if i == 0 => format.push_str("\\055"),
+            b' '..=b'~' => format.push(char::from(*byte)),
+            _ => {
+                let _ = write!(format, "\\{byte:03o}");
+            }
+        }
+    }
+
+    format!("printf {}", shell_quote(&format))
+}
+
 pub struct Http(String);
 
 impl Deref for Http {
@@ -131,8 +710,8 @@ impl From<&Response<Body>> for Http {
     }
 }
 
-impl From<&Request<Body>> for Http {
-    fn from(value: &Request<Body>) -> Self {
+impl From<&hyper::Request<Body>> for Http {
+    fn from(value: &hyper::Request<Body>) -> Self {
         let mut output = String::new();
 
         output.push_str(&format!(
@@ -157,20 +736,111 @@ impl From<&Request<Body>> for Http {
     }
 }
 
-impl Http {
-    pub fn print(endpoint: &mut Endpoint) -> QuartzResult {
-        let url = endpoint.full_url()?;
-        let path = url.path_and_query().unwrap();
-
-        println!("{} {} HTTP/1.1", endpoint.method, path.as_str());
-        println!("Host: {}", url.host().unwrap());
-        print!("{}", endpoint.headers);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::form::Field;
 
-        if let Some(body) = endpoint.body() {
-            println!();
-            print!("{body}");
+    fn request() -> Request {
+        Request {
+            method: String::from("PURGE"),
+            url: String::from("https://example.com/cache?key=a"),
+            headers: vec![(String::from("X-Empty"), String::new())],
+            body: Some(b"it's \"quoted\"".to_vec()),
+            ..Default::default()
         }
+    }
+
+    #[test]
+    fn snippet_quoting() {
+        let request = request();
+
+        assert_eq!(
+            Httpie.render(&request),
+            "http --raw 'it'\\''s \"quoted\"' PURGE 'https://example.com/cache?key=a' 'X-Empty;'\n"
+        );
+        assert!(Fetch
+            .render(&request)
+            .contains("  body: \"it's \\\"quoted\\\"\",\n"));
+        assert!(Reqwest
+            .render(&request)
+            .contains(".request(reqwest::Method::from_bytes(b\"PURGE\")?, "));
+    }
+
+    #[test]
+    fn snippet_curl_quoting() {
+        let curl = Curl {
+            long: true,
+            multiline: false,
+        };
+
+        assert_eq!(
+            curl.render(&request()),
+            concat!(
+                "curl --location 'https://example.com/cache?key=a' --request PURGE ",
+                "--header 'X-Empty;' --header 'Content-Type:' ",
+                "--data-raw 'it'\\''s \"quoted\"'\n",
+            )
+        );
+    }
+
+    #[test]
+    fn snippet_binary_body() {
+        let request = Request {
+            body: Some(vec![0x89, b'P', b'\n', 0xff]),
+            ..request()
+        };
+        let curl = Curl {
+            long: false,
+            multiline: false,
+        };
+
+        let curl = curl.render(&request);
+        assert!(
+            curl.starts_with("printf '\\211P\\n\\377' > body\ncurl "),
+            "{curl}"
+        );
+        assert!(curl.ends_with(" --data-binary @body\n"), "{curl}");
+
+        let wget = Wget.render(&request);
+        assert!(
+            wget.starts_with("printf '\\211P\\n\\377' > body\nwget "),
+            "{wget}"
+        );
+        assert!(wget.contains(" --body-file body "), "{wget}");
+        assert!(!wget.contains("--body-data"), "{wget}");
+    }
+
+    #[test]
+    fn snippet_wget_multipart() {
+        let request = Request {
+            body: None,
+            form: Some(Form::Multipart(vec![
+                Field::parse("photo=@rex.png").unwrap()
+            ])),
+            boundary: String::from("quartz"),
+            ..request()
+        };
+
+        let wget = Wget.render(&request);
+        assert!(wget.starts_with(concat!(
+            r#"{ printf '\055-quartz\r\nContent-Disposition: form-data; name="photo"; filename="rex.png"\r\n"#,
+            r#"Content-Type: image/png\r\n\r\n'; cat 'rex.png'; printf '\r\n--quartz--\r\n'; } > body.multipart"#,
+            "\nwget ",
+        )));
+        assert!(wget.contains(" --body-file body.multipart "), "{wget}");
+        assert!(!wget.contains("--body-data"), "{wget}");
+    }
+
+    #[test]
+    fn snippet_go_without_body() {
+        let request = Request {
+            body: None,
+            ..request()
+        };
 
-        Ok(())
+        let go = Go.render(&request);
+        assert!(!go.contains("strings"), "{go}");
+        assert!(go.contains("http.NewRequest(\"PURGE\", \"https://example.com/cache?key=a\", nil)"));
     }
 }
@@ -0,0 +1,280 @@
+use std::fmt::Display;
+use std::time::Duration;
+
+use hyper::HeaderMap;
+use serde::{Deserialize, Serialize};
+
+/// Time to wait before reconnecting, until the server sets another.
+const RETRY: Duration = Duration::from_secs(3);
+
+/// How events are printed while they are received.
+#[derive(Debug, Clone, Copy, Default, clap::ValueEnum)]
+pub enum Format {
+    /// Event fields, followed by a blank line
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// Event dispatched by a `text/event-stream` response.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Event {
+    /// Event type, which is "message" unless the server names it.
+    pub event: String,
+
+    /// ID set along with this event, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    pub data: String,
+}
+
+/// Incremental parser of an event stream, following the WHATWG HTML specification.
+pub struct Parser {
+    /// Line not yet terminated.
+    line: Vec<u8>,
+
+    /// Whether the last line ended in CR, so a following LF is part of its ending.
+    cr: bool,
+
+    event: Option<String>,
+    id: Option<String>,
+    data: Vec<String>,
+
+    last_id: Option<String>,
+    retry: Duration,
+}
+
+/// Whether `headers` announce an event stream.
+pub fn is_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get("Content-Type")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("text/event-stream"))
+}
+
+impl Event {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "event: {}", self.event)?;
+
+        if let Some(id) = &self.id {
+            writeln!(f, "id: {id}")?;
+        }
+
+        for line in self.data.split('\n') {
+            writeln!(f, "data: {line}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self {
+            line: Vec::new(),
+            cr: false,
+            event: None,
+            id: None,
+            data: Vec::new(),
+            last_id: None,
+            retry: RETRY,
+        }
+    }
+}
+
+impl Parser {
+    /// Reads `chunk` of the stream, returning the events it completes.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        for &byte in chunk {
+            match byte {
+                b'\n' if self.cr => self.cr = false,
+                b'\n' | b'\r' => {
+                    self.cr = byte == b'\r';
+
+                    let line = std::mem::take(&mut self.line);
+                    if let Some(event) = self.line(&String::from_utf8_lossy(&line)) {
+                        events.push(event);
+                    }
+                }
+                _ => {
+                    self.cr = false;
+                    self.line.push(byte);
+                }
+            }
+        }
+
+        events
+    }
+
+    /// ID of the last event received, to be sent as Last-Event-ID when reconnecting.
+    pub fn last_id(&self) -> Option<&str> {
+        self.last_id.as_deref().filter(|id| !id.is_empty())
+    }
+
+    /// Time to wait before reconnecting.
+    pub fn retry(&self) -> Duration {
+        self.retry
+    }
+
+    /// Drops any partly received line and event, keeping only what is carried over to the next
+    /// connection: the last event ID and reconnection time.
+    pub fn reset(&mut self) {
+        *self = Self {
+            last_id: self.last_id.take(),
+            retry: self.retry,
+            ..Self::default()
+        };
+    }
+
+    fn line(&mut self, line: &str) -> Option<Event> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+
+        // Comments are usually sent to keep the connection alive
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            "id" if !value.contains('\0') => self.id = Some(value.to_string()),
+            "retry" => {
+                if let Ok(ms) = value.parse() {
+                    self.retry = Duration::from_millis(ms);
+                }
+            }
+            _ => (),
+        }
+
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<Event> {
+        let event = self.event.take();
+        let id = self.id.take();
+
+        // Only events that were fully received move the reconnection point forward
+        if id.is_some() {
+            self.last_id.clone_from(&id);
+        }
+
+        if self.data.is_empty() {
+            return None;
+        }
+
+        Some(Event {
+            event: event
+                .filter(|name| !name.is_empty())
+                .unwrap_or(String::from("message")),
+            id,
+            data: std::mem::take(&mut self.data).join("\n"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sse_parse() {
+        let mut parser = Parser::default();
+
+        let events = parser.feed(b": ping\n\nevent: update\nid: 1\ndata: {\"a\":1}\ndata:2\n");
+        assert!(events.is_empty());
+
+        let events = parser.feed(b"\r\ndata: hello\r");
+        assert_eq!(
+            events,
+            [Event {
+                event: String::from("update"),
+                id: Some(String::from("1")),
+                data: String::from("{\"a\":1}\n2"),
+            }]
+        );
+
+        let events = parser.feed(b"\nretry: 10\nid\n\n");
+        assert_eq!(
+            events,
+            [Event {
+                event: String::from("message"),
+                id: Some(String::new()),
+                data: String::from("hello"),
+            }]
+        );
+        assert_eq!(parser.last_id(), None);
+        assert_eq!(parser.retry(), Duration::from_millis(10));
+    }
+
+    #[test]
+    fn sse_reset() {
+        let mut parser = Parser::default();
+
+        parser.feed(b"retry: 10\nid: 1\ndata: first\n\nevent: update\nid: 2\ndata: cut\nda");
+        parser.reset();
+
+        assert_eq!(parser.last_id(), Some("1"));
+        assert_eq!(parser.retry(), Duration::from_millis(10));
+
+        let events = parser.feed(b"ta: second\n\n");
+        assert!(events.is_empty());
+
+        let events = parser.feed(b"data: third\n\n");
+        assert_eq!(
+            events,
+            [Event {
+                event: String::from("message"),
+                id: None,
+                data: String::from("third"),
+            }]
+        );
+    }
+
+    #[test]
+    fn sse_event_display() {
+        let event = Event {
+            event: String::from("update"),
+            id: Some(String::from("7")),
+            data: String::from("a\nb"),
+        };
+
+        assert_eq!(
+            event.to_string(),
+            "event: update\nid: 7\ndata: a\ndata: b\n"
+        );
+        assert_eq!(
+            event.to_json(),
+            r#"{"event":"update","id":"7","data":"a\nb"}"#
+        );
+    }
+
+    #[test]
+    fn sse_content_type() {
+        let mut headers = HeaderMap::new();
+        assert!(!is_event_stream(&headers));
+
+        headers.insert(
+            "Content-Type",
+            "text/event-stream; charset=utf-8".parse().unwrap(),
+        );
+        assert!(is_event_stream(&headers));
+    }
+}
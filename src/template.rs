@@ -0,0 +1,360 @@
+use serde_json::Value;
+
+/// Logic-less template, in a subset of the Mustache syntax, rendered with JSON values.
+///
+/// - `{{name}}` writes a value, where `name` may be a dotted path such as `a.b` or `.` for the
+///   current value. It may be followed by filters: `{{body | json}}`.
+/// - `{{#name}}...{{/name}}` renders its content for each item of a list, or once when the
+///   value is present and not false or empty. Inside lists, `@index`, `@first` and `@last` are
+///   also available.
+/// - `{{^name}}...{{/name}}` renders its content when the value is absent, false or empty.
+/// - `{{! comment }}` is ignored.
+///
+/// Lines with only a section or comment tag are removed from the output.
+///
+/// # Examples
+///
+/// ```
+/// use quartz_cli::template::Template;
+/// use serde_json::json;
+///
+/// let template = Template::parse(
+///     "{{method}} {{url}}\n{{#headers}}\n{{name | upper}}: {{value}}\n{{/headers}}",
+/// )
+/// .unwrap();
+///
+/// let context = json!({
+///     "method": "GET",
+///     "url": "https://example.com",
+///     "headers": [{ "name": "accept", "value": "*/*" }],
+/// });
+///
+/// assert_eq!(template.render(&context), "GET https://example.com\nACCEPT: */*\n");
+/// ```
+#[derive(Debug)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Variable {
+        path: String,
+        filters: Vec<Filter>,
+    },
+    Section {
+        path: String,
+        inverted: bool,
+        nodes: Vec<Node>,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Filter {
+    /// Double-quoted string literal, as in JSON.
+    Json,
+    /// Single-quoted shell word.
+    Shell,
+    /// Percent-encoded URL component.
+    Url,
+    Upper,
+    Lower,
+}
+
+/// Tag of a template, before sections are nested.
+enum Token<'a> {
+    Text(String),
+    Variable(&'a str),
+    Open(&'a str, bool),
+    Close(&'a str),
+}
+
+impl Template {
+    /// # Errors
+    ///
+    /// This function will return an error if tags are unterminated, sections are not closed in
+    /// order or a filter does not exist.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut stack: Vec<(&str, bool, Vec<Node>)> = vec![("", false, Vec::new())];
+
+        for token in tokens(input)? {
+            match token {
+                Token::Text(text) => stack.last_mut().unwrap().2.push(Node::Text(text)),
+                Token::Variable(tag) => {
+                    let mut parts = tag.split('|').map(str::trim);
+                    let path = parts.next().unwrap_or_default().to_string();
+                    let filters = parts.map(filter).collect::<Result<_, _>>()?;
+
+                    stack
+                        .last_mut()
+                        .unwrap()
+                        .2
+                        .push(Node::Variable { path, filters });
+                }
+                Token::Open(path, inverted) => stack.push((path, inverted, Vec::new())),
+                Token::Close(path) => {
+                    let (open, inverted, nodes) = stack.pop().unwrap();
+                    if stack.is_empty() || open != path {
+                        return Err(format!("unexpected {{{{/{path}}}}} in template"));
+                    }
+
+                    stack.last_mut().unwrap().2.push(Node::Section {
+                        path: open.to_string(),
+                        inverted,
+                        nodes,
+                    });
+                }
+            }
+        }
+
+        let (path, _, nodes) = stack.pop().unwrap();
+        if !stack.is_empty() {
+            return Err(format!("unclosed {{{{#{path}}}}} in template"));
+        }
+
+        Ok(Self { nodes })
+    }
+
+    pub fn render(&self, context: &Value) -> String {
+        let mut out = String::new();
+        render(&self.nodes, &mut vec![context], &mut Vec::new(), &mut out);
+
+        out
+    }
+}
+
+/// Splits `input` into text and tags, removing lines that only hold a section or comment tag.
+fn tokens(input: &str) -> Result<Vec<Token<'_>>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = input;
+
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..]
+            .find("}}")
+            .map(|i| start + i)
+            .ok_or("unterminated tag in template")?;
+
+        let tag = rest[start + 2..end].trim();
+        let mut text = &rest[..start];
+        let mut after = &rest[end + 2..];
+
+        let token = match tag.chars().next() {
+            Some('#') => Some(Token::Open(tag[1..].trim(), false)),
+            Some('^') => Some(Token::Open(tag[1..].trim(), true)),
+            Some('/') => Some(Token::Close(tag[1..].trim())),
+            Some('!') => None,
+            _ => {
+                tokens.push(Token::Text(text.to_string()));
+                tokens.push(Token::Variable(tag));
+                rest = after;
+                continue;
+            }
+        };
+
+        // Standalone tags take their whole line with them
+        let line_start = text.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let at_line_start = text[line_start..].trim().is_empty()
+            && (line_start > 0 || tokens.is_empty() || ends_line(&tokens));
+        let line_end = after.find('\n').map(|i| i + 1).unwrap_or(after.len());
+        if at_line_start && after[..line_end].trim().is_empty() {
+            text = &text[..line_start];
+            after = &after[line_end..];
+        }
+
+        tokens.push(Token::Text(text.to_string()));
+        if let Some(token) = token {
+            tokens.push(token);
+        }
+
+        rest = after;
+    }
+
+    tokens.push(Token::Text(rest.to_string()));
+
+    Ok(tokens)
+}
+
+/// Whether the text before the last tag ended a line, so the next tag may be standalone.
+fn ends_line(tokens: &[Token]) -> bool {
+    match tokens.last() {
+        Some(Token::Text(text)) => text.is_empty() || text.ends_with('\n'),
+        _ => true,
+    }
+}
+
+fn filter(name: &str) -> Result<Filter, String> {
+    match name {
+        "json" => Ok(Filter::Json),
+        "shell" => Ok(Filter::Shell),
+        "url" => Ok(Filter::Url),
+        "upper" => Ok(Filter::Upper),
+        "lower" => Ok(Filter::Lower),
+        _ => Err(format!("unknown template filter: {name}")),
+    }
+}
+
+fn render(
+    nodes: &[Node],
+    stack: &mut Vec<&Value>,
+    loops: &mut Vec<(usize, usize)>,
+    out: &mut String,
+) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Variable { path, filters } => {
+                let mut value = match lookup(path, stack, loops) {
+                    Some(Value::String(s)) => s,
+                    Some(Value::Null) | None => String::new(),
+                    Some(value) => value.to_string(),
+                };
+
+                for filter in filters {
+                    value = apply(*filter, &value);
+                }
+
+                out.push_str(&value);
+            }
+            Node::Section {
+                path,
+                inverted,
+                nodes,
+            } => {
+                let value = lookup_ref(path, stack);
+
+                let truthy = match (value, loop_value(path, loops)) {
+                    (_, Some(flag)) => flag == "true",
+                    (None | Some(Value::Null) | Some(Value::Bool(false)), _) => false,
+                    (Some(Value::String(s)), _) => !s.is_empty(),
+                    (Some(Value::Array(list)), _) => !list.is_empty(),
+                    (Some(_), _) => true,
+                };
+
+                if *inverted {
+                    if !truthy {
+                        render(nodes, stack, loops, out);
+                    }
+                    continue;
+                }
+
+                match value {
+                    Some(Value::Array(list)) => {
+                        for (i, item) in list.iter().enumerate() {
+                            stack.push(item);
+                            loops.push((i, list.len()));
+                            render(nodes, stack, loops, out);
+                            loops.pop();
+                            stack.pop();
+                        }
+                    }
+                    Some(value) if truthy => {
+                        stack.push(value);
+                        render(nodes, stack, loops, out);
+                        stack.pop();
+                    }
+                    None if truthy => render(nodes, stack, loops, out),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Value at `path`, looked up from the innermost context outwards, or a loop value.
+fn lookup(path: &str, stack: &[&Value], loops: &[(usize, usize)]) -> Option<Value> {
+    if let Some(value) = loop_value(path, loops) {
+        return Some(Value::String(value));
+    }
+
+    lookup_ref(path, stack).cloned()
+}
+
+fn lookup_ref<'a>(path: &str, stack: &[&'a Value]) -> Option<&'a Value> {
+    if path == "." {
+        return stack.last().copied();
+    }
+
+    let mut keys = path.split('.');
+    let first = keys.next()?;
+
+    let mut value = stack.iter().rev().find_map(|context| context.get(first))?;
+    for key in keys {
+        value = value.get(key)?;
+    }
+
+    Some(value)
+}
+
+fn loop_value(path: &str, loops: &[(usize, usize)]) -> Option<String> {
+    let (index, len) = loops.last()?;
+
+    match path {
+        "@index" => Some(index.to_string()),
+        "@first" => Some((*index == 0).to_string()),
+        "@last" => Some((index + 1 == *len).to_string()),
+        _ => None,
+    }
+}
+
+fn apply(filter: Filter, value: &str) -> String {
+    match filter {
+        Filter::Json => serde_json::to_string(value).unwrap_or_default(),
+        Filter::Shell => format!("'{}'", value.replace('\'', "'\\''")),
+        Filter::Url => value
+            .bytes()
+            .map(|b| match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                    (b as char).to_string()
+                }
+                _ => format!("%{b:02X}"),
+            })
+            .collect(),
+        Filter::Upper => value.to_uppercase(),
+        Filter::Lower => value.to_lowercase(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn template_sections() {
+        let template = Template::parse(concat!(
+            "call({{#headers}}{{name | json}}{{^@last}}, {{/@last}}{{/headers}})\n",
+            "{{#body}}\n",
+            "  body = {{. | shell}}\n",
+            "{{/body}}\n",
+            "{{^body}}\n",
+            "  no body\n",
+            "{{/body}}\n",
+            "{{! ignored }}\n",
+            "{{missing.key}}{{page.size}}",
+        ))
+        .unwrap();
+
+        let context = json!({
+            "headers": [{ "name": "a" }, { "name": "b" }],
+            "body": "it's",
+            "page": { "size": 10 },
+        });
+        assert_eq!(
+            template.render(&context),
+            "call(\"a\", \"b\")\n  body = 'it'\\''s'\n10"
+        );
+
+        let context = json!({ "headers": [], "body": null });
+        assert_eq!(template.render(&context), "call()\n  no body\n");
+    }
+
+    #[test]
+    fn template_errors() {
+        assert!(Template::parse("{{#a}}").is_err());
+        assert!(Template::parse("{{#a}}{{/b}}").is_err());
+        assert!(Template::parse("{{/a}}").is_err());
+        assert!(Template::parse("{{a | nope}}").is_err());
+        assert!(Template::parse("{{a").is_err());
+    }
+}
@@ -0,0 +1,436 @@
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use tokio::time::{sleep_until, Instant};
+
+use crate::{encoding::base64, history::EntryBuilder, random_bytes, QuartzResult};
+
+/// Appended to the handshake key to compute the Sec-WebSocket-Accept header, as RFC 6455 sets.
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Time to wait for the server to close the connection after we asked to.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opcode {
+    Continuation,
+    Text,
+    Binary,
+    Close,
+    Ping,
+    Pong,
+}
+
+/// Single WebSocket frame. Messages may be split into several of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    /// Whether this is the last frame of a message.
+    pub fin: bool,
+    pub opcode: Opcode,
+    pub payload: Vec<u8>,
+}
+
+/// Whether `url` uses the ws:// or wss:// scheme.
+pub fn is_websocket_url(url: &str) -> bool {
+    let url = url.to_ascii_lowercase();
+
+    url.starts_with("ws://") || url.starts_with("wss://")
+}
+
+/// URL with its ws:// or wss:// scheme replaced by the HTTP one the handshake is sent to.
+pub fn http_url(url: &str) -> String {
+    match url.split_once("://") {
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("ws") => format!("http://{rest}"),
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("wss") => format!("https://{rest}"),
+        _ => url.to_string(),
+    }
+}
+
+/// Random value for the Sec-WebSocket-Key header.
+pub fn key() -> String {
+    base64(&random_bytes::<16>())
+}
+
+/// Sec-WebSocket-Accept header value the server must answer `key` with.
+pub fn accept(key: &str) -> String {
+    base64(&openssl::sha::sha1(format!("{key}{GUID}").as_bytes()))
+}
+
+impl Opcode {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0x0 => Some(Self::Continuation),
+            0x1 => Some(Self::Text),
+            0x2 => Some(Self::Binary),
+            0x8 => Some(Self::Close),
+            0x9 => Some(Self::Ping),
+            0xA => Some(Self::Pong),
+            _ => None,
+        }
+    }
+
+    fn as_u8(&self) -> u8 {
+        match self {
+            Self::Continuation => 0x0,
+            Self::Text => 0x1,
+            Self::Binary => 0x2,
+            Self::Close => 0x8,
+            Self::Ping => 0x9,
+            Self::Pong => 0xA,
+        }
+    }
+}
+
+impl Frame {
+    pub fn new(opcode: Opcode, payload: Vec<u8>) -> Self {
+        Self {
+            fin: true,
+            opcode,
+            payload,
+        }
+    }
+
+    /// Close frame with a status `code`, as sent to end a session.
+    pub fn close(code: u16) -> Self {
+        Self::new(Opcode::Close, code.to_be_bytes().to_vec())
+    }
+
+    /// Encodes this frame, masking its payload with `mask` as clients must.
+    pub fn encode(&self, mask: Option<[u8; 4]>) -> Vec<u8> {
+        let mut bytes = vec![(self.fin as u8) << 7 | self.opcode.as_u8()];
+        let masked = (mask.is_some() as u8) << 7;
+
+        match self.payload.len() {
+            len @ 0..=125 => bytes.push(masked | len as u8),
+            len @ 126..=0xFFFF => {
+                bytes.push(masked | 126);
+                bytes.extend((len as u16).to_be_bytes());
+            }
+            len => {
+                bytes.push(masked | 127);
+                bytes.extend((len as u64).to_be_bytes());
+            }
+        }
+
+        match mask {
+            Some(mask) => {
+                bytes.extend(mask);
+                bytes.extend(
+                    self.payload
+                        .iter()
+                        .enumerate()
+                        .map(|(i, byte)| byte ^ mask[i % 4]),
+                );
+            }
+            None => bytes.extend(&self.payload),
+        }
+
+        bytes
+    }
+
+    /// Reads the next frame from `reader`, unmasking its payload.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the frame is malformed or its payload is larger
+    /// than `max` bytes.
+    pub async fn read<R>(reader: &mut R, max: u64) -> std::io::Result<Self>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let mut head = [0; 2];
+        reader.read_exact(&mut head).await?;
+
+        let opcode = Opcode::from_u8(head[0] & 0x0F).ok_or(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "unknown frame opcode",
+        ))?;
+
+        let len = match head[1] & 0x7F {
+            126 => reader.read_u16().await? as u64,
+            127 => reader.read_u64().await?,
+            len => len as u64,
+        };
+
+        if len > max {
+            return Err(too_large(len, max));
+        }
+
+        let mut mask = None;
+        if head[1] & 0x80 != 0 {
+            let mut key = [0; 4];
+            reader.read_exact(&mut key).await?;
+            mask = Some(key);
+        }
+
+        let mut payload = Vec::new();
+        reader.take(len).read_to_end(&mut payload).await?;
+        if (payload.len() as u64) < len {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+
+        if let Some(mask) = mask {
+            for (i, byte) in payload.iter_mut().enumerate() {
+                *byte ^= mask[i % 4];
+            }
+        }
+
+        Ok(Self {
+            fin: head[0] & 0x80 != 0,
+            opcode,
+            payload,
+        })
+    }
+}
+
+fn too_large(len: u64, max: u64) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("message of {len} bytes exceeds the limit of {max}"),
+    )
+}
+
+/// Lines read from standard input, each to be sent as a text message.
+pub fn stdin() -> mpsc::Receiver<String> {
+    let (lines, input) = mpsc::channel(16);
+
+    // A blocking thread does not hold the runtime back from shutting down once the session ends
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+
+            if lines.blocking_send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    input
+}
+
+/// Message as printed and recorded, with binary ones described by their size.
+fn text(opcode: Opcode, payload: &[u8]) -> String {
+    match (opcode, std::str::from_utf8(payload)) {
+        (Opcode::Text, Ok(text)) => text.to_string(),
+        _ => format!("[binary message, {} bytes]", payload.len()),
+    }
+}
+
+fn timestamp() -> String {
+    chrono::Local::now().format("%H:%M:%S%.3f").to_string()
+}
+
+async fn send<W>(writer: &mut W, frame: Frame) -> QuartzResult
+where
+    W: AsyncWrite + Unpin,
+{
+    writer
+        .write_all(&frame.encode(Some(random_bytes())))
+        .await?;
+    writer.flush().await?;
+
+    Ok(())
+}
+
+/// Exchanges messages over an upgraded `stream` until either side closes it.
+///
+/// The `first` message is sent right away, followed by every `input` line. Incoming messages are
+/// written to `out` with the time they were received. The whole transcript is recorded in
+/// `entry`. Messages larger than `max` bytes end the connection.
+///
+/// # Errors
+///
+/// This function will return an error if a message could not be sent.
+pub async fn session<S, O>(
+    stream: S,
+    first: Option<Vec<u8>>,
+    mut input: mpsc::Receiver<String>,
+    out: &mut O,
+    entry: &mut EntryBuilder,
+    max: u64,
+) -> QuartzResult
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+    O: AsyncWrite + Unpin + ?Sized,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+
+    // Frames are read apart, as reading one cannot be interrupted halfway
+    let (incoming, mut frames) = mpsc::channel(16);
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(reader);
+
+        // Size of the message being reassembled from its frames
+        let mut size = 0;
+
+        loop {
+            let frame = Frame::read(&mut reader, max).await.and_then(|frame| {
+                match frame.opcode {
+                    Opcode::Text | Opcode::Binary => size = frame.payload.len() as u64,
+                    Opcode::Continuation => size += frame.payload.len() as u64,
+                    _ => return Ok(frame),
+                }
+
+                match size > max {
+                    true => Err(too_large(size, max)),
+                    false => Ok(frame),
+                }
+            });
+            let failed = frame.is_err();
+
+            if incoming.send(frame).await.is_err() || failed {
+                break;
+            }
+        }
+    });
+
+    if let Some(body) = first {
+        let opcode = match std::str::from_utf8(&body) {
+            Ok(_) => Opcode::Text,
+            Err(_) => Opcode::Binary,
+        };
+
+        entry.message_raw(format!("{} > {}", timestamp(), text(opcode, &body)));
+        send(&mut writer, Frame::new(opcode, body)).await?;
+    }
+
+    // Opcode and payload of a message split into several frames
+    let mut message = (Opcode::Text, Vec::new());
+    let mut closing: Option<Instant> = None;
+
+    loop {
+        tokio::select! {
+            line = input.recv(), if closing.is_none() => match line {
+                Some(line) => {
+                    entry.message_raw(format!("{} > {line}", timestamp()));
+                    send(&mut writer, Frame::new(Opcode::Text, line.into_bytes())).await?;
+                }
+                None => {
+                    send(&mut writer, Frame::close(1000)).await?;
+                    closing = Some(Instant::now() + CLOSE_TIMEOUT);
+                }
+            },
+            frame = frames.recv() => {
+                let frame = match frame {
+                    Some(Ok(frame)) => frame,
+                    Some(Err(err)) => {
+                        entry.message_raw(format!("* connection lost: {err}"));
+                        break;
+                    }
+                    None => break,
+                };
+
+                match frame.opcode {
+                    Opcode::Ping => {
+                        send(&mut writer, Frame::new(Opcode::Pong, frame.payload)).await?;
+                    }
+                    Opcode::Pong => (),
+                    Opcode::Close => {
+                        if closing.is_none() {
+                            // Echoes the status code, as the closing handshake expects
+                            let code = match frame.payload.get(..2) {
+                                Some(code) => u16::from_be_bytes([code[0], code[1]]),
+                                None => 1000,
+                            };
+                            send(&mut writer, Frame::close(code)).await?;
+                        }
+
+                        let mut note = String::from("* connection closed");
+                        if let [high, low, reason @ ..] = frame.payload.as_slice() {
+                            note.push_str(&format!(": {}", u16::from_be_bytes([*high, *low])));
+                            if !reason.is_empty() {
+                                note.push_str(&format!(" {}", String::from_utf8_lossy(reason)));
+                            }
+                        }
+
+                        entry.message_raw(note);
+                        break;
+                    }
+                    opcode => {
+                        if opcode != Opcode::Continuation {
+                            message = (opcode, Vec::new());
+                        }
+                        message.1.extend(frame.payload);
+
+                        if !frame.fin {
+                            continue;
+                        }
+
+                        let (opcode, payload) =
+                            std::mem::replace(&mut message, (Opcode::Text, Vec::new()));
+                        let time = timestamp();
+                        let text = text(opcode, &payload);
+
+                        out.write_all(format!("{time} < {text}\n").as_bytes()).await?;
+                        out.flush().await?;
+                        entry.message_raw(format!("{time} < {text}"));
+                    }
+                }
+            },
+            _ = sleep_until(closing.unwrap_or_else(Instant::now)), if closing.is_some() => {
+                entry.message_raw(String::from("* server did not close the connection"));
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn websocket_url() {
+        assert!(is_websocket_url("wss://example.com/socket"));
+        assert!(!is_websocket_url("https://example.com/socket"));
+
+        assert_eq!(http_url("ws://localhost:8080/"), "http://localhost:8080/");
+        assert_eq!(http_url("WSS://example.com"), "https://example.com");
+        assert_eq!(http_url("example.com"), "example.com");
+    }
+
+    #[test]
+    fn websocket_key() {
+        let key = key();
+
+        assert_eq!(key.len(), 24);
+        assert_ne!(key, super::key());
+    }
+
+    #[test]
+    fn websocket_accept() {
+        // Example from RFC 6455
+        assert_eq!(
+            accept("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[tokio::test]
+    async fn websocket_frame() {
+        let frame = Frame::new(Opcode::Text, b"Hello".to_vec());
+
+        let bytes = frame.encode(Some([0x37, 0xfa, 0x21, 0x3d]));
+        assert_eq!(
+            bytes,
+            [0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58]
+        );
+        assert_eq!(Frame::read(&mut bytes.as_slice(), 5).await.unwrap(), frame);
+
+        let frame = Frame::new(Opcode::Binary, vec![0; 300]);
+        let bytes = frame.encode(None);
+        assert_eq!(bytes[..4], [0x82, 126, 0x01, 0x2c]);
+        assert_eq!(
+            Frame::read(&mut bytes.as_slice(), 1024).await.unwrap(),
+            frame
+        );
+
+        let err = Frame::read(&mut bytes.as_slice(), 299).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -91,3 +91,173 @@ fn compatible_with_apply_env_option() -> TestResult {
 
     Ok(())
 }
+
+#[test]
+fn it_sends_multipart_form() -> TestResult {
+    let server = Server::new(|req| {
+        let content_type = req.header("content-type").unwrap_or_default();
+        let body = String::from_utf8_lossy(&req.body);
+
+        response(200, &[], &format!("{content_type}\n{body}"))
+    });
+    let quartz = Quartz::preset_empty_project()?;
+    std::fs::write(quartz.dir().join("../rex.txt"), "good dog")?;
+
+    let output = quartz.cmd(&[
+        "create",
+        "pets",
+        "--url",
+        &server.url("/pets"),
+        "-X",
+        "POST",
+        "--form",
+        "name=Rex",
+        "--form",
+        "photo=@rex.txt;filename=me.txt",
+        "--use",
+    ])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let toml = std::fs::read_to_string(quartz.dir().join("endpoints/pets/endpoint.toml"))?;
+    assert!(toml.contains("[[form.multipart]]"), "{toml}");
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let (content_type, body) = output.stdout.split_once('\n').unwrap();
+    let boundary = content_type
+        .strip_prefix("multipart/form-data; boundary=")
+        .unwrap();
+    assert_eq!(
+        body,
+        format!(
+            concat!(
+                "--{0}\r\n",
+                "Content-Disposition: form-data; name=\"name\"\r\n",
+                "\r\n",
+                "Rex\r\n",
+                "--{0}\r\n",
+                "Content-Disposition: form-data; name=\"photo\"; filename=\"me.txt\"\r\n",
+                "Content-Type: text/plain\r\n",
+                "\r\n",
+                "good dog\r\n",
+                "--{0}--\r\n",
+            ),
+            boundary
+        )
+    );
+
+    let output = quartz.cmd(&["last"])?;
+    assert!(
+        output.stdout.contains("\n< rex.txt\r\n"),
+        "{}",
+        output.stdout
+    );
+
+    // Files are read relative to the project, wherever it is sent from
+    let subdir = quartz.dir().join("../docs");
+    std::fs::create_dir(&subdir)?;
+    let output = quartz.command(&["send"]).current_dir(&subdir).output()?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("good dog"));
+
+    Ok(())
+}
+
+#[test]
+fn it_sends_urlencoded_form() -> TestResult {
+    let server = Server::new(|req| {
+        let content_type = req.header("content-type").unwrap_or_default();
+        let body = String::from_utf8_lossy(&req.body);
+
+        response(200, &[], &format!("{content_type}\n{body}"))
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "login", "--url", &server.url("/login"), "--use"])?;
+
+    let output = quartz.cmd(&[
+        "send",
+        "-X",
+        "POST",
+        "--urlencoded",
+        "user=rex dog",
+        "--urlencoded",
+        "pass=a&b",
+    ])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(
+        output.stdout,
+        "application/x-www-form-urlencoded\nuser=rex+dog&pass=a%26b"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_fails_on_missing_form_file() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "pets", "--url", "http://localhost", "--use"])?;
+
+    let output = quartz.cmd(&["send", "-X", "POST", "-F", "photo=@nope.png"])?;
+    assert!(!output.status.success(), "{}", output.stdout);
+    assert!(output.stderr.contains("nope.png"), "{}", output.stderr);
+
+    Ok(())
+}
+
+#[test]
+fn it_sends_and_receives_binary_bodies() -> TestResult {
+    const PAYLOAD: &[u8] = &[0x89, b'P', b'N', b'G', 0, 0xff, 0xfe, b'\n', 0x80];
+
+    // Echoes the request body
+    let server = Server::new(|req| {
+        let mut res = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            req.body.len()
+        )
+        .into_bytes();
+        res.extend(&req.body);
+
+        res
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "upload",
+        "--url",
+        &server.url("/upload"),
+        "-X",
+        "POST",
+        "--use",
+    ])?;
+    std::fs::write(quartz.dir().join("endpoints/upload/body"), PAYLOAD)?;
+
+    let output = quartz.cmd(&["body", "show"])?;
+    assert_eq!(output.stdout_bytes, PAYLOAD);
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(output.stdout_bytes, PAYLOAD);
+
+    let output = quartz.cmd(&["last", "res", "body"])?;
+    assert_eq!(output.stdout_bytes, PAYLOAD);
+
+    let output = quartz.cmd(&["last"])?;
+    assert_eq!(output.stdout.matches("[binary body, 9 bytes]").count(), 2);
+
+    let output = quartz.cmd(&["history", "export", "--har"])?;
+    let har: serde_json::Value = serde_json::from_str(&output.stdout)?;
+    let content = &har["log"]["entries"][0]["response"]["content"];
+    assert_eq!(content["encoding"], "base64");
+    assert_eq!(content["text"], "iVBORwD//gqA");
+    assert_eq!(content["size"], 9);
+
+    Ok(())
+}
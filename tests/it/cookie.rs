@@ -0,0 +1,166 @@
+use crate::utils::*;
+
+#[test]
+fn it_sets_and_removes_cookies() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+
+    let output = quartz.cmd(&["cookie", "set", "session=abc", "--domain", "example.com"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    quartz.cmd(&["cookie", "set", "session=xyz", "--domain", "other.com"])?;
+    quartz.cmd(&["cookie", "set", "theme=dark", "--domain", "example.com"])?;
+
+    let output = quartz.cmd(&["cookie", "ls", "session", "--domain", "example.com"])?;
+    assert_eq!(output.stdout.trim(), "abc", "{}", output.stderr);
+
+    let output = quartz.cmd(&["cookie", "rm", "session", "--domain", "example.com"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let output = quartz.cmd(&["cookie", "ls", "session"])?;
+    assert_eq!(output.stdout.trim(), "xyz");
+
+    // Domains are compared exactly, so parent domains and subdomains are left alone
+    quartz.cmd(&["cookie", "set", "a=1", "--domain", "api.other.com"])?;
+    quartz.cmd(&[
+        "cookie",
+        "set",
+        "a=2",
+        "--domain",
+        "other.com",
+        "--subdomains",
+    ])?;
+
+    let output = quartz.cmd(&["cookie", "ls", "a", "--domain", "API.other.com"])?;
+    assert_eq!(output.stdout.trim(), "1", "{}", output.stderr);
+
+    quartz.cmd(&["cookie", "rm", "a", "--domain", "other.com"])?;
+    let output = quartz.cmd(&["cookie", "ls", "a"])?;
+    assert_eq!(output.stdout.trim(), "1");
+
+    let output = quartz.cmd(&["cookie", "rm", "idontexist"])?;
+    assert!(!output.status.success());
+
+    quartz.cmd(&["cookie", "clear"])?;
+    let output = quartz.cmd(&["cookie", "ls"])?;
+    assert!(output.stdout.is_empty(), "{}", output.stdout);
+
+    Ok(())
+}
+
+#[test]
+fn it_sets_cookie_expiration() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "cookie",
+        "set",
+        "a=1",
+        "-d",
+        "example.com",
+        "--max-age",
+        "60",
+    ])?;
+    quartz.cmd(&[
+        "cookie",
+        "set",
+        "b=2",
+        "-d",
+        "example.com",
+        "--expires",
+        "Wed, 01 Jan 3000 00:00:00 GMT",
+    ])?;
+    quartz.cmd(&[
+        "cookie",
+        "set",
+        "c=3",
+        "-d",
+        "example.com",
+        "--max-age",
+        "0",
+    ])?;
+
+    let output = quartz.cmd(&["cookie", "ls"])?;
+    let mut cookies: Vec<&str> = output.stdout.lines().collect();
+    cookies.sort();
+    assert_eq!(cookies, ["a=1", "b=2"]);
+
+    let output = quartz.cmd(&["cookie", "export"])?;
+    assert!(
+        output.stdout.contains("\t32503680000\tb\t2"),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_imports_curl_cookie_jar() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+
+    let content = [
+        "# Netscape HTTP Cookie File",
+        "# https://curl.se/docs/http-cookies.html",
+        "",
+        ".example.com\tTRUE\t/\tFALSE\t32503680000\tsession\tabc",
+        "#HttpOnly_example.com\tFALSE\t/\tTRUE\t0\ttoken\txyz",
+        "example.com\tFALSE\t/\tFALSE\t1\texpired\t0",
+    ]
+    .join("\n");
+
+    let output = quartz.cmd_stdin(&["cookie", "import"], &content)?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let output = quartz.cmd(&["cookie", "ls"])?;
+    let mut cookies: Vec<&str> = output.stdout.lines().collect();
+    cookies.sort();
+    assert_eq!(cookies, ["session=abc", "token=xyz"]);
+
+    let output = quartz.cmd(&["cookie", "export"])?;
+    assert!(output.stdout.starts_with("# Netscape HTTP Cookie File\n"));
+    assert!(output
+        .stdout
+        .contains("#HttpOnly_example.com\tFALSE\t/\tTRUE\t0\ttoken\txyz"));
+
+    Ok(())
+}
+
+#[test]
+fn it_copies_cookies_between_envs_as_json() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["env", "create", "staging"])?;
+    quartz.cmd(&[
+        "cookie",
+        "set",
+        "session=abc",
+        "-d",
+        "example.com",
+        "--secure",
+    ])?;
+
+    let path = quartz.dir().join("cookies.json");
+    let output = quartz.cmd(&["cookie", "export", path.to_str().unwrap()])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let json = std::fs::read_to_string(&path)?;
+    assert!(json.contains("\"name\": \"session\""), "{}", json);
+    assert!(json.contains("\"secure\": true"), "{}", json);
+
+    let output = quartz.cmd(&[
+        "cookie",
+        "import",
+        path.to_str().unwrap(),
+        "--env",
+        "staging",
+    ])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    quartz.cmd(&["env", "use", "staging"])?;
+    let output = quartz.cmd(&["cookie", "ls", "session"])?;
+    assert_eq!(output.stdout.trim(), "abc", "{}", output.stderr);
+
+    let output = quartz.cmd(&["cookie", "export", "--env", "idontexist"])?;
+    assert!(!output.status.success());
+
+    Ok(())
+}
@@ -322,3 +322,56 @@ fn use_previous_handle() -> TestResult {
     assert_eq!(output.trim(), "endpoint2");
     Ok(())
 }
+
+#[test]
+fn it_creates_endpoint_from_curl() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+
+    let output = quartz.cmd(&[
+        "create",
+        "users/create",
+        "--from-curl",
+        "curl 'https://example.com/users?notify=true' -H 'Content-Type: application/json' --data-raw '{\"name\":\"Rex\"}' --compressed",
+    ])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let output = quartz.cmd(&["-x", "users/create", "show", "method"])?;
+    assert_eq!(output.stdout.trim(), "POST");
+
+    let output = quartz.cmd(&["-x", "users/create", "show", "query", "notify"])?;
+    assert_eq!(output.stdout.trim(), "true");
+
+    let output = quartz.cmd(&["-x", "users/create", "show", "headers", "Content-Type"])?;
+    assert_eq!(output.stdout.trim(), "application/json");
+
+    let output = quartz.cmd(&["-x", "users/create", "show", "body"])?;
+    assert_eq!(output.stdout.trim(), "{\"name\":\"Rex\"}");
+
+    Ok(())
+}
+
+#[test]
+fn it_creates_endpoint_from_curl_on_stdin() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+
+    let output = quartz.cmd_stdin(
+        &["create", "search", "--from-curl", "-", "-X", "PATCH"],
+        "curl -G https://example.com/search \\\n  -d q=rex --retry-all-errors",
+    )?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(
+        output
+            .stderr
+            .contains("--retry-all-errors: option is not supported"),
+        "{}",
+        output.stderr
+    );
+
+    let output = quartz.cmd(&["-x", "search", "show", "method"])?;
+    assert_eq!(output.stdout.trim(), "PATCH");
+
+    let output = quartz.cmd(&["-x", "search", "show", "query", "q"])?;
+    assert_eq!(output.stdout.trim(), "rex");
+
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use crate::utils::*;
+use serde_json::Value;
+
+#[test]
+fn it_exports_openapi_spec() -> Result<(), Box<dyn std::error::Error>> {
+    let server = Server::new(|_| {
+        response(
+            200,
+            &[("Content-Type", "application/json")],
+            r#"{ "id": 1, "name": "Rex", "tags": ["good"] }"#,
+        )
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["var", "set", &format!("baseUrl={}", server.url(""))])?;
+    quartz.cmd(&["create", "api", "--url", "{{baseUrl}}"])?;
+    quartz.cmd(&[
+        "create",
+        "api/pets",
+        "--url",
+        "**/pets/{{id}}",
+        "-q",
+        "fields=name",
+        "-H",
+        "X-Tenant: {{tenant}}",
+    ])?;
+    quartz.cmd(&["var", "set", "id=1", "tenant=acme"])?;
+
+    let output = quartz.cmd(&["-x", "api/pets", "send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let output = quartz.cmd(&["export", "openapi"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let spec: Value = serde_json::from_str(&output.stdout)?;
+    assert_eq!(spec["openapi"], "3.0.3");
+    assert_eq!(spec["servers"][0]["url"], "{baseUrl}");
+    assert_eq!(
+        spec["servers"][0]["variables"]["baseUrl"]["default"],
+        server.url("")
+    );
+
+    let operation = &spec["paths"]["/pets/{id}"]["get"];
+    assert_eq!(operation["operationId"], "pets");
+    assert_eq!(operation["tags"][0], "api");
+
+    let parameters: Vec<(&str, &str)> = operation["parameters"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| (p["name"].as_str().unwrap(), p["in"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        parameters,
+        [("id", "path"), ("fields", "query"), ("X-Tenant", "header")]
+    );
+
+    let schema = &operation["responses"]["200"]["content"]["application/json"]["schema"];
+    assert_eq!(schema["properties"]["id"]["type"], "integer");
+    assert_eq!(schema["properties"]["tags"]["items"]["type"], "string");
+
+    // Endpoints that were never sent have no known response
+    assert!(spec["paths"]["/"]["get"]["responses"]["default"].is_object());
+
+    Ok(())
+}
+
+#[test]
+fn it_exports_history_as_har() -> TestResult {
+    let server = Server::new(|req| match req.path.as_str() {
+        "/old" => response(301, &[("Location", "/new")], ""),
+        _ => response(200, &[("Content-Type", "text/plain")], "moved"),
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "local", "--url", &server.url("/old"), "--use"])?;
+    quartz.cmd(&["send"])?;
+
+    let output = quartz.cmd(&["history", "export", "--har"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let har: serde_json::Value = serde_json::from_str(&output.stdout)?;
+    let entries = har["log"]["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+
+    assert_eq!(entries[0]["request"]["url"], server.url("/old"));
+    assert_eq!(entries[0]["response"]["status"], 301);
+    assert_eq!(entries[0]["response"]["redirectURL"], "/new");
+    assert_eq!(entries[1]["request"]["url"], server.url("/new"));
+    assert_eq!(entries[1]["response"]["content"]["text"], "moved");
+    assert_eq!(entries[1]["_handle"], "local");
+
+    // Entries can be imported back as handles
+    let file = quartz.dir().join("history.har");
+    std::fs::write(&file, &output.stdout)?;
+
+    let output = quartz.cmd(&[
+        "import",
+        "har",
+        file.to_str().unwrap(),
+        "--url",
+        "/new$",
+        "--into",
+        "replay",
+    ])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let output = quartz.cmd(&["-c", "-x", "replay/local", "show", "url"])?;
+    assert_eq!(output.stdout.trim(), server.url("/new"));
+
+    Ok(())
+}
+
+#[test]
+fn it_exports_bodies_that_look_like_heads_as_har() -> TestResult {
+    let server = Server::new(|_| {
+        response(
+            200,
+            &[("Content-Type", "text/plain")],
+            "< HTTP/1.1 500 Fake\n* note",
+        )
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "local",
+        "--url",
+        &server.url("/"),
+        "-X",
+        "POST",
+        "-d",
+        "> GET /fake HTTP/1.1",
+        "--use",
+    ])?;
+    quartz.cmd(&["send"])?;
+
+    let output = quartz.cmd(&["history", "export", "--har"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let har: serde_json::Value = serde_json::from_str(&output.stdout)?;
+    let entries = har["log"]["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+
+    assert_eq!(entries[0]["request"]["method"], "POST");
+    assert_eq!(
+        entries[0]["request"]["postData"]["text"],
+        "> GET /fake HTTP/1.1"
+    );
+    assert_eq!(entries[0]["response"]["status"], 200);
+    assert_eq!(
+        entries[0]["response"]["content"]["text"],
+        "< HTTP/1.1 500 Fake\n* note"
+    );
+
+    let output = quartz.cmd(&["last", "res", "head"])?;
+    assert!(!output.stdout.contains("500"), "{}", output.stdout);
+
+    Ok(())
+}
@@ -0,0 +1,290 @@
+use crate::utils::*;
+
+const SPEC: &str = r##"{
+  "openapi": "3.0.0",
+  "info": { "title": "Pet Store" },
+  "servers": [{ "url": "http://localhost:8080/v1" }],
+  "paths": {
+    "/pets": {
+      "post": {
+        "tags": ["pets"],
+        "operationId": "createPet",
+        "requestBody": {
+          "content": {
+            "application/json": { "example": { "name": "Rex" } }
+          }
+        }
+      }
+    },
+    "/pets/{petId}": {
+      "get": {
+        "tags": ["pets"],
+        "operationId": "showPet",
+        "parameters": [
+          { "name": "petId", "in": "path", "required": true },
+          { "name": "fields", "in": "query", "example": "name" }
+        ]
+      }
+    }
+  }
+}"##;
+
+#[test]
+fn it_imports_openapi_spec() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+
+    let output = quartz.cmd_stdin(&["import", "openapi", "-"], SPEC)?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let output = quartz.cmd(&["ls"])?;
+    assert!(output.stdout.contains("pet-store/pets/createPet"));
+
+    quartz.cmd(&["var", "set", "petId=7"])?;
+    let output = quartz.cmd(&["-c", "-x", "pet-store/pets/showPet", "show", "url"])?;
+    assert_eq!(
+        output.stdout.trim(),
+        "http://localhost:8080/v1/pets/7",
+        "{}",
+        output.stderr
+    );
+
+    let output = quartz.cmd(&["-x", "pet-store/pets/showPet", "show", "query", "fields"])?;
+    assert_eq!(output.stdout.trim(), "name");
+
+    let output = quartz.cmd(&["-x", "pet-store/pets/createPet", "show", "body"])?;
+    assert!(
+        output.stdout.contains("\"name\": \"Rex\""),
+        "{}",
+        output.stdout
+    );
+
+    let output = quartz.cmd(&["var", "get", "baseUrl"])?;
+    assert_eq!(output.stdout.trim(), "http://localhost:8080/v1");
+
+    // Existing handles are kept unless forced
+    let output = quartz.cmd_stdin(&["import", "openapi", "-"], SPEC)?;
+    assert!(output.stderr.contains("warning"), "{}", output.stdout);
+
+    Ok(())
+}
+
+#[test]
+fn it_lists_openapi_import_on_dry_run() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+
+    let output = quartz.cmd_stdin(
+        &["import", "openapi", "-", "--into", "api", "--dry-run"],
+        SPEC,
+    )?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(
+        output.stdout.contains("POST api/pets/createPet **/pets"),
+        "{}",
+        output.stdout
+    );
+    assert!(output
+        .stdout
+        .contains("GET  api/pets/showPet **/pets/{{petId}}"));
+
+    let output = quartz.cmd(&["ls"])?;
+    assert!(output.stdout.trim().is_empty(), "{}", output.stdout);
+
+    Ok(())
+}
+
+#[test]
+fn it_imports_postman_collection() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+    let env = quartz.dir().join("staging.postman_environment.json");
+    std::fs::write(
+        &env,
+        r#"{ "name": "Staging", "values": [{ "key": "baseUrl", "value": "https://staging.example.com", "enabled": true }] }"#,
+    )?;
+
+    let collection = r#"{
+      "info": { "name": "Shop" },
+      "item": [{
+        "name": "Orders",
+        "item": [{
+          "name": "Create order",
+          "event": [{ "listen": "prerequest", "script": { "exec": ["pm.environment.set('a', 1)"] } }],
+          "request": {
+            "method": "POST",
+            "url": "{{baseUrl}}/orders",
+            "body": { "mode": "raw", "raw": "{\"qty\": 1}", "options": { "raw": { "language": "json" } } }
+          }
+        }]
+      }]
+    }"#;
+
+    let output = quartz.cmd_stdin(
+        &["import", "postman", "-", "--env", env.to_str().unwrap()],
+        collection,
+    )?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(
+        output
+            .stderr
+            .contains("shop/orders/create-order: pre-request scripts are not supported"),
+        "{}",
+        output.stderr
+    );
+
+    let output = quartz.cmd(&["ls"])?;
+    assert!(
+        output.stdout.contains("---  shop/orders\n"),
+        "{}",
+        output.stdout
+    );
+    assert!(output.stdout.contains("POST shop/orders/create-order"));
+
+    let output = quartz.cmd(&["-x", "shop/orders/create-order", "show", "body"])?;
+    assert_eq!(output.stdout.trim(), "{\"qty\": 1}");
+
+    quartz.cmd(&["env", "use", "staging"])?;
+    let output = quartz.cmd(&["var", "get", "baseUrl"])?;
+    assert_eq!(output.stdout.trim(), "https://staging.example.com");
+
+    Ok(())
+}
+
+#[test]
+fn it_imports_insomnia_export() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+
+    let export = r#"
+_type: export
+__export_format: 4
+resources:
+  - { _id: wrk_1, _type: workspace, name: Billing }
+  - { _id: env_1, _type: environment, parentId: wrk_1, name: Base, data: { host: "http://localhost" } }
+  - { _id: env_2, _type: environment, parentId: env_1, name: Production, data: { host: "https://billing.example.com" } }
+  - { _id: fld_1, _type: request_group, parentId: wrk_1, name: Invoices }
+  - _id: req_1
+    _type: request
+    parentId: fld_1
+    name: Get invoice
+    method: GET
+    url: "{{ _.host }}/invoices/1"
+    headers: [{ name: Accept, value: application/json }]
+"#;
+
+    let output = quartz.cmd_stdin(&["import", "insomnia", "-"], export)?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let output = quartz.cmd(&["-c", "-x", "billing/invoices/get-invoice", "show", "url"])?;
+    assert_eq!(output.stdout.trim(), "http://localhost/invoices/1");
+
+    quartz.cmd(&["env", "use", "production"])?;
+    let output = quartz.cmd(&["-c", "-x", "billing/invoices/get-invoice", "show", "url"])?;
+    assert_eq!(
+        output.stdout.trim(),
+        "https://billing.example.com/invoices/1"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_imports_and_exports_http_file() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+
+    let file = r#"
+@host = http://localhost:3000
+
+### Create user
+# @name users/create
+POST {{host}}/users HTTP/1.1
+Content-Type: application/json
+
+{"name": "Rex"}
+
+###
+GET {{host}}/users?page=2
+"#;
+
+    let output = quartz.cmd_stdin(&["import", "http", "-", "--into", "api"], file)?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let output = quartz.cmd(&["-c", "-x", "api/users/create", "show", "url"])?;
+    assert_eq!(output.stdout.trim(), "http://localhost:3000/users");
+
+    let output = quartz.cmd(&["-x", "api/get-users", "show", "query", "page"])?;
+    assert_eq!(output.stdout.trim(), "2");
+
+    let output = quartz.cmd(&["export", "http", "api/users"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(
+        output.stdout,
+        "###\n# @name api/users/create\nPOST {{host}}/users\nContent-Type: application/json\n\n{\"name\": \"Rex\"}\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_skips_imported_handles_outside_of_the_project() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+    let project = quartz.dir().join("..");
+
+    let spec = r#"{
+      "openapi": "3.0.0",
+      "info": { "title": ".." },
+      "paths": {
+        "/../../escape": {
+          "get": { "tags": [".."], "operationId": ".." }
+        },
+        "/pets": {
+          "get": { "tags": ["pets"], "operationId": "listPets" }
+        }
+      }
+    }"#;
+
+    let output = quartz.cmd_stdin(&["import", "openapi", "-", "--into", "api"], spec)?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(
+        output
+            .stderr
+            .contains("skipping api/../.., which is not a valid handle"),
+        "{}",
+        output.stderr
+    );
+
+    let output = quartz.cmd_stdin(
+        &[
+            "import",
+            "openapi",
+            "-",
+            "--into",
+            "paths",
+            "--group-by",
+            "paths",
+        ],
+        spec,
+    )?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(
+        output
+            .stderr
+            .contains("skipping paths/../../escape/get, which is not a valid handle"),
+        "{}",
+        output.stderr
+    );
+
+    let file = "###\n# @name ../../escape\nGET http://localhost/escape\n";
+    let output = quartz.cmd_stdin(&["import", "http", "-"], file)?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(output.stdout.contains("Imported 0 endpoints"));
+
+    assert!(!project.join("escape").exists());
+    assert!(!quartz.dir().join("escape").exists());
+
+    let output = quartz.cmd(&["ls"])?;
+    assert!(
+        output.stdout.contains("api/pets/listPets"),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
@@ -1,9 +1,17 @@
 pub mod body;
 pub mod config;
+pub mod cookie;
 pub mod endpoint;
 pub mod env;
+pub mod export;
 pub mod header;
+pub mod import;
 pub mod init;
 pub mod op;
 pub mod query;
+pub mod run;
+pub mod send;
+pub mod snippet;
+pub mod test;
 pub mod var;
+pub mod websocket;
@@ -0,0 +1,263 @@
+use crate::utils::*;
+
+fn append_endpoint(quartz: &Quartz, handle: &[&str], content: &str) -> TestResult {
+    let mut path = quartz.dir().join("endpoints");
+    for name in handle {
+        path.push(name);
+    }
+    path.push("endpoint.toml");
+
+    let mut endpoint = std::fs::read_to_string(&path)?;
+    endpoint.push_str(content);
+    std::fs::write(&path, endpoint)?;
+
+    Ok(())
+}
+
+fn preset_api(server: &Server) -> Result<Quartz, Box<dyn std::error::Error>> {
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "api/login", "--url", &server.url("/login")])?;
+    quartz.cmd(&["create", "api/users", "--url", &server.url("/users")])?;
+    quartz.cmd(&[
+        "create",
+        "api/users/broken",
+        "--url",
+        &server.url("/broken"),
+    ])?;
+    quartz.cmd(&["create", "other", "--url", &server.url("/other")])?;
+
+    append_endpoint(
+        &quartz,
+        &["api", "login"],
+        "\n[[capture]]\nvar = \"token\"\njson = \"$.token\"\n",
+    )?;
+    append_endpoint(
+        &quartz,
+        &["api", "users"],
+        "\n[[assert]]\ncontains = \"secret\"\n",
+    )?;
+    append_endpoint(
+        &quartz,
+        &["api", "users", "broken"],
+        "\n[[assert]]\nstatus = 200\n",
+    )?;
+
+    // The template is resolved only after login ran
+    quartz.cmd(&["use", "api/users", "-H", "Authorization: {{token}}"])?;
+
+    Ok(quartz)
+}
+
+fn handler(req: &Request) -> Vec<u8> {
+    match req.path.as_str() {
+        "/login" => response(200, &[], r#"{ "token": "secret" }"#),
+        "/broken" => response(500, &[], ""),
+        _ => response(200, &[], req.header("Authorization").unwrap_or_default()),
+    }
+}
+
+#[test]
+fn it_runs_subtree_in_order() -> TestResult {
+    let server = Server::new(handler);
+    let quartz = preset_api(&server).map_err(|err| err.to_string())?;
+
+    let output = quartz.cmd(&["run", "api"])?;
+    assert!(!output.status.success(), "{}", output.stdout);
+
+    let login = output
+        .stdout
+        .find("GET api/login")
+        .ok_or(output.stdout.clone())?;
+    let users = output
+        .stdout
+        .find("GET api/users\n")
+        .ok_or(output.stdout.clone())?;
+    let broken = output
+        .stdout
+        .find("GET api/users/broken")
+        .ok_or(output.stdout.clone())?;
+    assert!(login < users && users < broken, "{}", output.stdout);
+    assert!(!output.stdout.contains("other"), "{}", output.stdout);
+
+    assert!(
+        output.stdout.contains("PASS body contains \"secret\""),
+        "{}",
+        output.stdout
+    );
+    assert!(
+        output.stdout.contains("FAIL status is 200: got 500"),
+        "{}",
+        output.stdout
+    );
+
+    let summary = output
+        .stdout
+        .lines()
+        .find(|line| line.starts_with("api/users/broken"))
+        .unwrap_or_default();
+    assert!(summary.contains("500"), "{}", output.stdout);
+    assert!(summary.ends_with("0/1"), "{}", output.stdout);
+    assert!(
+        output.stdout.contains("2 passed, 1 failed"),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_stops_at_first_failure_with_bail() -> TestResult {
+    let server = Server::new(handler);
+    let quartz = preset_api(&server).map_err(|err| err.to_string())?;
+
+    append_endpoint(&quartz, &["api", "login"], "\n[[assert]]\nstatus = 201\n")?;
+
+    let output = quartz.cmd(&["run", "api", "--bail", "--delay", "10"])?;
+    assert!(!output.status.success(), "{}", output.stdout);
+    assert!(
+        !output.stdout.contains("GET api/users"),
+        "{}",
+        output.stdout
+    );
+    assert!(
+        output.stdout.contains("0 passed, 1 failed"),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_runs_once_per_iteration_row() -> TestResult {
+    let server = Server::new(|req| match req.path.as_str() {
+        "/users/2" => response(404, &[], ""),
+        _ => response(200, &[], ""),
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "users", "--url", &server.url("/users/{{id}}")])?;
+    append_endpoint(&quartz, &["users"], "\n[[assert]]\nstatus = 200\n")?;
+
+    let data = quartz.dir().join("data.json");
+    std::fs::write(&data, r#"[{ "id": 1 }, { "id": 2 }, { "id": 3 }]"#)?;
+
+    let output = quartz.cmd(&["run", "users", "--iteration-data", data.to_str().unwrap()])?;
+    assert!(!output.status.success(), "{}", output.stdout);
+    assert!(output.stdout.contains("Iteration 3"), "{}", output.stdout);
+    assert!(output.stdout.contains("ITERATION"), "{}", output.stdout);
+    assert!(
+        output.stdout.contains("2 passed, 1 failed"),
+        "{}",
+        output.stdout
+    );
+
+    let failed = output
+        .stdout
+        .lines()
+        .find(|line| line.contains(" 404 "))
+        .unwrap_or_default();
+    assert!(failed.starts_with("2 "), "{}", output.stdout);
+
+    let output = quartz.cmd(&["test", "users", "--iteration-data", data.to_str().unwrap()])?;
+    assert!(!output.status.success(), "{}", output.stdout);
+    assert!(
+        output.stdout.contains("2 passed, 1 failed"),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_writes_test_reports() -> TestResult {
+    let server = Server::new(handler);
+    let quartz = preset_api(&server).map_err(|err| err.to_string())?;
+
+    let output = quartz.cmd(&["run", "api", "--report", "junit"])?;
+    assert!(!output.status.success());
+    assert!(
+        output.stdout.starts_with("<?xml version=\"1.0\""),
+        "{}",
+        output.stdout
+    );
+    assert_eq!(output.stdout.matches("<testcase ").count(), 3);
+    assert!(
+        output
+            .stdout
+            .contains("<failure message=\"status is 200: got 500\""),
+        "{}",
+        output.stdout
+    );
+    assert!(!output.stdout.contains("PASS"), "{}", output.stdout);
+
+    let path = quartz.dir().join("report.tap");
+    let output = quartz.cmd(&[
+        "test",
+        "api/users/broken",
+        "--report",
+        "tap",
+        "--report-file",
+        path.to_str().unwrap(),
+    ])?;
+    assert!(!output.status.success());
+    assert!(
+        output.stdout.contains("FAIL status is 200"),
+        "{}",
+        output.stdout
+    );
+
+    let tap = std::fs::read_to_string(&path)?;
+    assert!(
+        tap.starts_with("TAP version 13\n1..1\nnot ok 1 - api/users/broken\n"),
+        "{}",
+        tap
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_skips_websocket_endpoints() -> TestResult {
+    let server = Server::new(|_| response(200, &[], "ok"));
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "api/health", "--url", &server.url("/health")])?;
+    quartz.cmd(&["create", "api/socket", "--url", "ws://localhost:1/socket"])?;
+    append_endpoint(&quartz, &["api", "socket"], "\n[[assert]]\nstatus = 101\n")?;
+
+    let output = quartz.cmd(&["run", "api"])?;
+    assert!(
+        output.status.success(),
+        "{}\n{}",
+        output.stdout,
+        output.stderr
+    );
+    assert!(
+        output
+            .stdout
+            .contains("SKIP websocket endpoints can only be opened by send"),
+        "{}",
+        output.stdout
+    );
+    assert!(
+        output.stdout.contains("1 passed, 0 failed, 1 skipped"),
+        "{}",
+        output.stdout
+    );
+
+    let output = quartz.cmd(&["test", "api/socket", "--report", "tap"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(
+        output
+            .stdout
+            .contains("ok 1 - api/socket # SKIP websocket endpoints can only be opened by send"),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
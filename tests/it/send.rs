@@ -0,0 +1,660 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::utils::*;
+
+#[test]
+fn it_outputs_response_body() -> TestResult {
+    let server = Server::new(|req| response(200, &[], &format!("{} {}", req.method, req.path)));
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "local", "--url", &server.url("/hello"), "--use"])?;
+
+    let output = quartz.cmd(&["send", "-X", "PUT"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(output.stdout, "PUT /hello");
+
+    Ok(())
+}
+
+#[test]
+fn it_fails_after_timeout() -> TestResult {
+    let server = Server::new(|_| {
+        std::thread::sleep(Duration::from_secs(3));
+        response(200, &[], "too late")
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "local", "--url", &server.url("/"), "--use"])?;
+
+    let output = quartz.cmd(&["send", "--timeout", "200"])?;
+    assert!(!output.status.success(), "{}", output.stdout);
+    assert!(output.stderr.contains("timed out"), "{}", output.stderr);
+
+    let output = quartz.cmd(&["last"])?;
+    assert!(
+        output.stdout.contains("attempt 1 failed"),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_retries_on_status() -> TestResult {
+    let count = AtomicUsize::new(0);
+    let server = Server::new(move |_| match count.fetch_add(1, Ordering::SeqCst) {
+        0 => response(503, &[], "unavailable"),
+        _ => response(200, &[], "ok"),
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "local", "--url", &server.url("/"), "--use"])?;
+
+    let output = quartz.cmd(&["send", "--retry", "3", "--retry-on", "503"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(output.stdout, "ok");
+
+    let output = quartz.cmd(&["last", "req"])?;
+    assert_eq!(output.stdout.matches("GET / HTTP/1.1").count(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn it_reads_retry_policy_from_endpoint() -> TestResult {
+    let count = AtomicUsize::new(0);
+    let server = Server::new(move |_| match count.fetch_add(1, Ordering::SeqCst) {
+        0 | 1 => response(502, &[], "bad gateway"),
+        _ => response(200, &[], "ok"),
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "local", "--url", &server.url("/"), "--use"])?;
+
+    let path = quartz
+        .dir()
+        .join("endpoints")
+        .join("local")
+        .join("endpoint.toml");
+    let mut content = std::fs::read_to_string(&path)?;
+    content.push_str("\n[retry]\nattempts = 2\non = [502]\n");
+    std::fs::write(&path, content)?;
+
+    // Policy allows a single retry, so the second 502 is the final response
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(output.stdout, "bad gateway");
+
+    Ok(())
+}
+
+#[test]
+fn it_only_sends_matching_cookies() -> TestResult {
+    let server = Server::new(|req| match req.path.as_str() {
+        "/start" => response(302, &[("Location", "/api/users")], ""),
+        _ => response(200, &[], req.header("Cookie").unwrap_or_default()),
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "local", "--url", &server.url("/start"), "--use"])?;
+
+    std::fs::write(
+        quartz.dir().join("env").join("default").join("cookies"),
+        [
+            "127.0.0.1\tFALSE\t/\tFALSE\t0\troot\t1",
+            "127.0.0.1\tFALSE\t/api\tFALSE\t0\tapi\t2",
+            "127.0.0.1\tFALSE\t/\tTRUE\t0\tsecure\t3",
+            "example.com\tTRUE\t/\tFALSE\t0\tother\t4",
+        ]
+        .join("\n"),
+    )?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(output.stdout, "api=2; root=1");
+
+    let output = quartz.cmd(&["last", "req"])?;
+    assert!(
+        output.stdout.contains("cookie: root=1\n"),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_stores_every_set_cookie() -> TestResult {
+    let server = Server::new(|_| {
+        response(
+            200,
+            &[
+                ("Set-Cookie", "first=1; Path=/; HttpOnly"),
+                ("Set-Cookie", "malformed"),
+                (
+                    "Set-Cookie",
+                    "second=2; Expires=Wed, 01 Jan 3000 00:00:00 GMT",
+                ),
+            ],
+            "",
+        )
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "local", "--url", &server.url("/"), "--use"])?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(output.stderr.contains("malformed"), "{}", output.stderr);
+
+    let output = quartz.cmd(&["show", "cookies", "first"])?;
+    assert_eq!(output.stdout.trim(), "1", "{}", output.stderr);
+
+    let output = quartz.cmd(&["show", "cookies", "second"])?;
+    assert_eq!(output.stdout.trim(), "2", "{}", output.stderr);
+
+    let jar = std::fs::read_to_string(quartz.dir().join("env").join("default").join("cookies"))?;
+    assert!(jar.contains("#HttpOnly_127.0.0.1\t"), "{}", jar);
+
+    Ok(())
+}
+
+#[test]
+fn it_captures_response_values() -> TestResult {
+    let server = Server::new(|req| match req.path.as_str() {
+        "/login" => response(
+            200,
+            &[("Set-Cookie", "session=s1; Path=/")],
+            r#"{ "access_token": "secret", "user": { "id": 7 } }"#,
+        ),
+        "/fail" => response(401, &[], r#"{ "access_token": "wrong" }"#),
+        _ => response(
+            200,
+            &[],
+            &format!(
+                "{} {}",
+                req.header("Authorization").unwrap_or_default(),
+                req.header("Cookie").unwrap_or_default()
+            ),
+        ),
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "login", "--url", &server.url("/login"), "--use"])?;
+    quartz.cmd(&[
+        "create",
+        "me",
+        "--url",
+        &server.url("/users/{{user}}"),
+        "-H",
+        "Authorization: Bearer {{token}}",
+    ])?;
+
+    let path = quartz
+        .dir()
+        .join("endpoints")
+        .join("login")
+        .join("endpoint.toml");
+    let mut content = std::fs::read_to_string(&path)?;
+    content.push_str(
+        r#"
+[[capture]]
+var = "token"
+json = "$.access_token"
+
+[[capture]]
+var = "user"
+json = "$.user.id"
+
+[[capture]]
+var = "session"
+cookie = "session"
+
+[[capture]]
+var = "missing"
+header = "X-Missing"
+"#,
+    );
+    std::fs::write(&path, content)?;
+
+    let output = quartz.cmd(&["send", "-v", "other=1"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(
+        output.stderr.contains("could not capture missing"),
+        "{}",
+        output.stderr
+    );
+
+    let output = quartz.cmd(&["var", "get", "session"])?;
+    assert_eq!(output.stdout.trim(), "s1");
+
+    // Variables passed as arguments are not persisted
+    let output = quartz.cmd(&["var", "get", "other"])?;
+    assert!(!output.status.success());
+
+    let output = quartz.cmd(&["-x", "me", "send"])?;
+    assert_eq!(
+        output.stdout, "Bearer secret session=s1",
+        "{}",
+        output.stderr
+    );
+
+    // Unsuccessful responses are not captured
+    quartz.cmd(&["use", "login", "--url", &server.url("/fail")])?;
+    quartz.cmd(&["send"])?;
+
+    let output = quartz.cmd(&["var", "get", "token"])?;
+    assert_eq!(output.stdout.trim(), "secret");
+
+    Ok(())
+}
+
+#[test]
+fn it_sends_once_per_iteration_row() -> TestResult {
+    let server = Server::new(|req| response(200, &[], &format!("{} {}", req.method, req.path)));
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "users",
+        "--url",
+        &server.url("/users/{{id}}?name={{name}}"),
+        "--use",
+    ])?;
+    quartz.cmd(&["var", "set", "name=default"])?;
+
+    let data = quartz.dir().join("data.csv");
+    std::fs::write(&data, "id,name\n1,first\n2,second\n")?;
+
+    let output = quartz.cmd(&[
+        "send",
+        "--iteration-data",
+        data.to_str().unwrap(),
+        "-v",
+        "name=override",
+    ])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(
+        output.stdout,
+        "GET /users/1?name=override\nGET /users/2?name=override\n"
+    );
+    assert!(output.stderr.contains("iteration 2"), "{}", output.stderr);
+
+    // Each iteration has its own history entry
+    let output = quartz.cmd(&["history"])?;
+    assert!(output.stdout.contains("/users/1"), "{}", output.stdout);
+    assert!(output.stdout.contains("/users/2"), "{}", output.stdout);
+
+    // Iteration values are not saved to the environment
+    let output = quartz.cmd(&["var", "get", "name"])?;
+    assert_eq!(output.stdout.trim(), "default");
+
+    Ok(())
+}
+
+#[test]
+fn it_streams_response_body() -> TestResult {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}/events", listener.local_addr()?);
+    let (resume, paused) = std::sync::mpsc::channel::<()>();
+
+    // Sends the second half of the body only once the first one was read
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buf = [0; 1024];
+        let _ = stream.read(&mut buf);
+
+        let _ = stream.write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 12\r\nConnection: close\r\n\r\nfirst\n",
+        );
+        let _ = paused.recv_timeout(Duration::from_secs(5));
+        let _ = stream.write_all(b"second");
+    });
+
+    let quartz = Quartz::preset_empty_project()?;
+    quartz.cmd(&["create", "events", "--url", &url, "--use"])?;
+
+    let start = std::time::Instant::now();
+    let mut child = quartz
+        .command(&["send"])
+        .stdout(std::process::Stdio::piped())
+        .spawn()?;
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    let mut line = String::new();
+    stdout.read_line(&mut line)?;
+    assert_eq!(line, "first\n");
+    assert!(start.elapsed() < Duration::from_secs(4));
+
+    resume.send(())?;
+    let mut rest = String::new();
+    stdout.read_to_string(&mut rest)?;
+    assert_eq!(rest, "second");
+    assert!(child.wait()?.success());
+
+    Ok(())
+}
+
+#[test]
+fn it_caps_response_body_in_history() -> TestResult {
+    let server = Server::new(|_| response(200, &[], "hello world"));
+    let quartz = Quartz::preset_empty_project()?;
+    let home = quartz.dir().join("..");
+
+    quartz.cmd(&["create", "hello", "--url", &server.url("/"), "--use"])?;
+
+    let output = quartz
+        .command(&["config", "set", "history.max_body_size", "4"])
+        .env("HOME", &home)
+        .output()?;
+    assert!(output.status.success());
+
+    let output = quartz.command(&["send"]).env("HOME", &home).output()?;
+    assert_eq!(output.stdout, b"hello world");
+
+    let output = quartz.cmd(&["last", "res", "body"])?;
+    assert_eq!(output.stdout, "hell\n");
+
+    let output = quartz.cmd(&["last"])?;
+    assert!(
+        output.stdout.contains("* body truncated to 4 of 11 bytes"),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_downloads_response_body_to_file() -> TestResult {
+    let server = Server::new(|req| match req.path.as_str() {
+        "/reports/missing.csv" => response(404, &[], "not found"),
+        _ => response(200, &[], "hello world"),
+    });
+    let quartz = Quartz::preset_empty_project()?;
+    let root = quartz.dir().join("..");
+
+    quartz.cmd(&[
+        "create",
+        "report",
+        "--url",
+        &server.url("/reports/2024.csv"),
+        "--use",
+    ])?;
+
+    let output = quartz.cmd(&["send", "-o", "report.csv"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(output.stdout, "");
+    assert_eq!(
+        std::fs::read_to_string(root.join("report.csv"))?,
+        "hello world"
+    );
+
+    let output = quartz.cmd(&["last"])?;
+    assert!(
+        output.stdout.contains(
+            "* body saved to report.csv (11 bytes, sha256 b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9)"
+        ),
+        "{}",
+        output.stdout
+    );
+    assert!(!output.stdout.contains("hello world"), "{}", output.stdout);
+
+    let output = quartz.cmd(&["send", "--remote-name"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(
+        std::fs::read_to_string(root.join("2024.csv"))?,
+        "hello world"
+    );
+
+    // Error bodies are printed instead
+    let output = quartz.cmd(&["send", "-O", "--url", &server.url("/reports/missing.csv")])?;
+    assert_eq!(output.stdout, "not found");
+    assert!(!root.join("missing.csv").exists());
+
+    let output = quartz.cmd(&["send", "-C", "-"])?;
+    assert!(!output.status.success());
+
+    Ok(())
+}
+
+#[test]
+fn it_resumes_download() -> TestResult {
+    let server = Server::new(|req| match req.header("range") {
+        Some("bytes=6-") => response(206, &[("Content-Range", "bytes 6-10/11")], "world"),
+        Some(range) => response(416, &[], range),
+        None => response(200, &[], "hello world"),
+    });
+    let quartz = Quartz::preset_empty_project()?;
+    let file = quartz.dir().join("..").join("report.csv");
+
+    quartz.cmd(&[
+        "create",
+        "report",
+        "--url",
+        &server.url("/report.csv"),
+        "--use",
+    ])?;
+
+    std::fs::write(&file, "hello ")?;
+    let output = quartz.cmd(&["send", "-o", "report.csv", "--continue-at", "-"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(std::fs::read_to_string(&file)?, "hello world");
+
+    let output = quartz.cmd(&["last"])?;
+    assert!(
+        output.stdout.contains(
+            "* body saved to report.csv (11 bytes, sha256 b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9)"
+        ),
+        "{}",
+        output.stdout
+    );
+
+    // Content past the offset is replaced
+    std::fs::write(&file, "hello there")?;
+    quartz.cmd(&["send", "-o", "report.csv", "-C", "6"])?;
+    assert_eq!(std::fs::read_to_string(&file)?, "hello world");
+
+    // Without a range, the whole file is downloaded again
+    std::fs::write(&file, "")?;
+    quartz.cmd(&["send", "-o", "report.csv", "-C", "-"])?;
+    assert_eq!(std::fs::read_to_string(&file)?, "hello world");
+
+    Ok(())
+}
+
+#[test]
+fn it_streams_server_sent_events() -> TestResult {
+    let server = Server::new(|req| {
+        let stream = [("Content-Type", "text/event-stream")];
+
+        match req.header("last-event-id") {
+            None => response(
+                200,
+                &stream,
+                ": welcome\nretry: 10\nid: 1\ndata: one\n\nevent: update\nid: 2\ndata: {\"n\":2}\ndata: more\n\n",
+            ),
+            Some("2") => response(200, &stream, "id: 3\ndata: three\n\n"),
+            Some(_) => response(204, &[], ""),
+        }
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "events", "--url", &server.url("/events"), "--use"])?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(
+        output.stdout,
+        concat!(
+            "event: message\nid: 1\ndata: one\n\n",
+            "event: update\nid: 2\ndata: {\"n\":2}\ndata: more\n\n",
+            "event: message\nid: 3\ndata: three\n\n",
+        )
+    );
+
+    let output = quartz.cmd(&["last"])?;
+    assert!(
+        output.stdout.contains("* 2 events received"),
+        "{}",
+        output.stdout
+    );
+    assert!(
+        output.stdout.contains("last-event-id: 3"),
+        "{}",
+        output.stdout
+    );
+    assert!(
+        output.stdout.contains("< HTTP/1.1 204"),
+        "{}",
+        output.stdout
+    );
+    assert!(
+        output
+            .stdout
+            .ends_with("event: update\nid: 2\ndata: {\"n\":2}\ndata: more\n\nevent: message\nid: 3\ndata: three\n"),
+        "{}",
+        output.stdout
+    );
+
+    let output = quartz.cmd(&["send", "--event-format", "json"])?;
+    assert_eq!(
+        output.stdout,
+        concat!(
+            "{\"event\":\"message\",\"id\":\"1\",\"data\":\"one\"}\n",
+            "{\"event\":\"update\",\"id\":\"2\",\"data\":\"{\\\"n\\\":2}\\nmore\"}\n",
+            "{\"event\":\"message\",\"id\":\"3\",\"data\":\"three\"}\n",
+        )
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_reconnects_to_event_streams_from_a_clean_state() -> TestResult {
+    let server = Server::new(|req| {
+        let stream = [("Content-Type", "text/event-stream")];
+
+        match req.header("last-event-id") {
+            // The second event is cut off by the disconnection
+            None => response(200, &stream, "retry: 10\nid: 1\ndata: one\n\ndata: cut\n"),
+            Some("1") => response(
+                200,
+                &[
+                    ("Content-Type", "text/event-stream"),
+                    ("Set-Cookie", "session=abc"),
+                ],
+                "id: 2\ndata: two\n\n",
+            ),
+            Some(_) => response(204, &[], ""),
+        }
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "events", "--url", &server.url("/events"), "--use"])?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(
+        output.stdout,
+        "event: message\nid: 1\ndata: one\n\nevent: message\nid: 2\ndata: two\n\n"
+    );
+
+    let output = quartz.cmd(&["cookie", "ls"])?;
+    assert!(output.stdout.contains("session"), "{}", output.stdout);
+
+    Ok(())
+}
+
+#[test]
+fn it_records_events_when_interrupted() -> TestResult {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}/events", listener.local_addr()?);
+
+    // Keeps the stream open after the first event
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buf = [0; 1024];
+        let _ = stream.read(&mut buf);
+
+        let _ = stream.write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\ndata: hello\n\n",
+        );
+        std::thread::sleep(Duration::from_secs(10));
+    });
+
+    let quartz = Quartz::preset_empty_project()?;
+    quartz.cmd(&["create", "events", "--url", &url, "--use"])?;
+
+    let mut child = quartz
+        .command(&["send"])
+        .stdout(std::process::Stdio::piped())
+        .spawn()?;
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    let mut line = String::new();
+    stdout.read_line(&mut line)?;
+    assert_eq!(line, "event: message\n");
+
+    std::process::Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()?;
+    assert!(child.wait()?.success());
+
+    let output = quartz.cmd(&["last"])?;
+    assert!(
+        output
+            .stdout
+            .ends_with("* 1 event received\n\nevent: message\ndata: hello\n"),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_fails_on_interrupted_download() -> TestResult {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}/report.csv", listener.local_addr()?);
+
+    // Drops every connection before the announced length is sent
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf);
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\nhello world");
+        }
+    });
+
+    let quartz = Quartz::preset_empty_project()?;
+    let file = quartz.dir().join("..").join("report.csv");
+    quartz.cmd(&["create", "report", "--url", &url, "--use"])?;
+
+    let output = quartz.cmd(&["send", "-o", "report.csv"])?;
+    assert!(!output.status.success());
+    assert!(output.stderr.contains("error"), "{}", output.stderr);
+
+    // Kept to be resumed later
+    assert_eq!(std::fs::read_to_string(&file)?, "hello world");
+
+    let output = quartz.cmd(&["last"])?;
+    assert!(!output.stdout.contains("body saved"), "{}", output.stdout);
+    assert!(
+        output
+            .stdout
+            .trim_end()
+            .ends_with("end of file before message length reached"),
+        "{}",
+        output.stdout
+    );
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(!output.status.success());
+    assert_eq!(output.stdout, "hello world");
+
+    Ok(())
+}
@@ -0,0 +1,214 @@
+use crate::utils::*;
+
+#[test]
+fn it_generates_snippets_with_env_headers_and_cookies() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "users",
+        "--url",
+        "https://example.com/users",
+        "-X",
+        "POST",
+        "-d",
+        "name=rex",
+        "--use",
+    ])?;
+    quartz.cmd(&["env", "header", "set", "X-Env: staging"])?;
+    quartz.cmd(&["cookie", "set", "session=abc", "--domain", "example.com"])?;
+    quartz.cmd(&["cookie", "set", "session=xyz", "--domain", "other.com"])?;
+
+    let output = quartz.cmd(&["show", "snippet", "python"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(
+        output.stdout.contains("\"X-Env\": \"staging\","),
+        "{}",
+        output.stdout
+    );
+    assert!(output.stdout.contains("\"Cookie\": \"session=abc\","));
+    assert!(output.stdout.contains("data=\"name=rex\","));
+
+    let output = quartz.cmd(&["show", "snippet", "wget"])?;
+    assert!(
+        output.stdout.starts_with(
+            "wget --quiet --output-document - --method POST --header 'Cookie: session=abc' --header 'X-Env: staging' --header 'user-agent: quartz/"
+        ),
+        "{}",
+        output.stdout
+    );
+    assert!(output
+        .stdout
+        .ends_with(" --body-data 'name=rex' 'https://example.com/users'\n"));
+
+    for target in ["curl", "http", "fetch", "go", "reqwest", "httpie"] {
+        let output = quartz.cmd(&["show", "snippet", target])?;
+        assert!(output.status.success(), "{}", output.stderr);
+        assert!(output.stdout.contains("session=abc"), "{}", output.stdout);
+        assert!(output.stdout.contains("staging"), "{}", output.stdout);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn it_generates_snippets_from_templates() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "users",
+        "--url",
+        "https://example.com/users",
+        "-X",
+        "POST",
+        "-q",
+        "page=2",
+        "-d",
+        "{\"name\":\"Rex\"}",
+        "--use",
+    ])?;
+    quartz.cmd(&["cookie", "set", "session=abc", "--domain", "example.com"])?;
+
+    let dir = quartz.dir().join("snippets");
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(
+        dir.join("sdk.tmpl"),
+        concat!(
+            "api.{{method | lower}}({{base_url | json}}, {\n",
+            "{{#query}}\n",
+            "  {{name}}: {{value | json}},\n",
+            "{{/query}}\n",
+            "}, {{body}}{{^body}}null{{/body}})\n",
+            "{{#cookies}}\n",
+            "// cookie {{name}}={{value}}\n",
+            "{{/cookies}}\n",
+        ),
+    )?;
+
+    let output = quartz.cmd(&["show", "snippet", "sdk"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(
+        output.stdout,
+        "api.post(\"https://example.com/users\", {\n  page: \"2\",\n}, {\"name\":\"Rex\"})\n// cookie session=abc\n"
+    );
+
+    let output = quartz.cmd(&["show", "snippet", "missing"])?;
+    assert!(!output.status.success());
+    assert!(
+        output.stderr.contains("no such snippet: missing"),
+        "{}",
+        output.stderr
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_generates_snippets_matching_sent_request() -> TestResult {
+    // Echoes request headers, except for those set by the connection
+    let server = Server::new(|req| {
+        let mut headers: Vec<String> = req
+            .headers
+            .iter()
+            .filter(|(k, _)| !["host", "content-length"].contains(&k.to_lowercase().as_str()))
+            .map(|(k, v)| format!("{}: {v}", k.to_lowercase()))
+            .collect();
+        headers.sort();
+
+        response(200, &[], &headers.join("\n"))
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "users", "--url", &server.url("/users"), "--use"])?;
+    quartz.cmd(&["env", "header", "set", "X-Env: staging"])?;
+    quartz.cmd(&["cookie", "set", "session=abc", "--domain", "127.0.0.1"])?;
+
+    let sent = quartz.cmd(&["send", "--json", "{\"name\":\"it's\"}"])?;
+    assert!(sent.status.success(), "{}", sent.stderr);
+    assert!(
+        sent.stdout.contains("user-agent: quartz/"),
+        "{}",
+        sent.stdout
+    );
+
+    let output = quartz.cmd(&["show", "snippet", "--json", "{\"name\":\"it's\"}", "http"])?;
+    let mut headers: Vec<String> = output
+        .stdout
+        .lines()
+        .skip(2)
+        .take_while(|line| !line.is_empty())
+        .map(|line| {
+            let (k, v) = line.split_once(": ").unwrap();
+            format!("{}: {v}", k.to_lowercase())
+        })
+        .collect();
+    headers.sort();
+    assert_eq!(headers.join("\n"), sent.stdout);
+
+    let output = quartz.cmd(&["show", "snippet", "--json", "{\"name\":\"it's\"}", "curl"])?;
+    assert!(
+        output
+            .stdout
+            .ends_with(" --data-raw '{\"name\":\"it'\\''s\"}'\n"),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_generates_snippets_with_forms() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "pets",
+        "--url",
+        "https://example.com/pets",
+        "-X",
+        "POST",
+        "-F",
+        "name=Rex",
+        "-F",
+        "photo=@rex.png",
+        "-F",
+        "note=a;b",
+        "--use",
+    ])?;
+
+    let output = quartz.cmd(&["show", "snippet", "curl"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(!output.stdout.contains("boundary"), "{}", output.stdout);
+    assert!(
+        output.stdout.ends_with(
+            " -F 'name=Rex' -F 'photo=@rex.png;type=image/png' --form-string 'note=a;b'\n"
+        ),
+        "{}",
+        output.stdout
+    );
+
+    let output = quartz.cmd(&["show", "snippet", "python"])?;
+    assert!(
+        output
+            .stdout
+            .contains("(\"photo\", (\"rex.png\", open(\"rex.png\", \"rb\"), \"image/png\")),"),
+        "{}",
+        output.stdout
+    );
+
+    let output = quartz.cmd(&["show", "snippet", "--urlencoded", "q=rex dog", "curl"])?;
+    assert!(
+        output.stdout.contains("application/x-www-form-urlencoded"),
+        "{}",
+        output.stdout
+    );
+    assert!(
+        output.stdout.ends_with(" --data-raw 'q=rex+dog'\n"),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
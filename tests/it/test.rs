@@ -0,0 +1,176 @@
+use crate::utils::*;
+
+fn append_endpoint(quartz: &Quartz, handle: &str, content: &str) -> TestResult {
+    let path = quartz
+        .dir()
+        .join("endpoints")
+        .join(handle)
+        .join("endpoint.toml");
+
+    let mut endpoint = std::fs::read_to_string(&path)?;
+    endpoint.push_str(content);
+    std::fs::write(&path, endpoint)?;
+
+    Ok(())
+}
+
+#[test]
+fn it_passes_assertions() -> TestResult {
+    let server = Server::new(|_| {
+        response(
+            200,
+            &[("Content-Type", "application/json")],
+            r#"{ "data": [{ "id": 7, "name": "quartz" }] }"#,
+        )
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "users", "--url", &server.url("/users")])?;
+    append_endpoint(
+        &quartz,
+        "users",
+        r#"
+[[assert]]
+status = 200
+
+[[assert]]
+header = "content-type"
+matches = "json"
+
+[[assert]]
+json = "$.data[0].id"
+equals = 7
+
+[[assert]]
+contains = "quartz"
+
+[[assert]]
+max_time = 5000
+"#,
+    )?;
+
+    let output = quartz.cmd(&["test", "users"])?;
+    assert!(
+        output.status.success(),
+        "{}\n{}",
+        output.stdout,
+        output.stderr
+    );
+    assert_eq!(
+        output.stdout.matches("PASS").count(),
+        5,
+        "{}",
+        output.stdout
+    );
+    assert!(
+        output.stdout.contains("5 passed, 0 failed"),
+        "{}",
+        output.stdout
+    );
+
+    // Assertions are kept when the endpoint is modified
+    quartz.cmd(&["use", "users", "-H", "Accept: application/json"])?;
+    let output = quartz.cmd(&["test"])?;
+    assert!(
+        output.status.success(),
+        "{}\n{}",
+        output.stdout,
+        output.stderr
+    );
+    assert!(
+        output.stdout.contains("5 passed, 0 failed"),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_fails_assertions() -> TestResult {
+    let server = Server::new(|_| response(404, &[], r#"{ "error": "not found" }"#));
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "users", "--url", &server.url("/users"), "--use"])?;
+    append_endpoint(
+        &quartz,
+        "users",
+        r#"
+[[assert]]
+status = 200
+
+[[assert]]
+json = "$.error"
+equals = "not found"
+"#,
+    )?;
+
+    let output = quartz.cmd(&["test"])?;
+    assert!(!output.status.success());
+    assert!(
+        output.stdout.contains("FAIL status is 200: got 404"),
+        "{}",
+        output.stdout
+    );
+    assert!(
+        output.stdout.contains("1 passed, 1 failed"),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_reports_errors_as_failed_cases() -> TestResult {
+    // Nothing listens on this port once the listener is dropped
+    let url = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        format!("http://{}/users", listener.local_addr()?)
+    };
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "users", "--url", &url, "--use"])?;
+    append_endpoint(&quartz, "users", "\n[[assert]]\nstatus = 200\n")?;
+
+    let output = quartz.cmd(&["test"])?;
+    assert!(!output.status.success());
+    assert!(output.stdout.contains("ERROR"), "{}", output.stdout);
+    assert!(
+        output.stdout.contains("0 passed, 1 failed"),
+        "{}",
+        output.stdout
+    );
+    assert!(!output.stderr.contains("panicked"), "{}", output.stderr);
+
+    Ok(())
+}
+
+#[test]
+fn it_rejects_output_options() -> TestResult {
+    let server = Server::new(|_| response(200, &[], "ok"));
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "users", "--url", &server.url("/users"), "--use"])?;
+    append_endpoint(&quartz, "users", "\n[[assert]]\ncontains = \"ok\"\n")?;
+
+    // Assertions need the body, which would be written to the file instead
+    for args in [
+        ["test", "-o", "users.json"],
+        ["run", "users", "--event-format"],
+    ] {
+        let output = quartz.cmd(&args)?;
+        assert!(!output.status.success(), "{args:?}");
+        assert!(
+            output.stderr.contains("unexpected argument"),
+            "{}",
+            output.stderr
+        );
+    }
+    assert!(!quartz.dir().join("../users.json").exists());
+
+    let output = quartz.cmd(&["test", "--timeout", "5000"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    Ok(())
+}
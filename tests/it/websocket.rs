@@ -0,0 +1,220 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+
+use crate::utils::*;
+
+/// Reads a masked frame sent by a client, returning its opcode and payload.
+fn read_frame(stream: &mut TcpStream) -> std::io::Result<(u8, Vec<u8>)> {
+    let mut head = [0; 2];
+    stream.read_exact(&mut head)?;
+
+    let len = match head[1] & 0x7F {
+        126 => {
+            let mut len = [0; 2];
+            stream.read_exact(&mut len)?;
+            u16::from_be_bytes(len) as usize
+        }
+        len => len as usize,
+    };
+
+    let mut mask = [0; 4];
+    stream.read_exact(&mut mask)?;
+
+    let mut payload = vec![0; len];
+    stream.read_exact(&mut payload)?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+
+    Ok((head[0] & 0x0F, payload))
+}
+
+fn write_frame(stream: &mut TcpStream, opcode: u8, payload: &[u8]) -> std::io::Result<()> {
+    stream.write_all(&[0x80 | opcode, payload.len() as u8])?;
+    stream.write_all(payload)
+}
+
+/// Spawns a WebSocket server that echoes text messages until the client closes the connection.
+/// Headers of the handshake are sent through the returned channel.
+fn echo_server() -> (String, mpsc::Receiver<Vec<(String, String)>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}/socket", listener.local_addr().unwrap());
+    let (handshake, headers) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line.trim_end().is_empty() {
+                break;
+            }
+
+            if let Some((key, value)) = line.split_once(':') {
+                lines.push((key.trim().to_lowercase(), value.trim().to_string()));
+            }
+        }
+
+        let key = lines
+            .iter()
+            .find(|(key, _)| key == "sec-websocket-key")
+            .map(|(_, value)| value.clone())
+            .unwrap();
+        handshake.send(lines).unwrap();
+
+        let _ = stream.write_all(
+            format!(
+                "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                quartz_cli::websocket::accept(&key)
+            )
+            .as_bytes(),
+        );
+
+        while let Ok((opcode, payload)) = read_frame(&mut stream) {
+            if opcode == 0x8 {
+                let _ = write_frame(&mut stream, 0x8, &payload);
+                break;
+            }
+
+            let mut echo = b"echo: ".to_vec();
+            echo.extend(payload);
+            let _ = write_frame(&mut stream, opcode, &echo);
+        }
+    });
+
+    (url, headers)
+}
+
+#[test]
+fn it_exchanges_websocket_messages() -> TestResult {
+    let (url, handshake) = echo_server();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "socket", "--url", &url, "-d", "hello", "--use"])?;
+    quartz.cmd(&["env", "header", "set", "X-Env: staging"])?;
+    quartz.cmd(&["cookie", "set", "session=abc", "--domain", "127.0.0.1"])?;
+
+    let output = quartz.cmd_stdin(&["send"], "second\n")?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let messages: Vec<&str> = output
+        .stdout
+        .lines()
+        .map(|line| {
+            let (time, message) = line.split_once(' ').unwrap();
+            assert_eq!(time.len(), "00:00:00.000".len(), "{line}");
+
+            message
+        })
+        .collect();
+    assert_eq!(messages, ["< echo: hello", "< echo: second"]);
+
+    let headers = handshake.recv()?;
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+    assert_eq!(header("upgrade"), Some("websocket"));
+    assert_eq!(header("sec-websocket-version"), Some("13"));
+    assert_eq!(header("x-env"), Some("staging"));
+    assert_eq!(header("cookie"), Some("session=abc"));
+
+    let output = quartz.cmd(&["last"])?;
+    let transcript: Vec<&str> = output
+        .stdout
+        .lines()
+        .filter_map(|line| line.split_once(' ').map(|(_, message)| message))
+        .collect();
+
+    // Messages are sent and received independently, so only their own order is kept
+    let sent: Vec<&&str> = transcript.iter().filter(|m| m.starts_with("> ")).collect();
+    let received: Vec<&&str> = transcript
+        .iter()
+        .filter(|m| m.starts_with("< echo"))
+        .collect();
+    assert_eq!(sent, [&"> hello", &"> second"]);
+    assert_eq!(received, [&"< echo: hello", &"< echo: second"]);
+    assert!(
+        output.stdout.ends_with("* connection closed: 1000\n"),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_fails_on_refused_websocket_upgrade() -> TestResult {
+    let server = Server::new(|_| response(200, &[], "not a socket"));
+    let quartz = Quartz::preset_empty_project()?;
+
+    let url = server.url("/socket").replace("http://", "ws://");
+    quartz.cmd(&["create", "socket", "--url", &url, "--use"])?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(!output.status.success());
+    assert!(
+        output.stderr.contains("websocket handshake failed: 200 OK"),
+        "{}",
+        output.stderr
+    );
+
+    // Endpoints can also be marked as WebSocket ones in their file
+    quartz.cmd(&["create", "marked", "--url", &server.url("/socket"), "--use"])?;
+    let path = quartz
+        .dir()
+        .join("endpoints")
+        .join("marked")
+        .join("endpoint.toml");
+    let content = std::fs::read_to_string(&path)?;
+    std::fs::write(&path, format!("kind = \"websocket\"\n{content}"))?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(
+        output.stderr.contains("websocket handshake failed: 200 OK"),
+        "{}",
+        output.stderr
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_drops_websocket_messages_over_the_limit() -> TestResult {
+    let (url, _handshake) = echo_server();
+    let quartz = Quartz::preset_empty_project()?;
+    let home = quartz.dir().join("..");
+
+    quartz.cmd(&["create", "socket", "--url", &url, "-d", "hello", "--use"])?;
+
+    let output = quartz
+        .command(&["config", "set", "history.max_body_size", "8"])
+        .env("HOME", &home)
+        .output()?;
+    assert!(output.status.success());
+
+    let output = quartz
+        .command(&["send"])
+        .env("HOME", &home)
+        .stdin(std::process::Stdio::null())
+        .output()?;
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let output = quartz.cmd(&["last"])?;
+    assert!(
+        output
+            .stdout
+            .contains("* connection lost: message of 11 bytes exceeds the limit of 8"),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
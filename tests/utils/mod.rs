@@ -1,3 +1,7 @@
+mod server;
+
+pub use server::*;
+
 use cuid::cuid2;
 use std::default::Default;
 use std::ffi::OsStr;
@@ -17,6 +21,8 @@ pub struct Quartz {
 
 pub struct QuartzOutput {
     pub stdout: String,
+    /// Standard output as it was written, which may not be valid UTF-8.
+    pub stdout_bytes: Vec<u8>,
     pub stderr: String,
     pub status: ExitStatus,
 }
@@ -146,6 +152,7 @@ impl Quartz {
         Ok(QuartzOutput {
             stdout: String::from_utf8_lossy(&output.stdout).into(),
             stderr: String::from_utf8_lossy(&output.stderr).into(),
+            stdout_bytes: output.stdout,
             status: output.status,
         })
     }
@@ -157,8 +164,10 @@ impl Quartz {
         let mut child = Command::new(self.bin.as_path())
             .current_dir(self.tmpdir.as_path())
             .args(args)
+            .env("NO_COLOR", "1")
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
             .spawn()?;
 
         let child_stdin = child.stdin.as_mut().unwrap();
@@ -169,10 +178,25 @@ impl Quartz {
         Ok(QuartzOutput {
             stdout: String::from_utf8_lossy(&output.stdout).into(),
             stderr: String::from_utf8_lossy(&output.stderr).into(),
+            stdout_bytes: output.stdout,
             status: output.status,
         })
     }
 
+    /// Command that runs quartz in this project, to be spawned or given a custom environment.
+    pub fn command<S>(&self, args: &[S]) -> Command
+    where
+        S: AsRef<OsStr>,
+    {
+        let mut command = Command::new(self.bin.as_path());
+        command
+            .current_dir(self.tmpdir.as_path())
+            .args(args)
+            .env("NO_COLOR", "1");
+
+        command
+    }
+
     pub fn dir(&self) -> PathBuf {
         self.tmpdir.join(".quartz")
     }
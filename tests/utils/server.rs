@@ -0,0 +1,115 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+
+/// Request received by a [`Server`].
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, key: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Local HTTP server to test requests without relying on external services.
+pub struct Server {
+    addr: SocketAddr,
+}
+
+impl Server {
+    /// Spawns a server that answers every request with the raw HTTP message returned by `handler`.
+    ///
+    /// Each connection is handled in its own thread, so a slow handler does not block others.
+    pub fn new<F>(handler: F) -> Self
+    where
+        F: Fn(&Request) -> Vec<u8> + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handler = Arc::new(handler);
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let handler = handler.clone();
+
+                std::thread::spawn(move || {
+                    if let Some(req) = read_request(&stream) {
+                        let mut stream = stream;
+                        let _ = stream.write_all(&handler(&req));
+                    }
+                });
+            }
+        });
+
+        Self { addr }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+}
+
+/// Builds a raw HTTP response message.
+pub fn response(status: u16, headers: &[(&str, &str)], body: &str) -> Vec<u8> {
+    let mut res = format!("HTTP/1.1 {status} Status\r\n");
+
+    for (key, value) in headers {
+        res.push_str(&format!("{key}: {value}\r\n"));
+    }
+
+    res.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    res.push_str("Connection: close\r\n\r\n");
+    res.push_str(body);
+
+    res.into_bytes()
+}
+
+fn read_request(stream: &TcpStream) -> Option<Request> {
+    let mut reader = BufReader::new(stream);
+
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some((key, value)) = line.split_once(':') {
+            headers.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let mut req = Request {
+        method,
+        path,
+        headers,
+        body: Vec::new(),
+    };
+
+    let length = req
+        .header("content-length")
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(0);
+
+    req.body.resize(length, 0);
+    reader.read_exact(&mut req.body).ok()?;
+
+    Some(req)
+}